@@ -0,0 +1,218 @@
+use actix_web::{
+    Error, HttpMessage,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{Method, header},
+    middleware::Next,
+    web::{Data, Query},
+};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    types::{TokenScope, Tokens},
+};
+
+/// Routes answered without a token. The docs only describe the API, they
+/// expose no journal data.
+fn is_public(path: &str) -> bool {
+    path == "/health" || path == "/openapi.json" || path.starts_with("/docs/")
+}
+
+/// Calendar apps subscribe to a plain URL and can't send headers, so the
+/// feed also takes its token from a `token` query parameter.
+const CALENDAR_FEED: &str = "/calendar.ics";
+
+fn bearer_token(req: &ServiceRequest) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+#[derive(Deserialize)]
+struct FeedToken {
+    token: Option<String>,
+}
+
+fn query_token(req: &ServiceRequest) -> Option<String> {
+    if req.path() != CALENDAR_FEED {
+        return None;
+    }
+    Query::<FeedToken>::from_query(req.query_string())
+        .ok()?
+        .into_inner()
+        .token
+}
+
+fn check_scope(scope: TokenScope, method: &Method, path: &str) -> Result<(), AppError> {
+    let read_only = matches!(*method, Method::GET | Method::HEAD);
+    let manages_tokens = path.starts_with("/tokens") && path != "/tokens/current";
+    match scope {
+        TokenScope::ReadWrite => Ok(()),
+        TokenScope::Read if read_only && !manages_tokens => Ok(()),
+        TokenScope::Read => Err(AppError::Forbidden("This token is read-only".to_string())),
+    }
+}
+
+/// Rejects requests without a valid `Authorization: Bearer` token, and the
+/// ones the token's scope doesn't cover. The token is stored in the request
+/// extensions for handlers to inspect.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    if is_public(req.path()) || req.method() == Method::OPTIONS {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+
+    let Some(tokens) = req.app_data::<Data<Tokens>>().cloned() else {
+        return Ok(req.error_response(AppError::Unauthorized(
+            "No tokens are configured".to_string(),
+        )));
+    };
+    let secret = bearer_token(&req)
+        .map(str::to_string)
+        .or_else(|| query_token(&req));
+    let token = match secret {
+        Some(secret) => tokens.authenticate(&secret).await,
+        None => {
+            return Ok(
+                req.error_response(AppError::Unauthorized("Missing bearer token".to_string()))
+            );
+        }
+    };
+    let Some(token) = token else {
+        return Ok(req.error_response(AppError::Unauthorized(
+            "Invalid or revoked token".to_string(),
+        )));
+    };
+    if let Err(err) = check_scope(token.scope, req.method(), req.path()) {
+        return Ok(req.error_response(err));
+    }
+
+    req.extensions_mut().insert(token);
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        App, HttpResponse,
+        http::StatusCode,
+        middleware::from_fn,
+        test::{TestRequest, call_service, init_service},
+        web,
+    };
+
+    use super::*;
+    use crate::types::{TokenRequest, test_vault};
+
+    async fn token(tokens: &Tokens, scope: TokenScope) -> (String, String) {
+        let request = TokenRequest {
+            name: format!("{:?}", scope),
+            scope,
+        };
+        let created = tokens.create(request).await.unwrap();
+        (created.token.id, created.secret)
+    }
+
+    #[test]
+    fn read_tokens_only_read() {
+        for (method, path) in [
+            (Method::GET, "/entries"),
+            (Method::HEAD, "/entries"),
+            (Method::GET, "/tokens/current"),
+        ] {
+            assert!(check_scope(TokenScope::Read, &method, path).is_ok());
+        }
+        for (method, path) in [
+            (Method::POST, "/entries"),
+            (Method::PUT, "/entry/1"),
+            (Method::DELETE, "/entry/1"),
+            (Method::PATCH, "/settings"),
+            (Method::GET, "/tokens"),
+            (Method::DELETE, "/tokens/1"),
+        ] {
+            assert!(matches!(
+                check_scope(TokenScope::Read, &method, path),
+                Err(AppError::Forbidden(_))
+            ));
+            assert!(check_scope(TokenScope::ReadWrite, &method, path).is_ok());
+        }
+    }
+
+    #[actix_web::test]
+    async fn checks_tokens_before_the_handlers() {
+        let (_dir, config) = test_vault(|_| {});
+        let tokens = Tokens::load(&config).unwrap();
+        let (_, read) = token(&tokens, TokenScope::Read).await;
+        let (write_id, write) = token(&tokens, TokenScope::ReadWrite).await;
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(authenticate))
+                .app_data(Data::new(tokens.clone()))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let status = |request: TestRequest, secret: Option<&str>| {
+            let request = match secret {
+                Some(secret) => {
+                    request.insert_header((header::AUTHORIZATION, format!("Bearer {}", secret)))
+                }
+                None => request,
+            };
+            let app = &app;
+            async move { call_service(app, request.to_request()).await.status() }
+        };
+
+        for path in ["/health", "/openapi.json", "/docs/", "/docs/index.html"] {
+            assert_eq!(
+                status(TestRequest::get().uri(path), None).await,
+                StatusCode::OK
+            );
+        }
+        for secret in [None, Some("tj_unknown"), Some("")] {
+            let request = TestRequest::get().uri("/entries");
+            assert_eq!(status(request, secret).await, StatusCode::UNAUTHORIZED);
+        }
+
+        assert_eq!(
+            status(TestRequest::get().uri("/entries"), Some(&read)).await,
+            StatusCode::OK
+        );
+        for request in [
+            TestRequest::post().uri("/entries"),
+            TestRequest::put().uri("/entry/1"),
+            TestRequest::delete().uri("/entry/1"),
+            TestRequest::get().uri("/tokens"),
+            TestRequest::post().uri("/tokens"),
+        ] {
+            assert_eq!(status(request, Some(&read)).await, StatusCode::FORBIDDEN);
+        }
+        assert_eq!(
+            status(TestRequest::post().uri("/entries"), Some(&write)).await,
+            StatusCode::OK
+        );
+
+        let feed = format!("/calendar.ics?token={}", read);
+        assert_eq!(
+            status(TestRequest::get().uri(&feed), None).await,
+            StatusCode::OK
+        );
+        let entries = format!("/entries?token={}", read);
+        assert_eq!(
+            status(TestRequest::get().uri(&entries), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        tokens.revoke(&write_id).await.unwrap();
+        assert_eq!(
+            status(TestRequest::get().uri("/entries"), Some(&write)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
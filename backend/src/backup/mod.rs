@@ -0,0 +1,414 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Cursor, Read, Write},
+};
+
+use aes_gcm::{
+    AeadCore, Aes256Gcm, KeyInit, Nonce,
+    aead::{Aead, OsRng, rand_core::RngCore},
+};
+use argon2::Argon2;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::{
+    CompressionMethod, ZipArchive, ZipWriter,
+    write::{FileOptions, SimpleFileOptions},
+};
+
+use crate::{
+    error::AppError,
+    types::{MIN_PASSPHRASE_LENGTH, Snapshot},
+};
+
+mod schedule;
+
+pub use schedule::*;
+
+/// Start of every backup file, followed by the format version.
+const MAGIC: &[u8; 8] = b"TJBACKUP";
+const VERSION: u8 = 1;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + SALT_LENGTH + NONCE_LENGTH;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const CONFIG_FILE: &str = "config.toml";
+const ENTRIES_DIR: &str = "entries/";
+const DRAFTS_DIR: &str = "drafts/";
+const TEMPLATES_DIR: &str = "templates/";
+const BLOBS_DIR: &str = "blobs/";
+
+pub const BACKUP_EXTENSION: &str = "tjbackup";
+pub const BACKUP_PREFIX: &str = "journal-backup-";
+
+/// Lists every other file of the archive, so a restore can tell it is
+/// complete and undamaged.
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u8,
+    created_at: String,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+/// The name of a backup made at `time`, e.g. `journal-backup-20240305-093000.tjbackup`.
+pub fn backup_file_name(time: DateTime<Local>) -> String {
+    format!(
+        "{}{}.{}",
+        BACKUP_PREFIX,
+        time.format("%Y%m%d-%H%M%S"),
+        BACKUP_EXTENSION
+    )
+}
+
+pub fn check_passphrase(passphrase: &str) -> Result<(), AppError> {
+    match passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        true => Err(AppError::BadRequest(format!(
+            "The passphrase must be at least {} characters long",
+            MIN_PASSPHRASE_LENGTH
+        ))),
+        false => Ok(()),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], AppError> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| AppError::Crypto(err.to_string()))?;
+    Ok(key)
+}
+
+fn sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// The files of the archive, by path.
+fn archive_files(snapshot: &Snapshot) -> Result<BTreeMap<String, Vec<u8>>, AppError> {
+    let mut files = BTreeMap::new();
+    for journal in &snapshot.entries {
+        let path = format!("{}{}.json", ENTRIES_DIR, journal.id);
+        files.insert(path, serde_json::to_vec_pretty(journal)?);
+    }
+    for draft in &snapshot.drafts {
+        let path = format!("{}{}.json", DRAFTS_DIR, draft.id);
+        files.insert(path, serde_json::to_vec_pretty(draft)?);
+    }
+    for template in &snapshot.templates {
+        let path = format!("{}{}.json", TEMPLATES_DIR, template.id);
+        files.insert(path, serde_json::to_vec_pretty(template)?);
+    }
+    for (address, data) in &snapshot.blobs {
+        files.insert(format!("{}{}", BLOBS_DIR, address), data.clone());
+    }
+    files.insert(
+        CONFIG_FILE.to_string(),
+        snapshot.config.clone().into_bytes(),
+    );
+    Ok(files)
+}
+
+/// `snapshot` as a zip archive with a manifest, encrypted with a key derived
+/// from `passphrase`.
+pub fn write_backup(snapshot: &Snapshot, passphrase: &str) -> Result<Vec<u8>, AppError> {
+    check_passphrase(passphrase)?;
+    let files = archive_files(snapshot)?;
+    let manifest = Manifest {
+        version: VERSION,
+        created_at: snapshot.created_at.clone(),
+        files: files
+            .iter()
+            .map(|(path, data)| ManifestFile {
+                path: path.clone(),
+                size: data.len() as u64,
+                sha256: sha256(data),
+            })
+            .collect(),
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options: SimpleFileOptions =
+        FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(MANIFEST_FILE, options)
+        .map_err(io::Error::from)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+    for (path, data) in &files {
+        zip.start_file(path.as_str(), options)
+            .map_err(io::Error::from)?;
+        zip.write_all(data)?;
+    }
+    let archive = zip.finish().map_err(io::Error::from)?.into_inner();
+    seal(&archive, passphrase)
+}
+
+/// The zip `archive` encrypted with a key derived from `passphrase`, after
+/// the header.
+fn seal(archive: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+    let mut salt = [0; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new_from_slice(&key)
+        .unwrap()
+        .encrypt(&nonce, archive)?;
+
+    let mut sealed = Vec::with_capacity(HEADER_LENGTH + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(VERSION);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+fn invalid(message: impl Into<String>) -> AppError {
+    AppError::BadRequest(message.into())
+}
+
+/// The zip archive inside the backup `data`.
+fn decrypt_backup(data: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+    if data.len() < HEADER_LENGTH || !data.starts_with(MAGIC) {
+        return Err(invalid("This is not a backup file"));
+    }
+    let version = data[MAGIC.len()];
+    if version != VERSION {
+        return Err(invalid(format!(
+            "Backups of version {} are not supported",
+            version
+        )));
+    }
+    let (salt, rest) = data[MAGIC.len() + 1..].split_at(SALT_LENGTH);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+    let key = derive_key(passphrase, salt)?;
+    Aes256Gcm::new_from_slice(&key)
+        .unwrap()
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| invalid("Wrong passphrase, or the backup is damaged"))
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(path: &str, data: &[u8]) -> Result<T, AppError> {
+    serde_json::from_slice(data).map_err(|err| invalid(format!("{} in the backup: {}", path, err)))
+}
+
+/// Decrypts the backup `data` and checks every file against the manifest.
+pub fn read_backup(data: &[u8], passphrase: &str) -> Result<Snapshot, AppError> {
+    let archive = decrypt_backup(data, passphrase)?;
+    let damaged = |err: zip::result::ZipError| invalid(format!("The backup is damaged: {}", err));
+    let mut zip = ZipArchive::new(Cursor::new(archive)).map_err(damaged)?;
+
+    let mut files = BTreeMap::new();
+    for index in 0..zip.len() {
+        let mut file = zip.by_index(index).map_err(damaged)?;
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        files.insert(file.name().to_string(), data);
+    }
+
+    let manifest: Manifest = match files.remove(MANIFEST_FILE) {
+        Some(data) => parse(MANIFEST_FILE, &data)?,
+        None => return Err(invalid("The backup has no manifest")),
+    };
+    if manifest.version != VERSION {
+        return Err(invalid(format!(
+            "Backups of version {} are not supported",
+            manifest.version
+        )));
+    }
+    for listed in &manifest.files {
+        let Some(data) = files.get(&listed.path) else {
+            return Err(invalid(format!("The backup is missing {}", listed.path)));
+        };
+        if data.len() as u64 != listed.size || sha256(data) != listed.sha256 {
+            return Err(invalid(format!(
+                "{} does not match the backup's manifest",
+                listed.path
+            )));
+        }
+    }
+    if let Some(path) = files
+        .keys()
+        .find(|path| !manifest.files.iter().any(|listed| &listed.path == *path))
+    {
+        return Err(invalid(format!("{} is not in the backup's manifest", path)));
+    }
+
+    let mut snapshot = Snapshot {
+        created_at: manifest.created_at,
+        ..Default::default()
+    };
+    for (path, data) in files {
+        if path.starts_with(ENTRIES_DIR) {
+            snapshot.entries.push(parse(&path, &data)?);
+        } else if path.starts_with(DRAFTS_DIR) {
+            snapshot.drafts.push(parse(&path, &data)?);
+        } else if path.starts_with(TEMPLATES_DIR) {
+            snapshot.templates.push(parse(&path, &data)?);
+        } else if let Some(address) = path.strip_prefix(BLOBS_DIR) {
+            snapshot.blobs.insert(address.to_string(), data);
+        } else if path == CONFIG_FILE {
+            snapshot.config = String::from_utf8_lossy(&data).into_owned();
+        }
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{Attachment, Draft, Journal, Template};
+
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse";
+
+    fn snapshot() -> Snapshot {
+        let mut journal = Journal::new(
+            "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60".to_string(),
+            Some("Trip".to_string()),
+            Some("Photos attached".to_string()),
+            vec!["travel".to_string()],
+        );
+        journal.attachments.push(Attachment {
+            id: "a1".to_string(),
+            name: "beach.jpg".to_string(),
+            content_type: "image/jpeg".to_string(),
+            size: 4,
+            blob: "b1".to_string(),
+        });
+        Snapshot {
+            created_at: "2024-03-05T09:30:00+00:00".to_string(),
+            entries: vec![journal],
+            drafts: vec![Draft {
+                id: "7a2d3e8f-4d7c-4a6f-8b9e-2c3d4e5f6071".to_string(),
+                title: Some("Half done".to_string()),
+                ..Default::default()
+            }],
+            templates: vec![Template {
+                id: "8b3e4f9a-5e8d-4b7a-9caf-3d4e5f607182".to_string(),
+                name: "Daily".to_string(),
+                ..Default::default()
+            }],
+            blobs: BTreeMap::from([("b1".to_string(), vec![0xff, 0xd8, 0xff, 0xe0])]),
+            config: "port = 7000\n".to_string(),
+        }
+    }
+
+    /// `backup` with the files of its archive changed by `change`, sealed
+    /// again so only the manifest can tell.
+    fn tamper(backup: &[u8], change: impl FnOnce(&mut BTreeMap<String, Vec<u8>>)) -> Vec<u8> {
+        let archive = decrypt_backup(backup, PASSPHRASE).unwrap();
+        let mut zip = ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut files = BTreeMap::new();
+        for index in 0..zip.len() {
+            let mut file = zip.by_index(index).unwrap();
+            let mut data = vec![];
+            file.read_to_end(&mut data).unwrap();
+            files.insert(file.name().to_string(), data);
+        }
+        change(&mut files);
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, data) in &files {
+            zip.start_file(path.as_str(), SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        seal(&zip.finish().unwrap().into_inner(), PASSPHRASE).unwrap()
+    }
+
+    fn rejected(backup: &[u8], passphrase: &str) -> String {
+        match read_backup(backup, passphrase) {
+            Err(AppError::BadRequest(message)) => message,
+            other => panic!("expected a bad request, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let snapshot = snapshot();
+        let backup = write_backup(&snapshot, PASSPHRASE).unwrap();
+        assert!(backup.starts_with(MAGIC));
+
+        let read = read_backup(&backup, PASSPHRASE).unwrap();
+        assert_eq!(read.created_at, snapshot.created_at);
+        assert_eq!(read.entries, snapshot.entries);
+        assert_eq!(read.drafts, snapshot.drafts);
+        assert_eq!(read.templates, snapshot.templates);
+        assert_eq!(read.blobs, snapshot.blobs);
+        assert_eq!(read.config, snapshot.config);
+    }
+
+    #[test]
+    fn rejects_a_wrong_passphrase() {
+        let backup = write_backup(&snapshot(), PASSPHRASE).unwrap();
+        assert!(rejected(&backup, "incorrect horse").starts_with("Wrong passphrase"));
+
+        let mut damaged = backup.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        assert!(rejected(&damaged, PASSPHRASE).starts_with("Wrong passphrase"));
+
+        assert!(matches!(
+            write_backup(&snapshot(), "short"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(
+            rejected(b"PK\x03\x04", PASSPHRASE),
+            "This is not a backup file"
+        );
+
+        let mut backup = write_backup(&snapshot(), PASSPHRASE).unwrap();
+        backup[MAGIC.len()] = 2;
+        assert_eq!(
+            rejected(&backup, PASSPHRASE),
+            "Backups of version 2 are not supported"
+        );
+    }
+
+    #[test]
+    fn checks_every_file_against_the_manifest() {
+        let backup = write_backup(&snapshot(), PASSPHRASE).unwrap();
+        assert!(read_backup(&tamper(&backup, |_| {}), PASSPHRASE).is_ok());
+
+        let changed = tamper(&backup, |files| {
+            files.insert("blobs/b1".to_string(), vec![0; 4]);
+        });
+        assert_eq!(
+            rejected(&changed, PASSPHRASE),
+            "blobs/b1 does not match the backup's manifest"
+        );
+
+        let missing = tamper(&backup, |files| {
+            files.remove("config.toml");
+        });
+        assert_eq!(
+            rejected(&missing, PASSPHRASE),
+            "The backup is missing config.toml"
+        );
+
+        let added = tamper(&backup, |files| {
+            files.insert("blobs/b2".to_string(), vec![1]);
+        });
+        assert_eq!(
+            rejected(&added, PASSPHRASE),
+            "blobs/b2 is not in the backup's manifest"
+        );
+
+        let unlisted = tamper(&backup, |files| {
+            files.remove(MANIFEST_FILE);
+        });
+        assert_eq!(
+            rejected(&unlisted, PASSPHRASE),
+            "The backup has no manifest"
+        );
+    }
+}
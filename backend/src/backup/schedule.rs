@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use actix_web::rt::time::interval;
+use chrono::Local;
+use shared::write_private;
+
+use crate::{
+    backup::{BACKUP_EXTENSION, BACKUP_PREFIX, backup_file_name, write_backup},
+    config::LiveConfig,
+    error::AppError,
+    types::{Config, Records},
+};
+
+/// How often the schedule is checked. Its settings are read anew every time,
+/// so config reloads apply.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The scheduled backups in `dir`, oldest first.
+fn list_backups(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(BACKUP_PREFIX)
+                        && name.ends_with(&format!(".{}", BACKUP_EXTENSION))
+                })
+        })
+        .collect();
+    // The timestamp in the name sorts in time order.
+    backups.sort();
+    Ok(backups)
+}
+
+/// Makes a backup when the newest one in the backup directory is older than
+/// the interval, then deletes all but the newest `backup_keep`.
+async fn backup_if_due(records: &Records, config: Arc<Config>) -> Result<(), AppError> {
+    let Some(schedule) = config.backup_schedule() else {
+        return Ok(());
+    };
+    let backups = list_backups(schedule.dir)?;
+    let due = backups.last().is_none_or(|newest| {
+        fs::metadata(newest)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age >= schedule.interval)
+    });
+    if !due {
+        return Ok(());
+    }
+
+    let snapshot = records.snapshot(config.clone()).await?;
+    let path = schedule.dir.join(backup_file_name(Local::now()));
+    write_private(&path, &write_backup(&snapshot, schedule.passphrase)?)?;
+    println!("Wrote backup {}", path.display());
+
+    let backups = list_backups(schedule.dir)?;
+    let expired = backups.len().saturating_sub(schedule.keep);
+    for old in &backups[..expired] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Writes a backup every `backup_interval_hours` while scheduled backups are
+/// configured. Runs until the server's runtime stops.
+pub async fn run_scheduled_backups(config: LiveConfig, records: Records) {
+    let mut ticks = interval(CHECK_INTERVAL);
+    loop {
+        ticks.tick().await;
+        if let Err(err) = backup_if_due(&records, config.current()).await {
+            eprintln!("Scheduled backup failed: {}", err);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+/// Serves the task journal API. Flags and environment variables override the
+/// matching settings in `conf.toml`.
+#[derive(Debug, Clone, Default, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Config file to use instead of looking in the user's config directory
+    /// and then the current directory.
+    #[arg(long, global = true, env = "TASK_JOURNAL_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Print the configuration in effect, with overrides applied, and exit.
+    #[arg(long)]
+    pub print_config: bool,
+    /// Address to listen on.
+    #[arg(long, env = "TASK_JOURNAL_HOST")]
+    pub host: Option<String>,
+    /// Port to listen on, 0 picks a free one.
+    #[arg(long, env = "TASK_JOURNAL_PORT")]
+    pub port: Option<u16>,
+    /// Listen on this Unix domain socket instead of TCP.
+    #[arg(long, env = "TASK_JOURNAL_UNIX_SOCKET")]
+    pub unix_socket: Option<PathBuf>,
+    /// Number of worker threads, defaults to one per CPU core.
+    #[arg(long, env = "TASK_JOURNAL_WORKERS")]
+    pub workers: Option<usize>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Write a commented default config, to `--config` or the user's config
+    /// directory, with a freshly generated secret.
+    Init {
+        /// Directory to keep journals in, created if needed. Defaults to the
+        /// user's data directory.
+        #[arg(long)]
+        storage: Option<PathBuf>,
+        /// Replace an existing config file.
+        #[arg(long)]
+        force: bool,
+    },
+}
@@ -0,0 +1,387 @@
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use shared::write_private;
+use uuid::Uuid;
+
+use crate::{
+    cli::Cli,
+    types::{CONFIG_KEYS, Config, ReloadReport},
+};
+
+const CONFIG_FILE: &str = "conf.toml";
+const APP_DIR: &str = "task-journal";
+const STORAGE_DIR: &str = "journals";
+/// Settings the running server can't pick up, they only apply after a restart.
+/// Swapping the secret while requests are in flight would leave entries
+/// encrypted under either key.
+const RESTART_KEYS: &[&str] = &["host", "port", "unix_socket", "workers", "secret"];
+
+/// Everything wrong with a config file, reported together so they can all be
+/// fixed in one go.
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration in {}:", self.path.display())?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// `conf.toml` in the user's config directory, e.g. `~/.config/task-journal/conf.toml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR).join(CONFIG_FILE))
+}
+
+/// Where `backend init` keeps journals unless told otherwise.
+pub fn default_storage_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR).join(STORAGE_DIR))
+}
+
+/// Finds the config file: `explicit` (from `--config` or `TASK_JOURNAL_CONFIG`)
+/// when given, otherwise the first of the user's config directory and the
+/// current directory that has one.
+pub fn find_config(explicit: Option<&Path>) -> io::Result<PathBuf> {
+    if let Some(path) = explicit {
+        return match path.is_file() {
+            true => Ok(path.to_path_buf()),
+            false => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Config file {} does not exist", path.display()),
+            )),
+        };
+    }
+
+    let candidates: Vec<PathBuf> = user_config_path()
+        .into_iter()
+        .chain(env::current_dir().ok().map(|dir| dir.join(CONFIG_FILE)))
+        .collect();
+    candidates
+        .iter()
+        .find(|path| path.is_file())
+        .cloned()
+        .ok_or_else(|| {
+            let searched: Vec<String> = candidates
+                .iter()
+                .map(|path| format!("  - {}", path.display()))
+                .collect();
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "No {} found, looked in:\n{}\nCreate one with `backend init`",
+                    CONFIG_FILE,
+                    searched.join("\n")
+                ),
+            )
+        })
+}
+
+/// Parses a config and applies the command line overrides, reporting syntax
+/// errors, unknown and missing keys, values of the wrong type and invalid
+/// values all at once.
+pub fn parse_config(content: &str, cli: &Cli) -> Result<Config, Vec<String>> {
+    let table = toml::from_str::<toml::Table>(content).map_err(|err| vec![err.to_string()])?;
+    let mut problems = vec![];
+
+    for key in table.keys() {
+        if !CONFIG_KEYS.contains(&key.as_str()) {
+            problems.push(format!("Unknown key `{}`", key));
+        }
+    }
+    let missing: Vec<&str> = ["storage", "secret"]
+        .into_iter()
+        .filter(|key| !table.contains_key(*key))
+        .collect();
+    for key in &missing {
+        problems.push(format!("Missing required key `{}`", key));
+    }
+
+    // Check each value on its own, over valid defaults, so every wrongly
+    // typed value is reported rather than just the first. The values that
+    // are fine are then checked together.
+    let defaults = toml::Table::try_from(Config::new(PathBuf::new(), String::new()))
+        .map_err(|err| vec![err.to_string()])?;
+    let mut valid = defaults.clone();
+    for (key, value) in table.iter() {
+        if !CONFIG_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let mut single = defaults.clone();
+        single.insert(key.clone(), value.clone());
+        match single.try_into::<Config>() {
+            Ok(_) => {
+                valid.insert(key.clone(), value.clone());
+            }
+            Err(err) => problems.push(format!("`{}`: {}", key, err.message())),
+        }
+    }
+
+    let config = valid
+        .try_into::<Config>()
+        .map_err(|err| vec![err.message().to_string()])?
+        .with_overrides(cli);
+    // Missing keys fall back to empty defaults, which are already reported.
+    problems.extend(config.problems().into_iter().filter(|problem| {
+        !missing
+            .iter()
+            .any(|key| problem.starts_with(&format!("`{}`", key)))
+    }));
+
+    match problems.is_empty() {
+        true => Ok(config),
+        false => Err(problems),
+    }
+}
+
+/// Reads the config at `path` and applies the command line overrides.
+pub fn load_config(path: &Path, cli: &Cli) -> io::Result<Config> {
+    let content = fs::read_to_string(path)?;
+    parse_config(&content, cli).map_err(|problems| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            ConfigError {
+                path: path.to_path_buf(),
+                problems,
+            },
+        )
+    })
+}
+
+/// A fresh 32 character secret.
+pub fn new_secret() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// The commented config `backend init` writes.
+pub fn default_config(storage: &Path, secret: &str) -> String {
+    let storage = toml::Value::String(storage.to_string_lossy().to_string());
+    format!(
+        r#"# Task journal backend configuration.
+
+# Directory holding the encrypted journals. It must exist and be writable.
+storage = {storage}
+
+# Key the journals are encrypted with, exactly 32 characters. Changing it makes
+# the existing journals unreadable.
+secret = "{secret}"
+
+# Address and port to listen on. Port 0 picks a free port, which is written to
+# <storage>/server.url while the server runs.
+host = "127.0.0.1"
+port = 7000
+
+# Listen on a Unix domain socket instead of host and port.
+# unix_socket = "/run/user/1000/task-journal.sock"
+
+# Worker threads, one per CPU core by default.
+# workers = 4
+
+# Largest accepted attachment in bytes, and how many one entry may have.
+max_attachment_size = 10485760
+max_attachments_per_entry = 20
+
+# Largest accepted import file in bytes.
+max_import_size = 104857600
+
+# Origins allowed to call the API from a browser, e.g. "http://localhost:8080".
+cors_origins = []
+
+# Write an encrypted backup to this directory every backup_interval_hours,
+# keeping the newest backup_keep. The passphrase is needed to restore them.
+# backup_dir = "/path/to/backups"
+# backup_interval_hours = 24
+# backup_keep = 7
+# backup_passphrase = "at least 8 characters"
+
+# Keep the storage directory in a git repository with a commit for every change.
+# Only encrypted files are committed.
+git = false
+"#
+    )
+}
+
+/// Writes a commented default config to `path`, readable only by the
+/// current user, keeping journals in `storage`, which is created. Refuses to replace an existing file unless
+/// `force` is set.
+pub fn init_config(path: &Path, storage: &Path, force: bool) -> io::Result<()> {
+    if path.exists() && !force {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists, pass --force to overwrite it",
+                path.display()
+            ),
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::create_dir_all(storage)?;
+    // The file holds the secret that encrypts every journal.
+    write_private(path, default_config(storage, &new_secret()).as_bytes())
+}
+
+/// Reads the config at `path`, first writing one that keeps journals in
+/// `storage` under a freshly generated secret when the file does not exist.
+pub fn get_or_create_config(path: &Path, storage: &Path) -> io::Result<Config> {
+    if !path.exists() {
+        init_config(path, storage, false)?;
+    }
+    load_config(path, &Cli::default())
+}
+
+/// The config in use, which can be swapped for a new version of its file
+/// while the server runs.
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    path: PathBuf,
+    cli: Arc<Cli>,
+    current: Arc<RwLock<Arc<Config>>>,
+}
+
+fn to_table(config: &Config) -> io::Result<toml::Table> {
+    toml::Table::try_from(config).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl LiveConfig {
+    /// `config` as loaded from `path` with the overrides in `cli`, which keep
+    /// applying on every reload.
+    pub fn new(path: PathBuf, cli: Cli, config: Config) -> Self {
+        LiveConfig {
+            path,
+            cli: Arc::new(cli),
+            current: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A snapshot of the settings, unaffected by later reloads.
+    pub fn current(&self) -> Arc<Config> {
+        match self.current.read() {
+            Ok(config) => config.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Reads and validates the config file again and swaps in everything
+    /// that changed, except the settings in `RESTART_KEYS`, which keep their
+    /// old values. The current config stays in place when the file is invalid.
+    pub fn reload(&self) -> io::Result<ReloadReport> {
+        let loaded = to_table(&load_config(&self.path, &self.cli)?)?;
+        let previous = to_table(&self.current())?;
+
+        let mut report = ReloadReport::default();
+        let mut applied = loaded.clone();
+        for key in CONFIG_KEYS {
+            if loaded.get(*key) == previous.get(*key) {
+                continue;
+            }
+            if RESTART_KEYS.contains(key) {
+                report.restart_required.push(key.to_string());
+                match previous.get(*key) {
+                    Some(value) => applied.insert(key.to_string(), value.clone()),
+                    None => applied.remove(*key),
+                };
+            } else {
+                report.applied.push(key.to_string());
+            }
+        }
+
+        let applied = applied
+            .try_into::<Config>()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        match self.current.write() {
+            Ok(mut current) => *current = Arc::new(applied),
+            Err(poisoned) => *poisoned.into_inner() = Arc::new(applied),
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_the_config_for_the_current_user_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let config = get_or_create_config(&path, &dir.path().join(STORAGE_DIR)).unwrap();
+        assert_eq!(config.storage(), dir.path().join(STORAGE_DIR));
+        assert_eq!(mode(&path), 0o600);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(init_config(&path, dir.path(), false).is_err());
+        init_config(&path, dir.path(), true).unwrap();
+        assert_eq!(mode(&path), 0o600);
+    }
+
+    #[test]
+    fn keeps_the_secret_until_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        let storage = dir.path().join(STORAGE_DIR);
+        let config = get_or_create_config(&path, &storage).unwrap();
+        let secret = to_table(&config).unwrap()["secret"].clone();
+        let live = LiveConfig::new(path.clone(), Cli::default(), config);
+
+        let text = fs::read_to_string(&path)
+            .unwrap()
+            .replace(secret.as_str().unwrap(), "fedcba9876543210fedcba9876543210")
+            .replace(
+                "max_attachments_per_entry = 20",
+                "max_attachments_per_entry = 5",
+            );
+        fs::write(&path, text).unwrap();
+
+        let report = live.reload().unwrap();
+        assert_eq!(report.applied, ["max_attachments_per_entry"]);
+        assert_eq!(report.restart_required, ["secret"]);
+        assert_eq!(to_table(&live.current()).unwrap()["secret"], secret);
+    }
+
+    #[test]
+    fn only_warns_about_a_weak_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = toml::Value::String(dir.path().to_string_lossy().to_string());
+        let config = parse_config(
+            &format!(
+                "storage = {}\nsecret = \"example32bytekey1234567890abcdef\"",
+                storage
+            ),
+            &Cli::default(),
+        )
+        .unwrap();
+        assert_eq!(config.warnings().len(), 1);
+
+        let config = get_or_create_config(&dir.path().join(CONFIG_FILE), dir.path()).unwrap();
+        assert!(config.warnings().is_empty());
+        assert!(
+            parse_config(
+                &format!("storage = {}\nsecret = \"short\"", storage),
+                &Cli::default()
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::{fmt, io};
+
+use actix_web::{
+    Error, HttpResponse, ResponseError,
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::BlockingError,
+    http::{
+        StatusCode,
+        header::{HeaderName, HeaderValue},
+    },
+    middleware::Next,
+};
+use uuid::Uuid;
+
+pub use shared::{ErrorCode, ErrorResponse};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    Locked(String),
+    BadRequest(String),
+    PayloadTooLarge(String),
+    Unauthorized(String),
+    Forbidden(String),
+    CorruptData(String),
+    Crypto(String),
+    Io(io::Error),
+}
+
+impl AppError {
+    /// Replaces the message of a `NotFound` error, which for files is the OS
+    /// error rather than a description of what was looked up.
+    pub fn with_not_found_message(self, message: impl FnOnce() -> String) -> Self {
+        match self {
+            AppError::NotFound(_) => AppError::NotFound(message()),
+            err => err,
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Locked(_) => ErrorCode::Locked,
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::CorruptData(_) => ErrorCode::CorruptData,
+            AppError::Crypto(_) => ErrorCode::Crypto,
+            AppError::Io(_) => ErrorCode::Io,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Locked(message)
+            | AppError::BadRequest(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message) => write!(f, "{}", message),
+            AppError::CorruptData(message) => write!(f, "Stored data is corrupt: {}", message),
+            AppError::Crypto(message) => write!(f, "Encryption failed: {}", message),
+            AppError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound(err.to_string()),
+            io::ErrorKind::InvalidInput => AppError::BadRequest(err.to_string()),
+            io::ErrorKind::InvalidData => AppError::CorruptData(err.to_string()),
+            io::ErrorKind::FileTooLarge => AppError::PayloadTooLarge(err.to_string()),
+            io::ErrorKind::ResourceBusy | io::ErrorKind::WouldBlock => {
+                AppError::Locked(err.to_string())
+            }
+            _ => AppError::Io(err),
+        }
+    }
+}
+
+impl From<aes_gcm::Error> for AppError {
+    fn from(_: aes_gcm::Error) -> Self {
+        AppError::Crypto("the data does not match the configured secret".to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::CorruptData(err.to_string())
+    }
+}
+
+impl From<BlockingError> for AppError {
+    fn from(err: BlockingError) -> Self {
+        AppError::Io(io::Error::other(err.to_string()))
+    }
+}
+
+impl From<git2::Error> for AppError {
+    fn from(err: git2::Error) -> Self {
+        match err.code() {
+            git2::ErrorCode::NotFound => AppError::NotFound(err.message().to_string()),
+            git2::ErrorCode::Locked => AppError::Locked(err.message().to_string()),
+            _ => AppError::Io(io::Error::other(err.message().to_string())),
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Locked(_) => StatusCode::LOCKED,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::CorruptData(_) | AppError::Crypto(_) | AppError::Io(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            code: self.code(),
+            message: self.to_string(),
+            request_id: None,
+        })
+    }
+}
+
+fn code_for_status(status: StatusCode) -> ErrorCode {
+    match status {
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::LOCKED => ErrorCode::Locked,
+        StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        status if status.is_client_error() => ErrorCode::BadRequest,
+        _ => ErrorCode::Internal,
+    }
+}
+
+/// Tags every response with a request id and rewrites error responses,
+/// including the ones produced by actix's extractors, into an [`ErrorResponse`].
+pub async fn request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    let res = next.call(req).await?;
+    let status = res.status();
+    let error = res
+        .response()
+        .error()
+        .map(|err| match err.as_error::<AppError>() {
+            Some(err) => (err.code(), err.to_string()),
+            None => (code_for_status(status), err.to_string()),
+        });
+
+    let mut res = match error {
+        Some((code, message)) => {
+            if status.is_server_error() {
+                eprintln!("[{}] {} {}: {}", request_id, method, path, message);
+            }
+            res.into_response(HttpResponse::build(status).json(ErrorResponse {
+                code,
+                message,
+                request_id: Some(request_id.clone()),
+            }))
+        }
+        None => res.map_into_boxed_body(),
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(res)
+}
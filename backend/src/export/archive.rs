@@ -0,0 +1,313 @@
+use std::{
+    collections::HashSet,
+    io::{self, Cursor, Write},
+    sync::Arc,
+};
+
+use shared::Journal;
+use zip::{
+    CompressionMethod, ZipWriter,
+    write::{FileOptions, SimpleFileOptions},
+};
+
+use crate::{
+    error::AppError,
+    export::{Exporter, entry_markdown},
+    types::{Config, read_blob},
+};
+
+/// A zip archive with one Markdown file per entry, named after its title, and
+/// the entry's attachments in a folder next to it.
+///
+/// The archive is assembled in memory and sent once complete, as the zip
+/// format needs to seek back while writing.
+pub struct ZipExporter {
+    config: Arc<Config>,
+    group_by_tag: bool,
+    used_paths: HashSet<String>,
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+}
+
+impl ZipExporter {
+    pub fn new(config: Arc<Config>, group_by_tag: bool) -> Self {
+        Self {
+            config,
+            group_by_tag,
+            used_paths: HashSet::new(),
+            zip: ZipWriter::new(Cursor::new(Vec::new())),
+        }
+    }
+
+    /// `path` (without extension), or `path-2`, `path-3`, ... if an earlier
+    /// entry already took it. Compared case insensitively, as many file
+    /// systems do.
+    fn unique_path(&mut self, path: String) -> String {
+        let mut candidate = path.clone();
+        let mut counter = 2;
+        while !self.used_paths.insert(candidate.to_lowercase()) {
+            candidate = format!("{}-{}", path, counter);
+            counter += 1;
+        }
+        candidate
+    }
+
+    fn add_file(&mut self, path: &str, contents: &[u8]) -> Result<(), AppError> {
+        let options: SimpleFileOptions =
+            FileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.zip
+            .start_file(path, options)
+            .map_err(io::Error::from)?;
+        self.zip.write_all(contents)?;
+        Ok(())
+    }
+}
+
+/// Lowercase letters and digits, everything else collapsed into single
+/// dashes.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_end_matches('-').chars().take(60).collect();
+    match slug.trim_end_matches('-') {
+        "" => "untitled".to_string(),
+        slug => slug.to_string(),
+    }
+}
+
+/// An attachment name that stays inside its folder.
+fn file_name(name: &str) -> String {
+    let name = name.replace(['/', '\\'], "_");
+    match name.trim_start_matches('.') {
+        "" => "attachment".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// A Markdown link destination, in angle brackets if it would otherwise end
+/// early.
+fn link_destination(path: &str) -> String {
+    match path.contains([' ', '(', ')']) {
+        true => format!("<{}>", path),
+        false => path.to_string(),
+    }
+}
+
+/// Points the links in `body` to `/entry/{id}/attachments/{attachment_id}`,
+/// with or without the backend's origin, at `path` instead.
+pub fn rewrite_attachment_link(
+    body: &str,
+    entry_id: &str,
+    attachment_id: &str,
+    path: &str,
+) -> String {
+    let target = format!("/entry/{}/attachments/{}", entry_id, attachment_id);
+    let mut rewritten = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(found) = rest.find(&target) {
+        let end = found + target.len();
+        let start = rest[..found]
+            .rfind(|c: char| c == '(' || c.is_whitespace())
+            .filter(|&open| rest[open..].starts_with('(') && rest[..open].ends_with(']'))
+            .filter(|_| rest[end..].starts_with(')'));
+        match start {
+            Some(open) => {
+                rewritten.push_str(&rest[..=open]);
+                rewritten.push_str(&link_destination(path));
+            }
+            None => rewritten.push_str(&rest[..end]),
+        }
+        rest = &rest[end..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+impl Exporter for ZipExporter {
+    fn content_type(&self) -> &'static str {
+        "application/zip"
+    }
+
+    fn extension(&self) -> &'static str {
+        "zip"
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let slug = slugify(journal.title.as_deref().unwrap_or_default());
+        let folder = journal
+            .tags
+            .first()
+            .filter(|_| self.group_by_tag)
+            .map(|tag| format!("{}/", slugify(tag)))
+            .unwrap_or_default();
+        let path = self.unique_path(format!("{}{}", folder, slug));
+        let stem = path.rsplit('/').next().unwrap_or(&path).to_string();
+
+        let mut body = journal.body.clone().unwrap_or_default();
+        let mut links = Vec::new();
+        let mut names = HashSet::new();
+        for attachment in &journal.attachments {
+            let mut name = file_name(&attachment.name);
+            let mut counter = 2;
+            while !names.insert(name.to_lowercase()) {
+                name = format!("{}-{}", counter, file_name(&attachment.name));
+                counter += 1;
+            }
+            let link = format!("{}-attachments/{}", stem, name);
+            let data = read_blob(&self.config, attachment)?;
+            self.add_file(&format!("{}{}", folder, link), &data)?;
+            body = rewrite_attachment_link(&body, &journal.id, &attachment.id, &link);
+            links.push(link);
+        }
+
+        let markdown = entry_markdown(journal, &body, &links);
+        self.add_file(&format!("{}.md", path), markdown.as_bytes())?;
+        Ok(Vec::new())
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>, AppError> {
+        let zip = std::mem::replace(&mut self.zip, ZipWriter::new(Cursor::new(Vec::new())));
+        Ok(zip.finish().map_err(io::Error::from)?.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::types::{Records, test_vault};
+
+    const ENTRY: &str = "00000000-0000-4000-8000-000000000001";
+
+    #[test]
+    fn slugifies_titles() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  --Trip to  the sea--  "), "trip-to-the-sea");
+        assert_eq!(slugify("Café Über straße"), "café-über-straße");
+        assert_eq!(slugify("日記 2024"), "日記-2024");
+        assert_eq!(slugify("../etc/passwd"), "etc-passwd");
+        assert_eq!(slugify(r"C:\Users\me"), "c-users-me");
+        assert_eq!(slugify(""), "untitled");
+        assert_eq!(slugify("?!/"), "untitled");
+        assert_eq!(slugify(&"word ".repeat(20)).chars().count(), 59);
+        assert!(!slugify(&"ab ".repeat(40)).ends_with('-'));
+    }
+
+    #[test]
+    fn numbers_paths_already_taken() {
+        let (_dir, config) = test_vault(|_| {});
+        let mut exporter = ZipExporter::new(config, true);
+        assert_eq!(exporter.unique_path("notes".to_string()), "notes");
+        assert_eq!(exporter.unique_path("notes".to_string()), "notes-2");
+        assert_eq!(exporter.unique_path("Notes".to_string()), "Notes-3");
+        assert_eq!(exporter.unique_path("work/notes".to_string()), "work/notes");
+        assert_eq!(exporter.unique_path("notes-2".to_string()), "notes-2-2");
+    }
+
+    #[test]
+    fn keeps_attachment_names_in_their_folder() {
+        assert_eq!(file_name("photo.png"), "photo.png");
+        assert_eq!(file_name("../../secret"), "_.._secret");
+        assert_eq!(file_name(r"dir\photo.png"), "dir_photo.png");
+        assert_eq!(file_name(".."), "attachment");
+        assert_eq!(file_name(".hidden"), "hidden");
+    }
+
+    #[test]
+    fn rewrites_links_to_the_attachment() {
+        let link = format!("/entry/{}/attachments/a1", ENTRY);
+        let body =
+            format!("![photo]({link}) and [file](http://localhost:8080{link}), again ![]({link})");
+        assert_eq!(
+            rewrite_attachment_link(&body, ENTRY, "a1", "day-attachments/photo.png"),
+            "![photo](day-attachments/photo.png) and [file](day-attachments/photo.png), \
+             again ![](day-attachments/photo.png)"
+        );
+        assert_eq!(
+            rewrite_attachment_link(&format!("[x]({})", link), ENTRY, "a1", "my day/a (1).png"),
+            "[x](<my day/a (1).png>)"
+        );
+    }
+
+    #[test]
+    fn leaves_other_links_alone() {
+        let other_entry = "/entry/00000000-0000-4000-8000-000000000002/attachments/a1";
+        let other_attachment = format!("/entry/{}/attachments/a2", ENTRY);
+        let bare = format!("see /entry/{}/attachments/a1 and `({})`", ENTRY, "x");
+        let longer = format!("[x](/entry/{}/attachments/a1/extra)", ENTRY);
+        for body in [
+            format!("[x]({})", other_entry),
+            format!("[x]({})", other_attachment),
+            bare,
+            longer,
+        ] {
+            assert_eq!(rewrite_attachment_link(&body, ENTRY, "a1", "a.png"), body);
+        }
+    }
+
+    #[actix_web::test]
+    async fn exports_attachments_next_to_the_entry() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let id = records
+            .insert(Some("My Day".to_string()), None, vec![], config.clone())
+            .await
+            .unwrap();
+        let mut attachments = Vec::new();
+        for data in [b"first".to_vec(), b"second".to_vec()] {
+            let attachment = records
+                .add_attachment(
+                    &id,
+                    "photo.png".to_string(),
+                    "image/png".to_string(),
+                    data,
+                    config.clone(),
+                )
+                .await
+                .unwrap();
+            attachments.push(attachment);
+        }
+        let body = format!(
+            "![one](/entry/{id}/attachments/{}) ![two](/entry/{id}/attachments/{}) \
+             [gone](/entry/{id}/attachments/missing)",
+            attachments[0].id, attachments[1].id
+        );
+        let journal = records
+            .update(
+                &id,
+                Some("My Day".to_string()),
+                Some(body),
+                vec![],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+
+        let mut exporter = ZipExporter::new(config, false);
+        exporter.entry(&journal).unwrap();
+        let mut zip = ZipArchive::new(Cursor::new(exporter.finish().unwrap())).unwrap();
+        let mut read = |path: &str| {
+            let mut contents = String::new();
+            zip.by_name(path)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        };
+        assert_eq!(read("my-day-attachments/photo.png"), "first");
+        assert_eq!(read("my-day-attachments/2-photo.png"), "second");
+        let markdown = read("my-day.md");
+        assert!(markdown.contains(
+            "![one](my-day-attachments/photo.png) ![two](my-day-attachments/2-photo.png)"
+        ));
+        assert!(markdown.contains(&format!("[gone](/entry/{}/attachments/missing)", id)));
+    }
+}
@@ -0,0 +1,118 @@
+use shared::{Journal, markdown_to_html};
+
+use crate::{error::AppError, export::Exporter};
+
+/// A single page with the styles inlined, readable offline and printable.
+pub struct HtmlExporter;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; line-height: 1.5; max-width: 46rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+article { border-bottom: 1px solid #ddd; padding-bottom: 1.5rem; margin-bottom: 1.5rem; }
+h1 { margin-bottom: 0.25rem; }
+.meta { color: #666; font-size: 0.9rem; }
+.tag { display: inline-block; background: #eef; border-radius: 0.25rem; padding: 0 0.4rem; margin-right: 0.25rem; }
+pre { background: #f5f5f5; padding: 0.75rem; overflow-x: auto; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 0.25rem 0.5rem; }
+@media print { article { page-break-inside: avoid; } }
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Exporter for HtmlExporter {
+    fn content_type(&self) -> &'static str {
+        "text/html; charset=utf-8"
+    }
+
+    fn extension(&self) -> &'static str {
+        "html"
+    }
+
+    fn header(&mut self) -> Result<Vec<u8>, AppError> {
+        Ok(format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Journal</title>\n<style>{}</style>\n</head>\n<body>\n",
+            STYLE
+        )
+        .into_bytes())
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let title = journal.title.as_deref().unwrap_or("Untitled");
+        let tags: String = journal
+            .tags
+            .iter()
+            .map(|tag| format!("<span class=\"tag\">{}</span>", escape(tag)))
+            .collect();
+        let created = journal
+            .created_at
+            .as_deref()
+            .map(|created_at| format!("<time datetime=\"{0}\">{0}</time> ", escape(created_at)))
+            .unwrap_or_default();
+        let body = markdown_to_html(journal.body.as_deref().unwrap_or_default());
+        Ok(format!(
+            "<article id=\"{}\">\n<h1>{}</h1>\n<p class=\"meta\">{}{}</p>\n{}</article>\n",
+            escape(&journal.id),
+            escape(title),
+            created,
+            tags,
+            body
+        )
+        .into_bytes())
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>, AppError> {
+        Ok(b"</body>\n</html>\n".to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(title: &str, body: &str, tags: &[&str]) -> String {
+        let journal = Journal::new(
+            "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60".to_string(),
+            Some(title.to_string()),
+            Some(body.to_string()),
+            tags.iter().map(|tag| tag.to_string()).collect(),
+        );
+        String::from_utf8(HtmlExporter.entry(&journal).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn escapes_titles_and_tags() {
+        let html = export("<b>Q&A</b> \"today\"", "", &["<i>x</i>"]);
+        assert!(html.contains("<h1>&lt;b&gt;Q&amp;A&lt;/b&gt; &quot;today&quot;</h1>"));
+        assert!(html.contains("<span class=\"tag\">&lt;i&gt;x&lt;/i&gt;</span>"));
+        assert!(!html.contains("<b>") && !html.contains("<i>"));
+    }
+
+    #[test]
+    fn renders_and_sanitizes_bodies() {
+        let body = "Some **bold** & more\n\n\
+                    <script>alert(1)</script>\n\n\
+                    <img src=\"x.png\" onerror=\"alert(2)\">\n\n\
+                    [click](javascript:alert(3)) <a href=\"https://example.com\" onclick=\"alert(4)\">site</a>";
+        let html = export("Title", body, &[]);
+        assert!(html.contains("<strong>bold</strong> &amp; more"));
+        assert!(html.contains("<img src=\"x.png\">"));
+        assert!(html.contains("https://example.com"));
+        for unsafe_html in ["<script", "alert(1)", "onerror", "javascript:", "onclick"] {
+            assert!(!html.contains(unsafe_html), "{} in {}", unsafe_html, html);
+        }
+    }
+
+    #[test]
+    fn wraps_entries_in_a_document() {
+        let mut exporter = HtmlExporter;
+        let header = String::from_utf8(exporter.header().unwrap()).unwrap();
+        let footer = String::from_utf8(exporter.finish().unwrap()).unwrap();
+        assert!(header.starts_with("<!DOCTYPE html>") && header.ends_with("<body>\n"));
+        assert_eq!(footer, "</body>\n</html>\n");
+    }
+}
@@ -0,0 +1,267 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use shared::Journal;
+
+use crate::{
+    error::AppError,
+    export::Exporter,
+    types::{DONE_TAG, TODO_TAG, Task},
+};
+
+/// Suffix of the UIDs given to tasks, so an imported feed keeps their ids.
+pub const UID_DOMAIN: &str = "@task-journal";
+
+/// Task entries as an iCalendar file: a VTODO for every task, and a VEVENT
+/// on the due date of those that have one. Other entries are left out.
+pub struct IcsExporter {
+    stamp: String,
+}
+
+impl Default for IcsExporter {
+    fn default() -> Self {
+        IcsExporter {
+            stamp: Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+        }
+    }
+}
+
+/// `value` escaped for a TEXT property.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `line` folded to 75 octets per line, as RFC 5545 asks, and terminated.
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// An RFC 3339 timestamp as a UTC DATE-TIME.
+fn utc(value: &str) -> Option<String> {
+    let time = DateTime::parse_from_rfc3339(value).ok()?;
+    Some(
+        time.with_timezone(&Utc)
+            .format("%Y%m%dT%H%M%SZ")
+            .to_string(),
+    )
+}
+
+/// The due date as a DATE or DATE-TIME property suffix, with the day after
+/// for the end of an all day event.
+fn due(value: &str) -> Option<(String, Option<String>)> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let next = date
+            .succ_opt()
+            .map(|next| next.format(";VALUE=DATE:%Y%m%d").to_string());
+        return Some((date.format(";VALUE=DATE:%Y%m%d").to_string(), next));
+    }
+    utc(value).map(|time| (format!(":{}", time), None))
+}
+
+impl Exporter for IcsExporter {
+    fn content_type(&self) -> &'static str {
+        "text/calendar; charset=utf-8"
+    }
+
+    fn extension(&self) -> &'static str {
+        "ics"
+    }
+
+    fn header(&mut self) -> Result<Vec<u8>, AppError> {
+        Ok(b"BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//task-journal//EN\r\nCALSCALE:GREGORIAN\r\n".to_vec())
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let Some(task) = Task::of(journal) else {
+            return Ok(Vec::new());
+        };
+
+        // Untitled tasks have no SUMMARY, so they come back untitled.
+        let summary = journal
+            .title
+            .as_deref()
+            .map(|title| format!("SUMMARY:{}", escape(title)));
+        let created = journal.created_at.as_deref().and_then(utc);
+        let modified = journal.updated_at.as_deref().and_then(utc);
+        let due = task.due.as_deref().and_then(due);
+        let categories: Vec<String> = journal
+            .tags
+            .iter()
+            .filter(|tag| *tag != DONE_TAG && *tag != TODO_TAG)
+            .map(|tag| escape(tag))
+            .collect();
+
+        let mut lines = vec![
+            "BEGIN:VTODO".to_string(),
+            format!("UID:{}{}", journal.id, UID_DOMAIN),
+            format!("DTSTAMP:{}", self.stamp),
+        ];
+        lines.extend(summary.clone());
+        if let Some(created) = &created {
+            lines.push(format!("CREATED:{}", created));
+        }
+        if let Some(modified) = &modified {
+            lines.push(format!("LAST-MODIFIED:{}", modified));
+        }
+        if let Some(body) = journal
+            .body
+            .as_deref()
+            .filter(|body| !body.trim().is_empty())
+        {
+            lines.push(format!("DESCRIPTION:{}", escape(body)));
+        }
+        if !categories.is_empty() {
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+        if let Some((start, _)) = &due {
+            lines.push(format!("DUE{}", start));
+        }
+        if let Some(rrule) = &task.rrule {
+            lines.push(format!("RRULE:{}", rrule));
+        }
+        if task.done {
+            lines.push("STATUS:COMPLETED".to_string());
+            if let Some(completed) = modified.as_ref().or(created.as_ref()) {
+                lines.push(format!("COMPLETED:{}", completed));
+            }
+        } else {
+            lines.push("STATUS:NEEDS-ACTION".to_string());
+        }
+        lines.push("END:VTODO".to_string());
+
+        if let Some((start, end)) = &due {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}-due{}", journal.id, UID_DOMAIN));
+            lines.push(format!("DTSTAMP:{}", self.stamp));
+            lines.extend(summary);
+            lines.push(format!("DTSTART{}", start));
+            if let Some(end) = end {
+                lines.push(format!("DTEND{}", end));
+            }
+            if let Some(rrule) = &task.rrule {
+                lines.push(format!("RRULE:{}", rrule));
+            }
+            lines.push("TRANSP:TRANSPARENT".to_string());
+            lines.push("END:VEVENT".to_string());
+        }
+
+        let mut out = String::new();
+        for line in &lines {
+            fold(line, &mut out);
+        }
+        Ok(out.into_bytes())
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>, AppError> {
+        Ok(b"END:VCALENDAR\r\n".to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(title: Option<&str>, body: &str, tags: &[&str]) -> Journal {
+        let tags = tags.iter().map(|tag| tag.to_string()).collect();
+        let mut journal = Journal::new(
+            "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60".to_string(),
+            title.map(str::to_string),
+            Some(body.to_string()),
+            tags,
+        );
+        journal.created_at = Some("2024-03-01T09:00:00+01:00".to_string());
+        journal.updated_at = Some("2024-03-02T09:00:00+01:00".to_string());
+        journal
+    }
+
+    fn export(journal: &Journal) -> String {
+        let mut exporter = IcsExporter {
+            stamp: "20240305T120000Z".to_string(),
+        };
+        String::from_utf8(exporter.entry(journal).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn escapes_and_folds_text() {
+        assert_eq!(escape("a\\b;c,d\r\ne"), r"a\\b\;c\,d\ne");
+
+        let mut out = String::new();
+        fold(&format!("SUMMARY:{}", "é".repeat(60)), &mut out);
+        let lines: Vec<&str> = out.trim_end_matches("\r\n").split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(
+            lines.concat().replace(" é", "é"),
+            format!("SUMMARY:{}", "é".repeat(60))
+        );
+    }
+
+    #[test]
+    fn writes_a_todo_and_an_event_on_the_due_day() {
+        let journal = task(
+            Some("Pay rent"),
+            "Transfer it\n- due: 2024-03-31\n- rec: 1m",
+            &["todo", "home"],
+        );
+        let id = &journal.id;
+        assert_eq!(
+            export(&journal),
+            [
+                "BEGIN:VTODO".to_string(),
+                format!("UID:{}@task-journal", id),
+                "DTSTAMP:20240305T120000Z".to_string(),
+                "SUMMARY:Pay rent".to_string(),
+                "CREATED:20240301T080000Z".to_string(),
+                "LAST-MODIFIED:20240302T080000Z".to_string(),
+                "DESCRIPTION:Transfer it\\n- due: 2024-03-31\\n- rec: 1m".to_string(),
+                "CATEGORIES:home".to_string(),
+                "DUE;VALUE=DATE:20240331".to_string(),
+                "RRULE:FREQ=MONTHLY".to_string(),
+                "STATUS:NEEDS-ACTION".to_string(),
+                "END:VTODO".to_string(),
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}-due@task-journal", id),
+                "DTSTAMP:20240305T120000Z".to_string(),
+                "SUMMARY:Pay rent".to_string(),
+                "DTSTART;VALUE=DATE:20240331".to_string(),
+                "DTEND;VALUE=DATE:20240401".to_string(),
+                "RRULE:FREQ=MONTHLY".to_string(),
+                "TRANSP:TRANSPARENT".to_string(),
+                "END:VEVENT".to_string(),
+                String::new(),
+            ]
+            .join("\r\n")
+        );
+    }
+
+    #[test]
+    fn leaves_out_other_entries_and_missing_fields() {
+        assert_eq!(export(&task(Some("Note"), "Text", &["work"])), "");
+
+        let exported = export(&task(None, "- due: 2024-03-31T17:30:00+02:00", &["done"]));
+        assert!(!exported.contains("SUMMARY"));
+        assert!(exported.contains("DUE:20240331T153000Z\r\n"));
+        assert!(exported.contains("STATUS:COMPLETED\r\nCOMPLETED:20240302T080000Z\r\n"));
+        assert!(exported.contains("DTSTART:20240331T153000Z\r\n"));
+        assert!(!exported.contains("DTEND"));
+    }
+}
@@ -0,0 +1,55 @@
+use shared::Journal;
+
+use crate::{error::AppError, export::Exporter};
+
+/// Every `Journal` record as one line of JSON.
+pub struct JsonlExporter;
+
+impl Exporter for JsonlExporter {
+    fn content_type(&self) -> &'static str {
+        "application/jsonl; charset=utf-8"
+    }
+
+    fn extension(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let mut line = serde_json::to_vec(journal)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_object_per_line() {
+        let mut first = Journal::new(
+            "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60".to_string(),
+            Some("Lines".to_string()),
+            Some("One\ntwo\r\n\nthree \u{2028} four".to_string()),
+            vec!["a".to_string()],
+        );
+        first.created_at = Some("2024-03-01T09:00:00+01:00".to_string());
+        let second = Journal::new(
+            "7a2d3e4f-5b6c-4d7e-8f90-a1b2c3d4e5f6".to_string(),
+            None,
+            None,
+            vec![],
+        );
+
+        let mut exporter = JsonlExporter;
+        let mut output = exporter.entry(&first).unwrap();
+        output.extend(exporter.entry(&second).unwrap());
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.ends_with('\n'));
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(serde_json::from_str::<Journal>(lines[0]).unwrap(), first);
+        assert_eq!(serde_json::from_str::<Journal>(lines[1]).unwrap(), second);
+    }
+}
@@ -0,0 +1,114 @@
+use shared::Journal;
+
+use crate::{error::AppError, export::Exporter};
+
+/// Markdown with each entry's id, tags and timestamps in a YAML front matter
+/// block, so nothing is lost on the way out.
+pub struct MarkdownExporter;
+
+/// A YAML double quoted scalar, which JSON string syntax is a subset of.
+fn yaml_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
+fn yaml_list<'a>(values: impl IntoIterator<Item = &'a String>) -> String {
+    let values: Vec<String> = values.into_iter().map(|value| yaml_string(value)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// The front matter block of `journal`, including its closing `---` line.
+/// `attachments` lists how the entry's attachments are referred to.
+pub fn front_matter(journal: &Journal, attachments: &[String]) -> String {
+    let mut front_matter = format!("---\nid: {}\n", yaml_string(&journal.id));
+    // Titled entries carry their title as the heading below instead.
+    if journal.title.is_none() {
+        front_matter.push_str("title: \"\"\n");
+    }
+    front_matter.push_str(&format!("tags: {}\n", yaml_list(&journal.tags)));
+    if let Some(created_at) = &journal.created_at {
+        front_matter.push_str(&format!("created_at: {}\n", yaml_string(created_at)));
+    }
+    if let Some(updated_at) = &journal.updated_at {
+        front_matter.push_str(&format!("updated_at: {}\n", yaml_string(updated_at)));
+    }
+    if !attachments.is_empty() {
+        front_matter.push_str(&format!("attachments: {}\n", yaml_list(attachments)));
+    }
+    front_matter.push_str("---\n");
+    front_matter
+}
+
+/// `journal` as a Markdown document, with `body` in place of its own.
+pub fn entry_markdown(journal: &Journal, body: &str, attachments: &[String]) -> String {
+    let front_matter = front_matter(journal, attachments);
+    match &journal.title {
+        Some(title) => format!("{}\n# {}\n\n{}\n\n", front_matter, title, body),
+        None => format!("{}\n{}\n\n", front_matter, body),
+    }
+}
+
+impl Exporter for MarkdownExporter {
+    fn content_type(&self) -> &'static str {
+        "text/markdown; charset=utf-8"
+    }
+
+    fn extension(&self) -> &'static str {
+        "md"
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let attachments: Vec<String> = journal
+            .attachments
+            .iter()
+            .map(|attachment| attachment.name.clone())
+            .collect();
+        let body = journal.body.as_deref().unwrap_or_default();
+        Ok(entry_markdown(journal, body, &attachments).into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_front_matter_values() {
+        let mut journal = Journal::new(
+            "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60".to_string(),
+            Some("Notes: \"draft\"".to_string()),
+            Some("Body".to_string()),
+            vec!["a, b".to_string(), "c\"d".to_string()],
+        );
+        journal.created_at = Some("2024-03-01T09:00:00+01:00".to_string());
+        let attachments = ["photo: 1.png".to_string()];
+
+        assert_eq!(
+            entry_markdown(&journal, "Body", &attachments),
+            "---\n\
+             id: \"6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60\"\n\
+             tags: [\"a, b\", \"c\\\"d\"]\n\
+             created_at: \"2024-03-01T09:00:00+01:00\"\n\
+             attachments: [\"photo: 1.png\"]\n\
+             ---\n\
+             \n\
+             # Notes: \"draft\"\n\
+             \n\
+             Body\n\n"
+        );
+    }
+
+    #[test]
+    fn marks_untitled_entries() {
+        let journal = Journal::new(
+            "1".to_string(),
+            None,
+            Some("# Not a title".to_string()),
+            vec![],
+        );
+        let markdown = String::from_utf8(MarkdownExporter.entry(&journal).unwrap()).unwrap();
+        assert_eq!(
+            markdown,
+            "---\nid: \"1\"\ntitle: \"\"\ntags: []\n---\n\n# Not a title\n\n"
+        );
+    }
+}
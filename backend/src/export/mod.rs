@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use futures_util::{Stream, stream};
+use shared::{ExportFormat, ExportOptions, Journal};
+
+use crate::{error::AppError, types::Config};
+
+mod archive;
+mod html;
+mod ical;
+mod jsonl;
+mod markdown;
+mod pdf;
+mod spreadsheet;
+
+pub use archive::*;
+pub use html::*;
+pub use ical::*;
+pub use jsonl::*;
+pub use markdown::*;
+pub use pdf::*;
+pub use spreadsheet::*;
+
+/// Renders entries into one export file, a piece at a time so the result can
+/// be streamed.
+pub trait Exporter: Send {
+    fn content_type(&self) -> &'static str;
+
+    fn extension(&self) -> &'static str;
+
+    /// Anything written before the first entry.
+    fn header(&mut self) -> Result<Vec<u8>, AppError> {
+        Ok(Vec::new())
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError>;
+
+    /// Anything written after the last entry.
+    fn finish(&mut self) -> Result<Vec<u8>, AppError> {
+        Ok(Vec::new())
+    }
+}
+
+pub fn exporter_for(options: &ExportOptions, config: Arc<Config>) -> Box<dyn Exporter> {
+    match options.format {
+        ExportFormat::Markdown => Box::new(MarkdownExporter),
+        ExportFormat::Jsonl => Box::new(JsonlExporter),
+        ExportFormat::Csv => Box::new(CsvExporter),
+        ExportFormat::Html => Box::new(HtmlExporter),
+        ExportFormat::Pdf => Box::new(PdfExporter::default()),
+        ExportFormat::Zip => Box::new(ZipExporter::new(config, options.group_by_tag)),
+    }
+}
+
+/// The chunks of the export, rendered as they are polled.
+pub fn export_stream(
+    mut exporter: Box<dyn Exporter>,
+    journals: Vec<Journal>,
+) -> impl Stream<Item = Result<actix_web::web::Bytes, AppError>> {
+    let mut journals = journals.into_iter();
+    let mut started = false;
+    let mut finished = false;
+    let chunks = std::iter::from_fn(move || {
+        if !started {
+            started = true;
+            return Some(exporter.header());
+        }
+        if finished {
+            return None;
+        }
+        match journals.next() {
+            Some(journal) => Some(exporter.entry(&journal)),
+            None => {
+                finished = true;
+                Some(exporter.finish())
+            }
+        }
+    });
+    stream::iter(chunks.map(|chunk| chunk.map(actix_web::web::Bytes::from)))
+}
@@ -0,0 +1,736 @@
+use std::io;
+
+use chrono::{DateTime, Local};
+use printpdf::{BuiltinFont, Color, IndirectFontRef, Mm, PdfDocument, Rgb};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use shared::Journal;
+
+use crate::{error::AppError, export::Exporter};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const TEXT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const FOOTER_Y: f32 = 10.0;
+const INDENT: f32 = 6.0;
+const MM_PER_PT: f32 = 0.3528;
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.0;
+const META_SIZE: f32 = 9.0;
+const TITLE_SIZE: f32 = 18.0;
+
+/// Advance widths of the printable ASCII characters in Helvetica, in
+/// thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278,
+    278, // space to /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // 0 to 9
+    278, 278, 584, 584, 584, 556, 1015, // : to @
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // A to M
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // N to Z
+    278, 278, 278, 469, 556, 333, // [ to `
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // a to m
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // n to z
+    334, 260, 334, 584, // { to ~
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+}
+
+impl Style {
+    const ALL: [Style; 5] = [
+        Style::Regular,
+        Style::Bold,
+        Style::Italic,
+        Style::BoldItalic,
+        Style::Mono,
+    ];
+
+    fn new(bold: bool, italic: bool) -> Self {
+        match (bold, italic) {
+            (false, false) => Style::Regular,
+            (true, false) => Style::Bold,
+            (false, true) => Style::Italic,
+            (true, true) => Style::BoldItalic,
+        }
+    }
+
+    fn font(self) -> BuiltinFont {
+        match self {
+            Style::Regular => BuiltinFont::Helvetica,
+            Style::Bold => BuiltinFont::HelveticaBold,
+            Style::Italic => BuiltinFont::HelveticaOblique,
+            Style::BoldItalic => BuiltinFont::HelveticaBoldOblique,
+            Style::Mono => BuiltinFont::Courier,
+        }
+    }
+}
+
+/// Width of `text` in millimetres. The builtin fonts come without metrics, so
+/// bold is taken to be 5% wider than regular, and anything outside ASCII as
+/// wide as a digit.
+fn text_width(text: &str, style: Style, size: f32) -> f32 {
+    let units: u32 = text
+        .chars()
+        .map(|c| match (style, c) {
+            (Style::Mono, _) => 600,
+            (_, ' '..='~') => HELVETICA_WIDTHS[c as usize - 32] as u32,
+            _ => 556,
+        })
+        .sum();
+    let scale = match style {
+        Style::Bold | Style::BoldItalic => 1.05,
+        _ => 1.0,
+    };
+    units as f32 / 1000.0 * size * MM_PER_PT * scale
+}
+
+fn line_height(size: f32) -> f32 {
+    size * 1.4 * MM_PER_PT
+}
+
+/// A piece of text placed on a page, `x` and `y` in millimetres from the
+/// bottom left corner.
+#[derive(Debug, Clone)]
+struct Span {
+    text: String,
+    style: Style,
+    size: f32,
+    x: f32,
+    y: f32,
+    muted: bool,
+}
+
+#[derive(Debug, Default)]
+struct Page {
+    spans: Vec<Span>,
+}
+
+impl Page {
+    fn centered(&mut self, text: &str, style: Style, size: f32, y: f32, muted: bool) {
+        self.spans.push(Span {
+            text: text.to_string(),
+            style,
+            size,
+            x: (PAGE_WIDTH - text_width(text, style, size)) / 2.0,
+            y,
+            muted,
+        });
+    }
+}
+
+/// The unit lines are broken between.
+#[derive(Debug, Clone)]
+struct Word {
+    text: String,
+    style: Style,
+    space_before: bool,
+}
+
+fn words(text: &str, style: Style) -> Vec<Word> {
+    text.split_whitespace()
+        .map(|word| Word {
+            text: word.to_string(),
+            style,
+            space_before: true,
+        })
+        .collect()
+}
+
+/// Places text on pages from top to bottom, starting a new page whenever the
+/// current one is full.
+#[derive(Debug, Default)]
+struct Layout {
+    pages: Vec<Page>,
+    y: f32,
+}
+
+impl Layout {
+    fn new_page(&mut self) {
+        self.pages.push(Page::default());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// The baseline of a line `height` below the previous one.
+    fn next_line(&mut self, height: f32) -> f32 {
+        if self.pages.is_empty() || self.y - height < MARGIN {
+            self.new_page();
+        }
+        self.y -= height;
+        self.y
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    fn push(&mut self, span: Span) {
+        if let Some(page) = self.pages.last_mut() {
+            page.spans.push(span);
+        }
+    }
+
+    /// Lays out `words` as lines no wider than the text width, `indent` in
+    /// from the margin, with `marker` hanging in front of the first line.
+    fn paragraph(
+        &mut self,
+        words: &[Word],
+        size: f32,
+        indent: f32,
+        muted: bool,
+        marker: Option<&str>,
+    ) {
+        let width = TEXT_WIDTH - indent;
+        let mut y = self.next_line(line_height(size));
+        if let Some(marker) = marker {
+            self.push(Span {
+                text: marker.to_string(),
+                style: Style::Regular,
+                size,
+                x: MARGIN + indent - text_width(marker, Style::Regular, size) - 1.5,
+                y,
+                muted,
+            });
+        }
+
+        let mut x = 0.0;
+        let mut line_empty = true;
+        for word in words {
+            let mut space_before = word.space_before;
+            for piece in split_to_width(&word.text, word.style, size, width) {
+                let space = match space_before && !line_empty {
+                    true => text_width(" ", word.style, size),
+                    false => 0.0,
+                };
+                let piece_width = text_width(&piece, word.style, size);
+                let (start, space) = match !line_empty && x + space + piece_width > width {
+                    true => {
+                        y = self.next_line(line_height(size));
+                        (0.0, 0.0)
+                    }
+                    false => (x, space),
+                };
+                self.push(Span {
+                    text: piece,
+                    style: word.style,
+                    size,
+                    x: MARGIN + indent + start + space,
+                    y,
+                    muted,
+                });
+                x = start + space + piece_width;
+                line_empty = false;
+                space_before = false;
+            }
+        }
+    }
+}
+
+/// `text` cut into pieces that fit `width`, for words longer than a line.
+fn split_to_width(text: &str, style: Style, size: f32, width: f32) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    for c in text.chars() {
+        let piece = pieces.last_mut().expect("starts with a piece");
+        let mut candidate = piece.clone();
+        candidate.push(c);
+        match !piece.is_empty() && text_width(&candidate, style, size) > width {
+            true => pieces.push(c.to_string()),
+            false => *piece = candidate,
+        }
+    }
+    pieces
+}
+
+/// Turns a Markdown body into laid out text.
+struct MarkdownLayout<'a> {
+    layout: &'a mut Layout,
+    words: Vec<Word>,
+    space: bool,
+    bold: usize,
+    italic: usize,
+    quotes: usize,
+    code_block: bool,
+    heading: Option<f32>,
+    /// The next number of each open list, `None` for bulleted ones.
+    lists: Vec<Option<u64>>,
+    marker: Option<String>,
+}
+
+impl<'a> MarkdownLayout<'a> {
+    fn new(layout: &'a mut Layout) -> Self {
+        Self {
+            layout,
+            words: Vec::new(),
+            space: false,
+            bold: 0,
+            italic: 0,
+            quotes: 0,
+            code_block: false,
+            heading: None,
+            lists: Vec::new(),
+            marker: None,
+        }
+    }
+
+    fn indent(&self) -> f32 {
+        (self.lists.len() + self.quotes) as f32 * INDENT
+    }
+
+    fn text(&mut self, text: &str, style: Style) {
+        for (i, piece) in text.split(char::is_whitespace).enumerate() {
+            if i > 0 {
+                self.space = true;
+            }
+            if piece.is_empty() {
+                continue;
+            }
+            self.words.push(Word {
+                text: piece.to_string(),
+                style,
+                space_before: self.space,
+            });
+            self.space = false;
+        }
+    }
+
+    fn inline_style(&self) -> Style {
+        Style::new(self.bold > 0 || self.heading.is_some(), self.italic > 0)
+    }
+
+    /// Lays out the text collected so far as one paragraph.
+    fn flush(&mut self, gap: bool) {
+        if self.words.is_empty() && self.marker.is_none() {
+            return;
+        }
+        let size = self.heading.unwrap_or(BODY_SIZE);
+        let indent = self.indent();
+        let muted = self.quotes > 0;
+        let marker = self.marker.take();
+        self.layout
+            .paragraph(&self.words, size, indent, muted, marker.as_deref());
+        if gap {
+            self.layout.gap(size * 0.5 * MM_PER_PT);
+        }
+        self.words.clear();
+        self.space = false;
+    }
+
+    fn code(&mut self, text: &str) {
+        let indent = self.indent() + INDENT / 2.0;
+        for line in text.lines() {
+            let word = Word {
+                text: line.replace('\t', "    "),
+                style: Style::Mono,
+                space_before: false,
+            };
+            self.layout
+                .paragraph(&[word], CODE_SIZE, indent, false, None);
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.flush(true);
+                self.layout.gap(2.0);
+                self.heading = Some(match level {
+                    HeadingLevel::H1 => 16.0,
+                    HeadingLevel::H2 => 14.0,
+                    _ => 12.0,
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                self.flush(true);
+                self.heading = None;
+            }
+            Event::End(TagEnd::Paragraph) => self.flush(true),
+            Event::Start(Tag::BlockQuote(_)) => {
+                self.flush(true);
+                self.quotes += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                self.flush(true);
+                self.quotes -= 1;
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.flush(true);
+                self.code_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                self.code_block = false;
+                self.layout.gap(BODY_SIZE * 0.5 * MM_PER_PT);
+            }
+            Event::Start(Tag::List(first)) => {
+                self.flush(false);
+                self.lists.push(first);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.flush(false);
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.layout.gap(BODY_SIZE * 0.5 * MM_PER_PT);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                self.flush(false);
+                self.marker = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "\u{2022}".to_string(),
+                });
+            }
+            Event::End(TagEnd::Item) => self.flush(false),
+            Event::TaskListMarker(checked) => {
+                self.marker = Some(if checked { "[x]" } else { "[ ]" }.to_string());
+            }
+            Event::Start(Tag::Strong) => self.bold += 1,
+            Event::End(TagEnd::Strong) => self.bold -= 1,
+            Event::Start(Tag::Emphasis) => self.italic += 1,
+            Event::End(TagEnd::Emphasis) => self.italic -= 1,
+            Event::Start(Tag::Image { .. }) => self.text("[image: ", Style::Italic),
+            Event::End(TagEnd::Image) => self.text("]", Style::Italic),
+            Event::End(TagEnd::TableCell) => {
+                self.space = true;
+                self.text("|", Style::Regular);
+                self.space = true;
+            }
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => self.flush(false),
+            Event::End(TagEnd::Table) => self.layout.gap(BODY_SIZE * 0.5 * MM_PER_PT),
+            Event::Text(text) if self.code_block => self.code(&text),
+            Event::Text(text) => self.text(&text, self.inline_style()),
+            Event::Code(text) => self.text(&text, Style::Mono),
+            Event::FootnoteReference(name) => {
+                self.text(&format!("[{}]", name), self.inline_style())
+            }
+            Event::SoftBreak => self.space = true,
+            Event::HardBreak => self.flush(false),
+            Event::Rule => {
+                self.flush(true);
+                self.layout
+                    .paragraph(&words("* * *", Style::Regular), BODY_SIZE, 0.0, true, None);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn local_time(timestamp: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|time| time.with_timezone(&Local))
+}
+
+/// A printable report: a cover page, a table of contents, then every entry
+/// starting on a new page with its tags and dates, and page numbers in the
+/// footer.
+///
+/// Uses the PDF builtin fonts, which only cover Western European characters;
+/// others are left out.
+#[derive(Debug, Default)]
+pub struct PdfExporter {
+    layout: Layout,
+    /// Title and index of the first page of every entry.
+    contents: Vec<(String, usize)>,
+    created: Vec<DateTime<Local>>,
+}
+
+impl PdfExporter {
+    fn cover(&self) -> Page {
+        let mut page = Page::default();
+        let mut y = PAGE_HEIGHT * 0.6;
+        page.centered("Journal", Style::Bold, 28.0, y, false);
+        y -= 14.0;
+        let count = match self.contents.len() {
+            1 => "1 entry".to_string(),
+            count => format!("{} entries", count),
+        };
+        page.centered(&count, Style::Regular, 14.0, y, false);
+        if let (Some(first), Some(last)) = (self.created.iter().min(), self.created.iter().max()) {
+            y -= 8.0;
+            let range = format!(
+                "{} to {}",
+                first.format("%Y-%m-%d"),
+                last.format("%Y-%m-%d")
+            );
+            page.centered(&range, Style::Regular, 12.0, y, true);
+        }
+        let exported = format!("Exported {}", Local::now().format("%Y-%m-%d %H:%M"));
+        page.centered(&exported, Style::Italic, 10.0, MARGIN + 10.0, true);
+        page
+    }
+
+    /// The table of contents, with entries numbered from `first_page`.
+    fn table_of_contents(&self, first_page: usize) -> Vec<Page> {
+        let mut layout = Layout::default();
+        layout.paragraph(
+            &words("Contents", Style::Bold),
+            TITLE_SIZE,
+            0.0,
+            false,
+            None,
+        );
+        layout.gap(4.0);
+        for (title, page) in &self.contents {
+            let number = (first_page + page + 1).to_string();
+            let number_width = text_width(&number, Style::Regular, BODY_SIZE);
+            let mut title = title.clone();
+            let available = TEXT_WIDTH - number_width - 5.0;
+            if text_width(&title, Style::Regular, BODY_SIZE) > available {
+                while text_width(&format!("{}...", title), Style::Regular, BODY_SIZE) > available {
+                    title.pop();
+                }
+                title.push_str("...");
+            }
+            let y = layout.next_line(line_height(BODY_SIZE) * 1.2);
+            layout.push(Span {
+                text: title,
+                style: Style::Regular,
+                size: BODY_SIZE,
+                x: MARGIN,
+                y,
+                muted: false,
+            });
+            layout.push(Span {
+                text: number,
+                style: Style::Regular,
+                size: BODY_SIZE,
+                x: MARGIN + TEXT_WIDTH - number_width,
+                y,
+                muted: false,
+            });
+        }
+        layout.pages
+    }
+}
+
+impl Exporter for PdfExporter {
+    fn content_type(&self) -> &'static str {
+        "application/pdf"
+    }
+
+    fn extension(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let title = journal.title.as_deref().unwrap_or("Untitled");
+        self.layout.new_page();
+        self.contents
+            .push((title.to_string(), self.layout.pages.len() - 1));
+        self.layout
+            .paragraph(&words(title, Style::Bold), TITLE_SIZE, 0.0, false, None);
+
+        let created = journal.created_at.as_deref().and_then(local_time);
+        let updated = journal.updated_at.as_deref().and_then(local_time);
+        let mut meta = Vec::new();
+        if let Some(created) = created {
+            meta.push(format!("Created {}", created.format("%Y-%m-%d %H:%M")));
+            self.created.push(created);
+        }
+        if let Some(updated) = updated.filter(|updated| Some(*updated) != created) {
+            meta.push(format!("Updated {}", updated.format("%Y-%m-%d %H:%M")));
+        }
+        if !journal.tags.is_empty() {
+            meta.push(format!("Tags: {}", journal.tags.join(", ")));
+        }
+        if !meta.is_empty() {
+            let meta = words(&meta.join("  \u{b7}  "), Style::Italic);
+            self.layout.paragraph(&meta, META_SIZE, 0.0, true, None);
+        }
+        self.layout.gap(6.0);
+
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_FOOTNOTES;
+        let body = journal.body.as_deref().unwrap_or_default();
+        let mut markdown = MarkdownLayout::new(&mut self.layout);
+        for event in Parser::new_ext(body, options) {
+            markdown.event(event);
+        }
+        markdown.flush(true);
+        Ok(Vec::new())
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>, AppError> {
+        render(&self.pages()).map_err(|err| AppError::Io(io::Error::other(err.to_string())))
+    }
+}
+
+impl PdfExporter {
+    /// Every page of the document: the cover, the table of contents and the
+    /// entries, numbered in the footer.
+    fn pages(&mut self) -> Vec<Page> {
+        let toc_pages = self.table_of_contents(0).len();
+        let mut pages = vec![self.cover()];
+        pages.extend(self.table_of_contents(1 + toc_pages));
+        pages.extend(std::mem::take(&mut self.layout.pages));
+
+        let total = pages.len();
+        for (index, page) in pages.iter_mut().enumerate().skip(1) {
+            let number = format!("Page {} of {}", index + 1, total);
+            page.centered(&number, Style::Regular, META_SIZE, FOOTER_Y, true);
+        }
+        pages
+    }
+}
+
+fn render(pages: &[Page]) -> Result<Vec<u8>, printpdf::Error> {
+    let (doc, first_page, first_layer) =
+        PdfDocument::new("Journal", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Text");
+    let fonts = Style::ALL
+        .iter()
+        .map(|style| doc.add_builtin_font(style.font()))
+        .collect::<Result<Vec<IndirectFontRef>, _>>()?;
+
+    for (index, page) in pages.iter().enumerate() {
+        let (page_index, layer_index) = match index {
+            0 => (first_page, first_layer),
+            _ => doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Text"),
+        };
+        let layer = doc.get_page(page_index).get_layer(layer_index);
+        for span in &page.spans {
+            let shade = if span.muted { 0.4 } else { 0.0 };
+            layer.set_fill_color(Color::Rgb(Rgb::new(shade, shade, shade, None)));
+            let font = &fonts[span.style as usize];
+            layer.use_text(span.text.clone(), span.size, Mm(span.x), Mm(span.y), font);
+        }
+    }
+    doc.save_to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal(id: usize, title: &str, body: &str) -> Journal {
+        let mut journal = Journal::new(
+            format!("00000000-0000-4000-8000-{:012}", id),
+            Some(title.to_string()),
+            Some(body.to_string()),
+            vec!["pdf".to_string()],
+        );
+        journal.created_at = Some("2024-03-01T09:00:00+01:00".to_string());
+        journal
+    }
+
+    fn export(journals: &[Journal]) -> PdfExporter {
+        let mut exporter = PdfExporter::default();
+        for journal in journals {
+            exporter.entry(journal).unwrap();
+        }
+        exporter
+    }
+
+    #[test]
+    fn writes_a_pdf_document() {
+        let journals = [journal(
+            1,
+            "First",
+            "Some *Markdown* with `code`.\n\n- a\n- b",
+        )];
+        let bytes = export(&journals).finish().unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+        assert!(bytes.trim_ascii_end().ends_with(b"%%EOF"));
+    }
+
+    #[test]
+    fn exports_nothing_as_a_cover_and_empty_contents() {
+        let mut exporter = PdfExporter::default();
+        let pages = exporter.pages();
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].spans.iter().any(|span| span.text == "0 entries"));
+        assert!(pages[1].spans.iter().any(|span| span.text == "Contents"));
+
+        let bytes = PdfExporter::default().finish().unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn contents_point_at_the_pages_of_the_entries() {
+        // Enough entries for two pages of contents, some of them several pages long.
+        let long = "A line of text that goes on for a while.\n\n".repeat(150);
+        let journals = (0..60)
+            .map(|id| {
+                journal(
+                    id,
+                    &format!("Entry{}", id),
+                    if id % 7 == 0 { &long } else { "Short." },
+                )
+            })
+            .collect::<Vec<Journal>>();
+        let pages = export(&journals).pages();
+
+        let contents = pages
+            .iter()
+            .skip(1)
+            .take_while(|page| {
+                !page
+                    .spans
+                    .iter()
+                    .any(|span| span.text == "Entry0" && span.size == TITLE_SIZE)
+            })
+            .flat_map(|page| page.spans.windows(2))
+            .filter(|pair| pair[0].y == pair[1].y && pair[0].text.starts_with("Entry"))
+            .map(|pair| (pair[0].text.clone(), pair[1].text.parse::<usize>().unwrap()))
+            .collect::<Vec<(String, usize)>>();
+        assert_eq!(contents.len(), journals.len());
+        assert_eq!(contents[0], ("Entry0".to_string(), 4));
+        assert!(pages.len() > 2 + journals.len());
+
+        for (title, number) in contents {
+            let page = &pages[number - 1];
+            let heading = &page.spans[0];
+            assert_eq!(
+                (heading.text.as_str(), heading.size),
+                (title.as_str(), TITLE_SIZE)
+            );
+            let footer = format!("Page {} of {}", number, pages.len());
+            assert!(page.spans.iter().any(|span| span.text == footer));
+        }
+    }
+
+    #[test]
+    fn breaks_long_words_and_titles_within_the_margins() {
+        let word = "x".repeat(400);
+        let title = format!(
+            "{} {}",
+            "Supercalifragilistic".repeat(20),
+            "and more ".repeat(30)
+        );
+        let body = format!("{}\n\n```\n{}\n```\n\n- > **{}**", word, word, word);
+        let journals = [journal(1, &title, &body), journal(2, &word, "")];
+        let mut exporter = export(&journals);
+        let pages = exporter.pages();
+
+        for span in pages.iter().flat_map(|page| &page.spans) {
+            let right = span.x + text_width(&span.text, span.style, span.size);
+            assert!(
+                span.x >= 0.0 && right <= PAGE_WIDTH - MARGIN + 0.01,
+                "{:?}",
+                span
+            );
+            assert!(
+                span.y >= FOOTER_Y && span.y <= PAGE_HEIGHT - MARGIN,
+                "{:?}",
+                span
+            );
+        }
+        assert!(render(&pages).unwrap().starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn leaves_out_characters_the_fonts_lack() {
+        let journals = [journal(1, "日記 📓", "Ünïcödé, 中文 and emoji 🎉")];
+        let bytes = export(&journals).finish().unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::io;
+
+use shared::Journal;
+
+use crate::{error::AppError, export::Exporter};
+
+/// One row per entry, tags separated by `;` and attachments by file name.
+pub struct CsvExporter;
+
+const COLUMNS: [&str; 7] = [
+    "id",
+    "title",
+    "tags",
+    "created_at",
+    "updated_at",
+    "attachments",
+    "body",
+];
+
+fn row<'a>(fields: impl IntoIterator<Item = &'a str>) -> Result<Vec<u8>, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(fields).map_err(io::Error::from)?;
+    writer
+        .into_inner()
+        .map_err(|err| AppError::Io(err.into_error()))
+}
+
+impl Exporter for CsvExporter {
+    fn content_type(&self) -> &'static str {
+        "text/csv; charset=utf-8"
+    }
+
+    fn extension(&self) -> &'static str {
+        "csv"
+    }
+
+    fn header(&mut self) -> Result<Vec<u8>, AppError> {
+        row(COLUMNS)
+    }
+
+    fn entry(&mut self, journal: &Journal) -> Result<Vec<u8>, AppError> {
+        let tags = journal.tags.join(";");
+        let attachments: Vec<&str> = journal
+            .attachments
+            .iter()
+            .map(|attachment| attachment.name.as_str())
+            .collect();
+        let attachments = attachments.join(";");
+        row([
+            journal.id.as_str(),
+            journal.title.as_deref().unwrap_or_default(),
+            &tags,
+            journal.created_at.as_deref().unwrap_or_default(),
+            journal.updated_at.as_deref().unwrap_or_default(),
+            &attachments,
+            journal.body.as_deref().unwrap_or_default(),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::Attachment;
+
+    use super::*;
+
+    #[test]
+    fn quotes_fields_that_need_it() {
+        let mut journal = Journal::new(
+            "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60".to_string(),
+            Some("Milk, eggs and \"bread\"".to_string()),
+            Some("First line\nsecond line\r\nthird".to_string()),
+            vec!["shopping".to_string(), "home".to_string()],
+        );
+        journal.attachments.push(Attachment {
+            name: "list, final.txt".to_string(),
+            ..Default::default()
+        });
+        let mut exporter = CsvExporter;
+        let mut csv = exporter.header().unwrap();
+        csv.extend(exporter.entry(&journal).unwrap());
+
+        let text = String::from_utf8(csv.clone()).unwrap();
+        assert!(text.starts_with("id,title,tags,created_at,updated_at,attachments,body\n"));
+        assert!(text.contains(",\"Milk, eggs and \"\"bread\"\"\",shopping;home,,,"));
+        assert!(text.contains(",\"list, final.txt\",\"First line\nsecond line\r\nthird\"\n"));
+
+        let mut reader = csv::Reader::from_reader(csv.as_slice());
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(&records[0][1], "Milk, eggs and \"bread\"");
+        assert_eq!(&records[0][5], "list, final.txt");
+        assert_eq!(&records[0][6], "First line\nsecond line\r\nthird");
+    }
+}
@@ -0,0 +1,25 @@
+use actix_web::{HttpResponse, post, web};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    reload::reload,
+    types::{ErrorResponse, Records, ReloadReport, Tokens},
+};
+
+#[utoipa::path(
+    tag = "admin",
+    responses(
+        (status = 200, description = "What changed and what needs a restart", body = ReloadReport),
+        (status = 400, description = "The config file is invalid, the current one is kept", body = ErrorResponse),
+    )
+)]
+#[post("/admin/reload")]
+async fn reload_config(
+    config: web::Data<LiveConfig>,
+    state: web::Data<Records>,
+    tokens: web::Data<Tokens>,
+) -> Result<HttpResponse, AppError> {
+    let report = reload(&config, &state, &tokens).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
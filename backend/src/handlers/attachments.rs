@@ -0,0 +1,147 @@
+use actix_multipart::Multipart;
+use actix_web::{HttpResponse, delete, get, http::header, post, web};
+use futures_util::StreamExt;
+use utoipa::ToSchema;
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    types::{Attachment, ErrorResponse, Records},
+};
+
+/// Multipart body of an upload. Every part carrying a file name is stored,
+/// whatever its field name.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct AttachmentUpload {
+    #[schema(value_type = Vec<String>, format = Binary)]
+    files: Vec<Vec<u8>>,
+}
+
+#[utoipa::path(
+    tag = "attachments",
+    params(("id" = String, Path, description = "Entry id")),
+    request_body(content = AttachmentUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "The stored attachments", body = Vec<Attachment>),
+        (status = 400, description = "Malformed upload or too many attachments", body = ErrorResponse),
+        (status = 404, description = "No such entry", body = ErrorResponse),
+        (status = 413, description = "A file exceeds the size limit", body = ErrorResponse),
+    )
+)]
+#[post("/entry/{id}/attachments")]
+async fn upload_attachments(
+    id: web::Path<String>,
+    mut payload: Multipart,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let config = config.current();
+    let limit = config.max_attachment_size();
+    let mut created = vec![];
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|err| AppError::BadRequest(err.to_string()))?;
+
+        let name = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+            .unwrap_or("attachment")
+            .to_string();
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.to_string())
+            .unwrap_or("application/octet-stream".to_string());
+
+        let mut data = vec![];
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|err| AppError::BadRequest(err.to_string()))?;
+            data.extend_from_slice(&chunk);
+            if data.len() as u64 > limit {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "{} exceeds the attachment size limit of {} bytes",
+                    name, limit
+                )));
+            }
+        }
+
+        let attachment = state
+            .add_attachment(&id, name, content_type, data, config.clone())
+            .await?;
+        created.push(attachment);
+    }
+
+    Ok(HttpResponse::Created().json(created))
+}
+
+#[utoipa::path(
+    tag = "attachments",
+    params(("id" = String, Path, description = "Entry id")),
+    responses(
+        (status = 200, body = Vec<Attachment>),
+        (status = 404, description = "No such entry", body = ErrorResponse),
+    )
+)]
+#[get("/entry/{id}/attachments")]
+async fn list_attachments(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let attachments = state.list_attachments(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(attachments))
+}
+
+#[utoipa::path(
+    tag = "attachments",
+    params(
+        ("id" = String, Path, description = "Entry id"),
+        ("attachment_id" = String, Path, description = "Attachment id"),
+    ),
+    responses(
+        (status = 200, description = "The decrypted file", body = String, content_type = "application/octet-stream"),
+        (status = 404, description = "No such entry or attachment", body = ErrorResponse),
+    )
+)]
+#[get("/entry/{id}/attachments/{attachment_id}")]
+async fn download_attachment(
+    path: web::Path<(String, String)>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let (id, attachment_id) = path.into_inner();
+    let (attachment, data) = state
+        .read_attachment(&id, &attachment_id, config.current())
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type(attachment.content_type)
+        .insert_header(header::ContentDisposition {
+            disposition: header::DispositionType::Attachment,
+            parameters: vec![header::DispositionParam::Filename(attachment.name)],
+        })
+        .body(data))
+}
+
+#[utoipa::path(
+    tag = "attachments",
+    params(
+        ("id" = String, Path, description = "Entry id"),
+        ("attachment_id" = String, Path, description = "Attachment id"),
+    ),
+    responses(
+        (status = 200, description = "Id of the deleted attachment", body = String),
+        (status = 404, description = "No such entry or attachment", body = ErrorResponse),
+    )
+)]
+#[delete("/entry/{id}/attachments/{attachment_id}")]
+async fn delete_attachment(
+    path: web::Path<(String, String)>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let (id, attachment_id) = path.into_inner();
+    state
+        .delete_attachment(&id, &attachment_id, config.current())
+        .await?;
+    Ok(HttpResponse::Ok().json(attachment_id))
+}
@@ -0,0 +1,109 @@
+use actix_multipart::Multipart;
+use actix_web::{HttpResponse, http::header, post, web};
+use chrono::Local;
+use futures_util::StreamExt;
+use utoipa::ToSchema;
+
+use crate::{
+    backup::{backup_file_name, read_backup, write_backup},
+    config::LiveConfig,
+    error::AppError,
+    types::{BackupRequest, ErrorResponse, Records, RestoreOptions, RestoreReport},
+};
+
+/// Multipart body of a restore.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct RestoreUpload {
+    /// The passphrase the backup was made with.
+    passphrase: String,
+    #[schema(value_type = String, format = Binary)]
+    backup: Vec<u8>,
+}
+
+#[utoipa::path(
+    tag = "backup",
+    request_body = BackupRequest,
+    responses(
+        (
+            status = 200,
+            description = "Every entry, attachment, draft and template, and the config without its secrets, as one encrypted file",
+            body = Vec<u8>,
+            content_type = "application/octet-stream",
+            headers(("Content-Disposition" = String, description = "Timestamped file name")),
+        ),
+        (status = 400, description = "The passphrase is too short", body = ErrorResponse),
+        (status = 500, description = "Storage could not be read", body = ErrorResponse),
+    )
+)]
+#[post("/backup")]
+async fn create_backup(
+    payload: web::Json<BackupRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let snapshot = state.snapshot(config.current()).await?;
+    let backup = write_backup(&snapshot, &payload.passphrase)?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .insert_header(header::ContentDisposition {
+            disposition: header::DispositionType::Attachment,
+            parameters: vec![header::DispositionParam::Filename(backup_file_name(
+                Local::now(),
+            ))],
+        })
+        .body(backup))
+}
+
+#[utoipa::path(
+    tag = "backup",
+    params(RestoreOptions),
+    request_body(content = RestoreUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "What was restored", body = RestoreReport),
+        (status = 400, description = "An unknown field, not a backup, the wrong passphrase, or a backup that does not match its manifest", body = ErrorResponse),
+    )
+)]
+#[post("/restore")]
+async fn restore_backup(
+    options: web::Query<RestoreOptions>,
+    mut payload: Multipart,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let mut passphrase = None;
+    let mut backup = None;
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|err| AppError::BadRequest(err.to_string()))?;
+        let name = field.name().unwrap_or_default().to_string();
+        if name != "passphrase" && name != "backup" {
+            return Err(AppError::BadRequest(format!(
+                "Unexpected field `{}`, a restore takes `passphrase` and `backup`",
+                name
+            )));
+        }
+        let mut data = vec![];
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|err| AppError::BadRequest(err.to_string()))?;
+            data.extend_from_slice(&chunk);
+        }
+        match name.as_str() {
+            "passphrase" => {
+                passphrase = Some(String::from_utf8(data).map_err(|_| {
+                    AppError::BadRequest("The passphrase is not UTF-8 text".to_string())
+                })?)
+            }
+            _ => backup = Some(data),
+        }
+    }
+
+    let passphrase =
+        passphrase.ok_or_else(|| AppError::BadRequest("Missing passphrase".to_string()))?;
+    let backup = backup.ok_or_else(|| AppError::BadRequest("Missing backup file".to_string()))?;
+    let snapshot = read_backup(&backup, &passphrase)?;
+    let report = state
+        .restore(snapshot, options.mode, config.current())
+        .await?;
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -0,0 +1,46 @@
+use actix_web::{HttpRequest, HttpResponse, get, web};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    export::{Exporter, IcsExporter, export_stream},
+    types::{EntryFilter, ErrorResponse, Records},
+};
+
+/// The feed holds every task, so it is only served to clients on this
+/// machine. Requests over a unix socket have no peer address.
+fn is_local(req: &HttpRequest) -> bool {
+    req.peer_addr()
+        .is_none_or(|addr| addr.ip().to_canonical().is_loopback())
+}
+
+#[utoipa::path(
+    tag = "calendar",
+    params(
+        EntryFilter,
+        ("token" = Option<String>, Query, description = "Token for calendar apps that can't send an `Authorization` header; a read-only one is best"),
+    ),
+    responses(
+        (status = 200, description = "Task entries as VTODOs, with a VEVENT on each due date", body = String, content_type = "text/calendar"),
+        (status = 400, description = "Malformed filter", body = ErrorResponse),
+        (status = 403, description = "The request did not come from this machine", body = ErrorResponse),
+    )
+)]
+#[get("/calendar.ics")]
+async fn calendar_feed(
+    req: HttpRequest,
+    filter: web::Query<EntryFilter>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    if !is_local(&req) {
+        return Err(AppError::Forbidden(
+            "The calendar feed is only served locally".to_string(),
+        ));
+    }
+    let journals = state.export(&filter, config.current()).await?;
+    let exporter = IcsExporter::default();
+    Ok(HttpResponse::Ok()
+        .content_type(exporter.content_type())
+        .streaming(export_stream(Box::new(exporter), journals)))
+}
@@ -0,0 +1,92 @@
+use actix_web::{HttpResponse, delete, get, post, put, web};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    types::{Draft, DraftRequest, ErrorResponse, Records},
+};
+
+#[utoipa::path(
+    tag = "drafts",
+    responses((status = 200, description = "Drafts, most recently saved first", body = Vec<Draft>))
+)]
+#[get("/drafts")]
+async fn list_drafts(
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let drafts = state.list_drafts(config.current()).await?;
+    Ok(HttpResponse::Ok().json(drafts))
+}
+
+#[utoipa::path(
+    tag = "drafts",
+    request_body = DraftRequest,
+    responses((status = 201, body = Draft))
+)]
+#[post("/drafts")]
+async fn create_draft(
+    payload: web::Json<DraftRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let draft = state
+        .insert_draft(payload.into_inner(), config.current())
+        .await?;
+    Ok(HttpResponse::Created().json(draft))
+}
+
+#[utoipa::path(
+    tag = "drafts",
+    params(("id" = String, Path, description = "Draft id")),
+    responses(
+        (status = 200, body = Draft),
+        (status = 404, description = "No such draft", body = ErrorResponse),
+    )
+)]
+#[get("/drafts/{id}")]
+async fn get_draft(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let draft = state.find_draft(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(draft))
+}
+
+#[utoipa::path(
+    tag = "drafts",
+    params(("id" = String, Path, description = "Draft id")),
+    request_body = DraftRequest,
+    responses((status = 200, body = Draft))
+)]
+#[put("/drafts/{id}")]
+async fn save_draft(
+    id: web::Path<String>,
+    payload: web::Json<DraftRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let draft = state
+        .save_draft(&id, payload.into_inner(), config.current())
+        .await?;
+    Ok(HttpResponse::Ok().json(draft))
+}
+
+#[utoipa::path(
+    tag = "drafts",
+    params(("id" = String, Path, description = "Draft id")),
+    responses(
+        (status = 200, description = "Id of the deleted draft", body = String),
+        (status = 404, description = "No such draft", body = ErrorResponse),
+    )
+)]
+#[delete("/drafts/{id}")]
+async fn delete_draft(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    state.delete_draft(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(id.to_string()))
+}
@@ -0,0 +1,51 @@
+use actix_web::{HttpResponse, get, post, web};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    types::{ErrorResponse, HistoryCommit, Journal, Records},
+};
+
+#[utoipa::path(
+    tag = "history",
+    params(("id" = String, Path, description = "Entry id")),
+    responses(
+        (status = 200, description = "Commits that created, changed or deleted the entry, newest first", body = Vec<HistoryCommit>),
+        (status = 400, description = "History is off", body = ErrorResponse),
+        (status = 404, description = "No commit touches the entry", body = ErrorResponse),
+    )
+)]
+#[get("/history/{id}")]
+async fn get_history(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let commits = state.history(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(commits))
+}
+
+#[utoipa::path(
+    tag = "history",
+    params(
+        ("id" = String, Path, description = "Entry id"),
+        ("commit" = String, Path, description = "Commit hash, possibly abbreviated"),
+    ),
+    responses(
+        (status = 200, description = "The entry as restored, also when it had been deleted", body = Journal),
+        (status = 400, description = "History is off, or not a commit hash", body = ErrorResponse),
+        (status = 404, description = "No such commit, or the entry is not in it", body = ErrorResponse),
+    )
+)]
+#[post("/history/{id}/{commit}/restore")]
+async fn restore_version(
+    path: web::Path<(String, String)>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let (id, commit) = path.into_inner();
+    let journal = state
+        .restore_version(&id, &commit, config.current())
+        .await?;
+    Ok(HttpResponse::Ok().json(journal))
+}
@@ -0,0 +1,73 @@
+use actix_multipart::Multipart;
+use actix_web::{HttpResponse, post, web};
+use futures_util::StreamExt;
+use utoipa::ToSchema;
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    import::importer_for,
+    types::{ErrorResponse, ImportOptions, ImportReport, Records},
+};
+
+/// Multipart body of an import. Every part is read as a file of the chosen
+/// format, whatever its field name.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+struct ImportUpload {
+    #[schema(value_type = Vec<String>, format = Binary)]
+    files: Vec<Vec<u8>>,
+}
+
+#[utoipa::path(
+    tag = "import",
+    params(ImportOptions),
+    request_body(content = ImportUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "What a dry run would import", body = ImportReport),
+        (status = 201, description = "The created and skipped entries", body = ImportReport),
+        (status = 400, description = "Malformed upload or a file not in the chosen format", body = ErrorResponse),
+        (status = 413, description = "A file exceeds the size limit", body = ErrorResponse),
+    )
+)]
+#[post("/import")]
+async fn import_entries(
+    options: web::Query<ImportOptions>,
+    mut payload: Multipart,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let config = config.current();
+    let limit = config.max_import_size();
+    let importer = importer_for(options.format);
+    let mut entries = vec![];
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|err| AppError::BadRequest(err.to_string()))?;
+        let name = field
+            .content_disposition()
+            .and_then(|disposition| disposition.get_filename())
+            .unwrap_or("import")
+            .to_string();
+
+        let mut data = vec![];
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|err| AppError::BadRequest(err.to_string()))?;
+            data.extend_from_slice(&chunk);
+            if data.len() as u64 > limit {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "{} exceeds the import size limit of {} bytes",
+                    name, limit
+                )));
+            }
+        }
+
+        entries.extend(importer.parse(&name, &data)?);
+    }
+
+    let report = state.import(entries, options.dry_run, config).await?;
+    Ok(match report.dry_run {
+        true => HttpResponse::Ok().json(report),
+        false => HttpResponse::Created().json(report),
+    })
+}
@@ -0,0 +1,38 @@
+use actix_web::{HttpResponse, get, web};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    types::{ErrorResponse, Graph, GraphNode, Records},
+};
+
+#[utoipa::path(
+    tag = "links",
+    params(("id" = String, Path, description = "Entry id")),
+    responses(
+        (status = 200, description = "Entries linking to this one", body = Vec<GraphNode>),
+        (status = 404, description = "No such entry", body = ErrorResponse),
+    )
+)]
+#[get("/entry/{id}/backlinks")]
+async fn get_backlinks(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let backlinks = state.backlinks(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(backlinks))
+}
+
+#[utoipa::path(
+    tag = "links",
+    responses((status = 200, body = Graph))
+)]
+#[get("/graph")]
+async fn get_graph(
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let graph = state.graph(config.current()).await?;
+    Ok(HttpResponse::Ok().json(graph))
+}
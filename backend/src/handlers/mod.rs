@@ -1,107 +1,200 @@
-use std::path::Path;
-
-use actix_files::NamedFile;
-use actix_web::{HttpRequest, HttpResponse, Responder, delete, get, http::header, post, web};
-use serde::Deserialize;
+use actix_web::{HttpResponse, Responder, delete, get, http::header, post, put, web};
+use chrono::Local;
+use serde::Serialize;
+use utoipa::ToSchema;
 
 use crate::{
-    types::{Config, CreateUpdateRequest, Records, SystemInfo},
+    config::LiveConfig,
+    error::AppError,
+    export::{export_stream, exporter_for},
+    types::{
+        CreateUpdateRequest, EntryFilter, ErrorResponse, ExportOptions, Journal, Records,
+        SystemInfo,
+    },
     utils::{get_disk_details, get_top_5_process_info},
 };
 
+mod admin;
+mod attachments;
+mod backup;
+mod calendar;
+mod drafts;
+mod history;
+mod import;
+mod links;
+mod templates;
+mod tokens;
+
+pub use admin::*;
+pub use attachments::*;
+pub use backup::*;
+pub use calendar::*;
+pub use drafts::*;
+pub use history::*;
+pub use import::*;
+pub use links::*;
+pub use templates::*;
+pub use tokens::*;
+
+#[utoipa::path(
+    tag = "entries",
+    request_body = CreateUpdateRequest,
+    responses(
+        (status = 201, description = "Id of the created entry", body = String),
+        (status = 400, description = "Malformed request", body = ErrorResponse),
+    )
+)]
 #[post("/entry")]
 async fn create_journal(
     payload: web::Json<CreateUpdateRequest>,
     state: web::Data<Records>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    match state
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let id = state
         .insert(
             payload.title.clone(),
             payload.body.clone(),
             payload.tags.clone(),
-            config.into_inner(),
+            config.current(),
         )
-        .await
-    {
-        Ok(id) => HttpResponse::Created().json(id),
-        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
-    }
+        .await?;
+    Ok(HttpResponse::Created().json(id))
 }
 
+#[utoipa::path(
+    tag = "entries",
+    params(("id" = String, Path, description = "Entry id")),
+    responses(
+        (status = 200, body = Journal),
+        (status = 404, description = "No such entry", body = ErrorResponse),
+    )
+)]
 #[get("/entry/{id}")]
 async fn get_by_id(
     id: web::Path<String>,
     state: web::Data<Records>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    match state.find_by_id(&id, config.into_inner()).await {
-        Ok(journal) => HttpResponse::Ok().json(journal),
-        Err(err) => HttpResponse::NotFound().body(err.to_string()).into(),
-    }
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let journal = state.find_by_id(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(journal))
 }
 
-#[derive(Deserialize)]
-struct QueryTag {
-    tag: Option<String>,
+#[utoipa::path(
+    tag = "entries",
+    params(("id" = String, Path, description = "Entry id")),
+    request_body = CreateUpdateRequest,
+    responses(
+        (status = 200, description = "The updated entry", body = Journal),
+        (status = 404, description = "No such entry", body = ErrorResponse),
+    )
+)]
+#[put("/entry/{id}")]
+async fn update_journal(
+    id: web::Path<String>,
+    payload: web::Json<CreateUpdateRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let payload = payload.into_inner();
+    let journal = state
+        .update(
+            &id,
+            payload.title,
+            payload.body,
+            payload.tags,
+            config.current(),
+        )
+        .await?;
+    Ok(HttpResponse::Ok().json(journal))
 }
 
+#[utoipa::path(
+    tag = "entries",
+    params(EntryFilter),
+    responses(
+        (status = 200, body = Vec<Journal>),
+        (status = 500, description = "Storage could not be read", body = ErrorResponse),
+    )
+)]
 #[get("/entries")]
 async fn get_all(
-    tag: web::Query<QueryTag>,
+    filter: web::Query<EntryFilter>,
     state: web::Data<Records>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    let query = tag.into_inner();
-    match state.find_by_tag(query.tag, config.into_inner()).await {
-        Ok(journals) => HttpResponse::Ok().json(journals),
-        Err(err) => HttpResponse::from_error(err),
-    }
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let journals = state.find(&filter, config.current()).await?;
+    Ok(HttpResponse::Ok().json(journals))
 }
 
+#[utoipa::path(
+    tag = "entries",
+    params(("id" = String, Path, description = "Entry id")),
+    responses(
+        (status = 200, description = "Id of the deleted entry", body = String),
+        (status = 404, description = "No such entry", body = ErrorResponse),
+    )
+)]
 #[delete("/entry/{id}")]
 async fn delete_by_id(
     id: web::Path<String>,
     state: web::Data<Records>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    match state.delete_by_id(&id, config.into_inner()).await {
-        Ok(_) => HttpResponse::Ok().json(id.to_string()),
-        Err(err) => HttpResponse::from_error(err),
-    }
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    state.delete_by_id(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(id.to_string()))
 }
 
-#[post("/export")]
+#[utoipa::path(
+    tag = "entries",
+    params(EntryFilter, ExportOptions),
+    responses(
+        (
+            status = 200,
+            description = "The entries as one file in the requested format",
+            content(
+                (String = "text/markdown"),
+                (String = "application/jsonl"),
+                (String = "text/csv"),
+                (String = "text/html"),
+                (Vec<u8> = "application/pdf"),
+                (Vec<u8> = "application/zip"),
+            ),
+            headers(("Content-Disposition" = String, description = "Timestamped file name")),
+        ),
+        (status = 500, description = "Storage could not be read", body = ErrorResponse),
+    )
+)]
+#[get("/export")]
 async fn export(
-    req: HttpRequest,
+    filter: web::Query<EntryFilter>,
+    options: web::Query<ExportOptions>,
     state: web::Data<Records>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    let file_name = "journal.md";
-
-    if let Err(e) = state.export(file_name, config.into_inner()).await {
-        return HttpResponse::InternalServerError().body(format!("Failed to export file: {}", e));
-    }
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let config = config.current();
+    let journals = state.export(&filter, config.clone()).await?;
+    let exporter = exporter_for(&options, config);
+    let file_name = format!(
+        "journal-{}.{}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        exporter.extension()
+    );
+    let content_type = exporter.content_type();
 
-    if Path::new(file_name).exists() {
-        match NamedFile::open_async(file_name).await {
-            Ok(mut file) => {
-                file = file.set_content_disposition(header::ContentDisposition {
-                    disposition: header::DispositionType::Attachment,
-                    parameters: vec![header::DispositionParam::Filename(file_name.to_string())],
-                });
-                return file.into_response(&req);
-            }
-            Err(e) => {
-                return HttpResponse::InternalServerError()
-                    .body(format!("Failed to open file: {}", e));
-            }
-        }
-    } else {
-        return HttpResponse::NotFound().body("File not found");
-    }
+    // Rendered entry by entry as the body is sent, nothing is written to disk.
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(header::ContentDisposition {
+            disposition: header::DispositionType::Attachment,
+            parameters: vec![header::DispositionParam::Filename(file_name)],
+        })
+        .streaming(export_stream(exporter, journals)))
 }
 
+#[utoipa::path(
+    tag = "system",
+    responses((status = 200, body = SystemInfo))
+)]
 #[get("/sysinfo")]
 async fn system_info() -> impl Responder {
     let disk_usage = get_disk_details();
@@ -115,3 +208,18 @@ async fn system_info() -> impl Responder {
         top_cpu_processes,
     })
 }
+
+#[derive(Serialize, ToSchema)]
+struct Health {
+    status: &'static str,
+}
+
+#[utoipa::path(
+    tag = "system",
+    security(()),
+    responses((status = 200, description = "The server is up", body = Health))
+)]
+#[get("/health")]
+async fn health() -> impl Responder {
+    HttpResponse::Ok().json(Health { status: "ok" })
+}
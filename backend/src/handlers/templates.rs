@@ -0,0 +1,121 @@
+use actix_web::{HttpResponse, delete, get, post, put, web};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    types::{
+        CreateUpdateRequest, ErrorResponse, Records, RenderTemplateRequest, Template,
+        TemplateRequest,
+    },
+};
+
+#[utoipa::path(
+    tag = "templates",
+    responses((status = 200, description = "Templates sorted by name", body = Vec<Template>))
+)]
+#[get("/templates")]
+async fn list_templates(
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let templates = state.list_templates(config.current()).await?;
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+#[utoipa::path(
+    tag = "templates",
+    request_body = TemplateRequest,
+    responses((status = 201, body = Template))
+)]
+#[post("/templates")]
+async fn create_template(
+    payload: web::Json<TemplateRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let template = state
+        .insert_template(payload.into_inner(), config.current())
+        .await?;
+    Ok(HttpResponse::Created().json(template))
+}
+
+#[utoipa::path(
+    tag = "templates",
+    params(("id" = String, Path, description = "Template id")),
+    responses(
+        (status = 200, body = Template),
+        (status = 404, description = "No such template", body = ErrorResponse),
+    )
+)]
+#[get("/templates/{id}")]
+async fn get_template(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let template = state.find_template(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(template))
+}
+
+#[utoipa::path(
+    tag = "templates",
+    params(("id" = String, Path, description = "Template id")),
+    request_body = TemplateRequest,
+    responses(
+        (status = 200, body = Template),
+        (status = 404, description = "No such template", body = ErrorResponse),
+    )
+)]
+#[put("/templates/{id}")]
+async fn update_template(
+    id: web::Path<String>,
+    payload: web::Json<TemplateRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let template = state
+        .update_template(&id, payload.into_inner(), config.current())
+        .await?;
+    Ok(HttpResponse::Ok().json(template))
+}
+
+#[utoipa::path(
+    tag = "templates",
+    params(("id" = String, Path, description = "Template id")),
+    responses(
+        (status = 200, description = "Id of the deleted template", body = String),
+        (status = 404, description = "No such template", body = ErrorResponse),
+    )
+)]
+#[delete("/templates/{id}")]
+async fn delete_template(
+    id: web::Path<String>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    state.delete_template(&id, config.current()).await?;
+    Ok(HttpResponse::Ok().json(id.to_string()))
+}
+
+#[utoipa::path(
+    tag = "templates",
+    params(("id" = String, Path, description = "Template id")),
+    request_body = RenderTemplateRequest,
+    responses(
+        (status = 200, description = "An entry request with the placeholders filled in", body = CreateUpdateRequest),
+        (status = 400, description = "Variables are missing", body = ErrorResponse),
+        (status = 404, description = "No such template", body = ErrorResponse),
+    )
+)]
+#[post("/templates/{id}/render")]
+async fn render_template(
+    id: web::Path<String>,
+    payload: web::Json<RenderTemplateRequest>,
+    state: web::Data<Records>,
+    config: web::Data<LiveConfig>,
+) -> Result<HttpResponse, AppError> {
+    let request = state
+        .render_template(&id, payload.into_inner(), config.current())
+        .await?;
+    Ok(HttpResponse::Ok().json(request))
+}
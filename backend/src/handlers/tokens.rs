@@ -0,0 +1,58 @@
+use actix_web::{HttpResponse, delete, get, post, web};
+
+use crate::{
+    error::AppError,
+    types::{ApiToken, CreatedToken, ErrorResponse, TokenRequest, Tokens},
+};
+
+#[utoipa::path(
+    tag = "tokens",
+    responses((status = 200, body = Vec<ApiToken>))
+)]
+#[get("/tokens")]
+async fn list_tokens(tokens: web::Data<Tokens>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(tokens.list().await))
+}
+
+#[utoipa::path(
+    tag = "tokens",
+    request_body = TokenRequest,
+    responses(
+        (status = 201, description = "The new token, including its secret", body = CreatedToken),
+        (status = 400, description = "The name is empty", body = ErrorResponse),
+    )
+)]
+#[post("/tokens")]
+async fn create_token(
+    payload: web::Json<TokenRequest>,
+    tokens: web::Data<Tokens>,
+) -> Result<HttpResponse, AppError> {
+    let created = tokens.create(payload.into_inner()).await?;
+    Ok(HttpResponse::Created().json(created))
+}
+
+#[utoipa::path(
+    tag = "tokens",
+    responses((status = 200, description = "The token the request was made with", body = ApiToken))
+)]
+#[get("/tokens/current")]
+async fn current_token(token: web::ReqData<ApiToken>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(token.into_inner()))
+}
+
+#[utoipa::path(
+    tag = "tokens",
+    params(("id" = String, Path, description = "Token id")),
+    responses(
+        (status = 200, description = "Id of the revoked token", body = String),
+        (status = 404, description = "No such token", body = ErrorResponse),
+    )
+)]
+#[delete("/tokens/{id}")]
+async fn revoke_token(
+    id: web::Path<String>,
+    tokens: web::Data<Tokens>,
+) -> Result<HttpResponse, AppError> {
+    tokens.revoke(&id).await?;
+    Ok(HttpResponse::Ok().json(id.to_string()))
+}
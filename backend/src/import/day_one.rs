@@ -0,0 +1,143 @@
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    import::{Importer, IncomingEntry, is_zip, unzip},
+};
+
+/// A Day One JSON export, or the zip Day One puts it in. The first line of
+/// an entry becomes its title.
+pub struct DayOneImporter;
+
+#[derive(Deserialize)]
+struct Export {
+    entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    uuid: Option<String>,
+    creation_date: Option<String>,
+    modified_date: Option<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    starred: bool,
+}
+
+/// Day One escapes Markdown punctuation in its exports.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(next)) if next.is_ascii_punctuation() => {
+                unescaped.push(*next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn entries(name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+    let export: Export = serde_json::from_slice(data).map_err(|err| {
+        AppError::BadRequest(format!("{} is not a Day One export: {}", name, err))
+    })?;
+    Ok(export
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let text = unescape(&entry.text);
+            let (first, rest) = text.trim().split_once('\n').unwrap_or((text.trim(), ""));
+            let mut tags = entry.tags;
+            if entry.starred {
+                tags.push("starred".to_string());
+            }
+            IncomingEntry {
+                id: entry.uuid,
+                title: Some(first.trim_start_matches('#').trim().to_string())
+                    .filter(|title| !title.is_empty()),
+                body: Some(rest.trim().to_string()).filter(|body| !body.is_empty()),
+                tags,
+                created_at: entry.creation_date,
+                updated_at: entry.modified_date,
+            }
+        })
+        .collect())
+}
+
+impl Importer for DayOneImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        if !is_zip(data) {
+            return entries(name, data);
+        }
+        let mut imported = vec![];
+        for file in unzip(name, data)? {
+            if file.path.ends_with(".json") {
+                imported.extend(entries(&file.path, &file.data)?);
+            }
+        }
+        Ok(imported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::tests::zip;
+
+    const EXPORT: &str = include_str!("fixtures/day_one.json");
+
+    #[test]
+    fn unescapes_markdown_punctuation() {
+        assert_eq!(unescape(r"1\. \*bold\* \\ \#tag"), r"1. *bold* \ #tag");
+        assert_eq!(
+            unescape(r"C:\Users and a trailing \"),
+            r"C:\Users and a trailing \"
+        );
+    }
+
+    #[test]
+    fn titles_entries_by_their_first_line() {
+        let entries = DayOneImporter
+            .parse("Journal.json", EXPORT.as_bytes())
+            .unwrap();
+        assert_eq!(
+            entries,
+            [
+                IncomingEntry {
+                    id: Some("5E3B1F0C2A9D4C8E9B7A6D5C4B3A2F10".to_string()),
+                    title: Some("Trip to Lisbon".to_string()),
+                    body: Some(
+                        "Pastéis de nata - the best! See [the map](https://example.com/lisbon)."
+                            .to_string()
+                    ),
+                    tags: vec!["travel".to_string(), "starred".to_string()],
+                    created_at: Some("2024-03-05T09:30:00Z".to_string()),
+                    updated_at: Some("2024-03-05T10:00:00Z".to_string()),
+                },
+                IncomingEntry {
+                    title: Some("Just one line".to_string()),
+                    created_at: Some("2024-03-06T08:00:00Z".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_the_json_files_of_a_zip() {
+        let data = zip(&[("Journal.json", EXPORT), ("photos/a.jpeg", "not json")]);
+        assert_eq!(DayOneImporter.parse("export.zip", &data).unwrap().len(), 3);
+        assert!(matches!(
+            DayOneImporter.parse("Journal.json", b"{\"entries\": 1}"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+/// A front matter value. Note tools only write flat text and list fields,
+/// anything nested is read as empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrontMatterValue {
+    Empty,
+    Text(String),
+    List(Vec<String>),
+}
+
+/// The fields of a YAML front matter block, read by hand for the subset of
+/// YAML that note tools write: `key: value` lines with plain, quoted or block
+/// scalars, and `[a, b]` or `- a` lists.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    fields: Vec<(String, FrontMatterValue)>,
+}
+
+/// How the lines indented below a key are read.
+enum Continuation {
+    /// After `key:`: list items, or a nested mapping that is ignored.
+    Block,
+    /// After `key: |` or `key: >`: the lines of the text, and whether they
+    /// are folded into one and the last line break kept.
+    Scalar { folded: bool, keep_break: bool },
+    /// After a plain value, which may go on over several lines.
+    Plain,
+    /// After a quoted value or a list, which end on their line.
+    None,
+}
+
+/// The value of a double quoted scalar, whose escapes are those of JSON plus
+/// a few more.
+fn double_quoted(text: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            c @ ('x' | 'u' | 'U') => {
+                let digits = match c {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let code: String = chars.by_ref().take(digits).collect();
+                char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+            }
+            c => c,
+        };
+        value.push(escaped);
+    }
+    Some(value)
+}
+
+/// The scalar at the start of `text` and what follows it, or `None` if a
+/// quote is left open.
+fn scalar(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start();
+    if let Some(rest) = text.strip_prefix('"') {
+        let mut escaped = false;
+        let end = rest.char_indices().find_map(|(i, c)| {
+            let closes = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            closes.then_some(i)
+        })?;
+        return Some((double_quoted(&rest[..end])?, &rest[end + 1..]));
+    }
+    if let Some(rest) = text.strip_prefix('\'') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\'' if chars.peek().is_some_and(|&(_, next)| next == '\'') => {
+                    chars.next();
+                    value.push('\'');
+                }
+                '\'' => return Some((value, &rest[i + 1..])),
+                c => value.push(c),
+            }
+        }
+        return None;
+    }
+    Some((text.to_string(), ""))
+}
+
+/// `text` without a trailing ` # comment`.
+fn without_comment(text: &str) -> &str {
+    let end = text
+        .match_indices('#')
+        .find(|&(i, _)| i == 0 || text[..i].ends_with([' ', '\t']))
+        .map_or(text.len(), |(i, _)| i);
+    text[..end].trim()
+}
+
+fn plain(text: &str) -> FrontMatterValue {
+    match without_comment(text) {
+        "" | "~" | "null" | "Null" | "NULL" => FrontMatterValue::Empty,
+        text => FrontMatterValue::Text(text.to_string()),
+    }
+}
+
+/// A list item or a value after a key: quoted, or plain up to a comment.
+fn item(text: &str) -> Option<FrontMatterValue> {
+    let text = text.trim();
+    if !text.starts_with(['"', '\'']) {
+        return Some(plain(text));
+    }
+    let (value, rest) = scalar(text)?;
+    match without_comment(rest) {
+        "" => Some(FrontMatterValue::Text(value)),
+        _ => None,
+    }
+}
+
+/// The items of a `[a, "b, c"]` list, the brackets already removed.
+fn flow_list(text: &str) -> Option<Vec<String>> {
+    let mut items = vec![];
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let (value, after) = match rest.starts_with(['"', '\'']) {
+            true => scalar(rest)?,
+            false => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                (rest[..end].trim().to_string(), &rest[end..])
+            }
+        };
+        if !value.is_empty() {
+            items.push(value);
+        }
+        let after = after.trim_start();
+        rest = match after.strip_prefix(',') {
+            Some(after) => after.trim_start(),
+            None if after.is_empty() => after,
+            None => return None,
+        };
+    }
+    Some(items)
+}
+
+impl FrontMatter {
+    /// Reads `text`, the lines between the `---` markers, or returns `None`
+    /// if it is not a mapping of fields.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut front_matter = FrontMatter::default();
+        let mut continuation = Continuation::None;
+        let mut block: Vec<&str> = vec![];
+
+        for line in text.lines() {
+            let indented = line.starts_with([' ', '\t']);
+            let trimmed = line.trim();
+            let continues = indented
+                || (matches!(continuation, Continuation::Block) && trimmed.starts_with('-'));
+            if continues && !matches!(continuation, Continuation::None) {
+                block.push(line);
+                continue;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                if matches!(continuation, Continuation::Scalar { .. }) {
+                    block.push(line);
+                }
+                continue;
+            }
+            if indented {
+                return None;
+            }
+            front_matter.finish_field(&continuation, &block)?;
+            block.clear();
+
+            let (key, value) = match trimmed.split_once(": ") {
+                Some((key, value)) => (key, value.trim()),
+                None => (trimmed.strip_suffix(':')?, ""),
+            };
+            let key = match key.starts_with(['"', '\'']) {
+                true => scalar(key)?.0,
+                false => key.trim().to_string(),
+            };
+            if key.is_empty() || key.starts_with(['-', '[', '{']) {
+                return None;
+            }
+
+            let value_text = without_comment(value);
+            let (value, next) = if value.starts_with(['"', '\'']) {
+                (item(value)?, Continuation::None)
+            } else if value_text.is_empty() {
+                (FrontMatterValue::Empty, Continuation::Block)
+            } else if let Some(indicator) = value_text.strip_prefix(['|', '>']) {
+                let continuation = Continuation::Scalar {
+                    folded: value_text.starts_with('>'),
+                    keep_break: !indicator.contains('-'),
+                };
+                (FrontMatterValue::Text(String::new()), continuation)
+            } else if let Some(list) = value_text.strip_prefix('[') {
+                let items = flow_list(list.strip_suffix(']')?)?;
+                (FrontMatterValue::List(items), Continuation::None)
+            } else if value_text.starts_with('{') {
+                (FrontMatterValue::Empty, Continuation::None)
+            } else {
+                (plain(value), Continuation::Plain)
+            };
+            front_matter.fields.push((key, value));
+            continuation = next;
+        }
+        front_matter.finish_field(&continuation, &block)?;
+        Some(front_matter)
+    }
+
+    /// Reads the lines indented below the last field into its value.
+    fn finish_field(&mut self, continuation: &Continuation, block: &[&str]) -> Option<()> {
+        let Some((_, value)) = self.fields.last_mut() else {
+            return Some(());
+        };
+        match continuation {
+            Continuation::Block if !block.is_empty() => {
+                let items = block
+                    .iter()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(|line| line.strip_prefix('-').map(item))
+                    .collect::<Option<Option<Vec<FrontMatterValue>>>>();
+                *value = match items {
+                    Some(items) => FrontMatterValue::List(
+                        items?
+                            .into_iter()
+                            .filter_map(|item| match item {
+                                FrontMatterValue::Text(text) => Some(text),
+                                _ => None,
+                            })
+                            .collect(),
+                    ),
+                    // A nested mapping.
+                    None => FrontMatterValue::Empty,
+                };
+            }
+            Continuation::Scalar { folded, keep_break } => {
+                let indent = block
+                    .iter()
+                    .find(|line| !line.trim().is_empty())
+                    .map_or(0, |line| line.len() - line.trim_start().len());
+                let lines: Vec<&str> = block
+                    .iter()
+                    .map(|line| line.get(indent..).unwrap_or_default().trim_end())
+                    .collect();
+                let mut text = lines.join(if *folded { " " } else { "\n" });
+                text.truncate(text.trim_end().len());
+                if *keep_break && !text.is_empty() {
+                    text.push('\n');
+                }
+                *value = FrontMatterValue::Text(text);
+            }
+            Continuation::Plain if !block.is_empty() => {
+                if let FrontMatterValue::Text(text) = value {
+                    for line in block {
+                        if let FrontMatterValue::Text(more) = plain(line) {
+                            text.push(' ');
+                            text.push_str(&more);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Some(())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&FrontMatterValue> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> FrontMatterValue {
+        FrontMatterValue::Text(value.to_string())
+    }
+
+    fn list(items: &[&str]) -> FrontMatterValue {
+        FrontMatterValue::List(items.iter().map(|item| item.to_string()).collect())
+    }
+
+    #[test]
+    fn reads_scalars() {
+        let front_matter = FrontMatter::parse(
+            "id: \"6f1c\\\"2f\\u00e9\"\n\
+             title: 'It''s: here' # note\n\
+             plain: Plans: Q2 # comment\n\
+             number: 12\n\
+             empty:\n\
+             null: ~\n\
+             hash: #not a value",
+        )
+        .unwrap();
+        assert_eq!(front_matter.get("id"), Some(&text("6f1c\"2fé")));
+        assert_eq!(front_matter.get("title"), Some(&text("It's: here")));
+        assert_eq!(front_matter.get("plain"), Some(&text("Plans: Q2")));
+        assert_eq!(front_matter.get("number"), Some(&text("12")));
+        for key in ["empty", "null", "hash"] {
+            assert_eq!(front_matter.get(key), Some(&FrontMatterValue::Empty));
+        }
+        assert!(front_matter.contains_key("empty"));
+        assert!(!front_matter.contains_key("missing"));
+    }
+
+    #[test]
+    fn reads_lists() {
+        let front_matter = FrontMatter::parse(
+            "flow: [a, \"b, c\", 'd']\n\
+             none: []\n\
+             block:\n  - one\n  - \"two\"\n\n  # comment\n  - three # note\n\
+             flush:\n- x\n- y\n\
+             nested:\n  key: value\n\
+             after: done",
+        )
+        .unwrap();
+        assert_eq!(front_matter.get("flow"), Some(&list(&["a", "b, c", "d"])));
+        assert_eq!(front_matter.get("none"), Some(&list(&[])));
+        assert_eq!(
+            front_matter.get("block"),
+            Some(&list(&["one", "two", "three"]))
+        );
+        assert_eq!(front_matter.get("flush"), Some(&list(&["x", "y"])));
+        assert_eq!(front_matter.get("nested"), Some(&FrontMatterValue::Empty));
+        assert_eq!(front_matter.get("after"), Some(&text("done")));
+    }
+
+    #[test]
+    fn reads_multi_line_text() {
+        let front_matter = FrontMatter::parse(
+            "literal: |\n  one\n    two\n\n  three\n\
+             folded: >-\n  one\n  two\n\
+             plain: one\n  two",
+        )
+        .unwrap();
+        assert_eq!(
+            front_matter.get("literal"),
+            Some(&text("one\n  two\n\nthree\n"))
+        );
+        assert_eq!(front_matter.get("folded"), Some(&text("one two")));
+        assert_eq!(front_matter.get("plain"), Some(&text("one two")));
+    }
+
+    #[test]
+    fn rejects_what_is_no_mapping() {
+        assert_eq!(FrontMatter::parse(""), Some(FrontMatter::default()));
+        assert_eq!(
+            FrontMatter::parse("# only a comment"),
+            Some(FrontMatter::default())
+        );
+        for text in [
+            "- a list",
+            "Just a sentence",
+            "\nAfter a rule\n",
+            "  indented: first",
+            "title: \"open",
+            "title: \"closed\" and more",
+            "tags: [a, b",
+        ] {
+            assert_eq!(FrontMatter::parse(text), None, "{:?}", text);
+        }
+    }
+}
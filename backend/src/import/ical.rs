@@ -0,0 +1,282 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use ical::{IcalParser, property::Property};
+
+use crate::{
+    error::AppError,
+    export::UID_DOMAIN,
+    import::{Importer, IncomingEntry, text},
+    types::{DONE_TAG, TODO_TAG, task_fields},
+};
+
+/// The VTODOs of an iCalendar file, as task entries. Completed todos are
+/// tagged `done` and the others `todo`; the due date and RRULE are kept in
+/// the body. Cancelled todos are left out.
+pub struct IcalImporter;
+
+fn value<'a>(properties: &'a [Property], name: &str) -> Option<&'a str> {
+    properties
+        .iter()
+        .find(|property| property.name.eq_ignore_ascii_case(name))
+        .and_then(|property| property.value.as_deref())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// A TEXT value with its escapes undone.
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => text.push('\n'),
+                Some(c) => text.push(c),
+                None => {}
+            },
+            c => text.push(c),
+        }
+    }
+    text
+}
+
+/// The items of a comma separated TEXT list.
+fn list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        if c == ',' && !escaped {
+            items.push(String::new());
+            continue;
+        }
+        escaped = c == '\\' && !escaped;
+        items.last_mut().unwrap().push(c);
+    }
+    items
+        .iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A DATE or DATE-TIME value as a date, an RFC 3339 timestamp for UTC
+/// times, or a local time for floating ones and those with a TZID.
+fn time(value: &str) -> Option<String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+    let (local, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").ok()?;
+    Some(match utc {
+        true => time.and_utc().to_rfc3339(),
+        false => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+    })
+}
+
+fn entry(properties: &[Property]) -> Option<IncomingEntry> {
+    let status = value(properties, "STATUS").unwrap_or_default();
+    if status.eq_ignore_ascii_case("CANCELLED") {
+        return None;
+    }
+    let completed = value(properties, "COMPLETED").and_then(time);
+    let done = status.eq_ignore_ascii_case("COMPLETED") || completed.is_some();
+
+    let mut tags: Vec<String> = properties
+        .iter()
+        .filter(|property| property.name.eq_ignore_ascii_case("CATEGORIES"))
+        .filter_map(|property| property.value.as_deref())
+        .flat_map(list)
+        .collect();
+    tags.dedup();
+    tags.retain(|tag| tag != DONE_TAG && tag != TODO_TAG);
+    tags.push(if done { DONE_TAG } else { TODO_TAG }.to_string());
+
+    let mut body = value(properties, "DESCRIPTION")
+        .map(unescape)
+        .unwrap_or_default();
+    let (due, rrule) = task_fields(&body);
+    let mut fields = vec![];
+    if let (None, Some(due)) = (due, value(properties, "DUE").and_then(time)) {
+        fields.push(format!("- due: {}", due));
+    }
+    if let (None, Some(rrule)) = (rrule, value(properties, "RRULE")) {
+        fields.push(format!("- rrule: {}", rrule));
+    }
+    if !fields.is_empty() {
+        if !body.trim().is_empty() {
+            body = format!("{}\n\n", body.trim_end());
+        }
+        body.push_str(&fields.join("\n"));
+    }
+
+    let id = value(properties, "UID")
+        .and_then(|uid| uid.strip_suffix(UID_DOMAIN))
+        .map(str::to_string);
+    Some(IncomingEntry {
+        id,
+        title: value(properties, "SUMMARY").map(unescape),
+        body: Some(body).filter(|body| !body.trim().is_empty()),
+        tags,
+        created_at: value(properties, "CREATED")
+            .or_else(|| value(properties, "DTSTAMP"))
+            .and_then(time),
+        updated_at: completed.or_else(|| value(properties, "LAST-MODIFIED").and_then(time)),
+    })
+}
+
+impl Importer for IcalImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        let text = text(name, data)?;
+        let mut entries = vec![];
+        for calendar in IcalParser::new(text.as_bytes()) {
+            let calendar = calendar.map_err(|error| {
+                AppError::BadRequest(format!("{} is not an iCalendar file: {}", name, error))
+            })?;
+            entries.extend(
+                calendar
+                    .todos
+                    .iter()
+                    .filter_map(|todo| entry(&todo.properties)),
+            );
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::Journal;
+
+    use super::*;
+    use crate::export::{Exporter, IcsExporter};
+
+    const FEED: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+PRODID:-//Example//EN\r
+BEGIN:VTODO\r
+UID:abc@example.com\r
+DTSTAMP:20240301T090000Z\r
+SUMMARY:Call the bank\\, again\r
+DESCRIPTION:About the card\\nand the loan\r
+CATEGORIES:errands,money\\,bank,todo\r
+DUE;TZID=Europe/Lisbon:20240310T170000\r
+RRULE:FREQ=WEEKLY;BYDAY=MO\r
+END:VTODO\r
+BEGIN:VTODO\r
+DTSTAMP:20240301T090000Z\r
+SUMMARY:Dropped\r
+STATUS:CANCELLED\r
+END:VTODO\r
+BEGIN:VTODO\r
+DTSTAMP:20240301T090000Z\r
+SUMMARY:Finished\r
+COMPLETED:20240304T100000Z\r
+DUE;VALUE=DATE:20240303\r
+END:VTODO\r
+BEGIN:VEVENT\r
+UID:event@example.com\r
+DTSTAMP:20240301T090000Z\r
+DTSTART:20240301T090000Z\r
+SUMMARY:Not a task\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn reads_todos_with_their_due_date_and_rule() {
+        let entries = IcalImporter.parse("tasks.ics", FEED.as_bytes()).unwrap();
+        assert_eq!(
+            entries,
+            [
+                IncomingEntry {
+                    title: Some("Call the bank, again".to_string()),
+                    body: Some(
+                        "About the card\nand the loan\n\n- due: 2024-03-10 17:00:00\n- rrule: FREQ=WEEKLY;BYDAY=MO"
+                            .to_string()
+                    ),
+                    tags: vec![
+                        "errands".to_string(),
+                        "money,bank".to_string(),
+                        "todo".to_string()
+                    ],
+                    created_at: Some("2024-03-01T09:00:00+00:00".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("Finished".to_string()),
+                    body: Some("- due: 2024-03-03".to_string()),
+                    tags: vec!["done".to_string()],
+                    created_at: Some("2024-03-01T09:00:00+00:00".to_string()),
+                    updated_at: Some("2024-03-04T10:00:00+00:00".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert!(matches!(
+            IcalImporter.parse("tasks.ics", b"BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\n"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn reads_back_its_own_export() {
+        let task = |id: &str, title: Option<&str>, body: &str, tags: &[&str]| {
+            let tags = tags.iter().map(|tag| tag.to_string()).collect();
+            let mut journal = Journal::new(
+                id.to_string(),
+                title.map(str::to_string),
+                Some(body.to_string()),
+                tags,
+            );
+            journal.created_at = Some("2024-03-01T09:00:00+00:00".to_string());
+            journal.updated_at = Some("2024-03-02T09:00:00+00:00".to_string());
+            journal
+        };
+        let journals = [
+            task(
+                "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60",
+                Some("Pay rent; on time, please"),
+                "- due: 2024-03-31\n- rrule: FREQ=MONTHLY;BYMONTHDAY=-1",
+                &["home", "todo"],
+            ),
+            task(
+                "7a2d3e8f-4d7c-4a6f-8b9e-2c3d4e5f6071",
+                None,
+                "Weekly review\n- recur: weekly\n- due: 2024-03-08T16:00:00+00:00",
+                &["done"],
+            ),
+        ];
+
+        let mut exporter = IcsExporter::default();
+        let mut data = exporter.header().unwrap();
+        for journal in &journals {
+            data.extend(exporter.entry(journal).unwrap());
+        }
+        data.extend(exporter.finish().unwrap());
+        let entries = IcalImporter.parse("tasks.ics", &data).unwrap();
+
+        assert_eq!(
+            entries,
+            [
+                IncomingEntry {
+                    id: Some(journals[0].id.clone()),
+                    title: journals[0].title.clone(),
+                    body: journals[0].body.clone(),
+                    tags: journals[0].tags.clone(),
+                    created_at: journals[0].created_at.clone(),
+                    updated_at: journals[0].updated_at.clone(),
+                },
+                IncomingEntry {
+                    id: Some(journals[1].id.clone()),
+                    title: None,
+                    body: journals[1].body.clone(),
+                    tags: journals[1].tags.clone(),
+                    created_at: journals[1].created_at.clone(),
+                    updated_at: journals[1].updated_at.clone(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use chrono::NaiveDateTime;
+
+use crate::{
+    error::AppError,
+    import::{Importer, IncomingEntry, inline_tags, text},
+};
+
+/// jrnl's plain text journal: every entry starts with a line like
+/// `[2024-03-05 09:30] Title`, optionally followed by a `*` for starred
+/// entries. `@tags` in the text become tags.
+pub struct JrnlImporter;
+
+/// The time and the rest of an entry's first line.
+fn entry_start(line: &str) -> Option<(NaiveDateTime, &str)> {
+    let (stamp, rest) = match line.strip_prefix('[') {
+        Some(line) => line.split_once(']')?,
+        None => (line.get(..16)?, line.get(16..)?),
+    };
+    let time = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%d %I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(stamp.trim(), format).ok())?;
+    Some((time, rest.trim()))
+}
+
+fn finish(time: NaiveDateTime, title: &str, body: &[&str]) -> IncomingEntry {
+    let title = title.trim_end_matches('*').trim();
+    let body = body.join("\n").trim().to_string();
+    let mut tags = inline_tags(title, '@');
+    for tag in inline_tags(&body, '@') {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    IncomingEntry {
+        title: Some(title.to_string()).filter(|title| !title.is_empty()),
+        body: Some(body).filter(|body| !body.is_empty()),
+        tags,
+        created_at: Some(time.format("%Y-%m-%d %H:%M:%S").to_string()),
+        ..Default::default()
+    }
+}
+
+impl Importer for JrnlImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        let text = text(name, data)?.replace("\r\n", "\n");
+        let mut entries = vec![];
+        let mut current: Option<(NaiveDateTime, &str, Vec<&str>)> = None;
+        for line in text.lines() {
+            match entry_start(line) {
+                Some((time, title)) => {
+                    if let Some((time, title, body)) = current.take() {
+                        entries.push(finish(time, title, &body));
+                    }
+                    current = Some((time, title, vec![]));
+                }
+                None => match current.as_mut() {
+                    Some((_, _, body)) => body.push(line),
+                    None if line.trim().is_empty() => {}
+                    None => {
+                        return Err(AppError::BadRequest(format!(
+                            "{} does not start with a jrnl entry such as `[2024-03-05 09:30] Title`",
+                            name
+                        )));
+                    }
+                },
+            }
+        }
+        if let Some((time, title, body)) = current {
+            entries.push(finish(time, title, &body));
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_entries_on_their_first_line() {
+        let entries = JrnlImporter
+            .parse("journal.txt", include_bytes!("fixtures/jrnl.txt"))
+            .unwrap();
+        assert_eq!(
+            entries,
+            [
+                IncomingEntry {
+                    title: Some("Standup notes @work".to_string()),
+                    body: Some("Talked about the release with @alice.".to_string()),
+                    tags: vec!["work".to_string(), "alice".to_string()],
+                    created_at: Some("2024-03-05 09:30:00".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("Evening run".to_string()),
+                    body: Some("5k in the park @health.".to_string()),
+                    tags: vec!["health".to_string()],
+                    created_at: Some("2024-03-06 18:05:30".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("No brackets @work".to_string()),
+                    tags: vec!["work".to_string()],
+                    created_at: Some("2024-03-07 07:15:00".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("Dinner".to_string()),
+                    created_at: Some("2024-03-08 19:15:00".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_text_before_the_first_entry() {
+        assert!(matches!(
+            JrnlImporter.parse("notes.txt", b"Just some notes\n[2024-03-05 09:30] Entry"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
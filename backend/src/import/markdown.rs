@@ -0,0 +1,287 @@
+use crate::{
+    error::AppError,
+    import::{FrontMatter, FrontMatterValue, Importer, IncomingEntry, text},
+};
+
+fn string(front_matter: &FrontMatter, key: &str) -> Option<String> {
+    match front_matter.get(key)? {
+        FrontMatterValue::Text(value) if !value.is_empty() => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// `tags` as a list, or as a single comma or space separated string.
+pub fn tags(front_matter: &FrontMatter) -> Vec<String> {
+    let tags = match front_matter.get("tags") {
+        Some(FrontMatterValue::List(tags)) => tags.clone(),
+        Some(FrontMatterValue::Text(tags)) => {
+            tags.split([',', ' ']).map(|tag| tag.to_string()).collect()
+        }
+        _ => Vec::<String>::new(),
+    };
+    tags.into_iter()
+        .map(|tag| tag.trim().trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// The index of the `---` line closing the front matter opened at `start`.
+fn closing_line(lines: &[&str], start: usize) -> Option<usize> {
+    (start + 1..lines.len()).find(|&i| lines[i].trim_end() == "---")
+}
+
+/// Whether an entry of our own export starts at `i`: a `---` line after a
+/// blank one, opening a front matter block with an `id`.
+fn starts_exported_entry(lines: &[&str], i: usize) -> bool {
+    if lines[i].trim_end() != "---" || (i > 0 && !lines[i - 1].trim().is_empty()) {
+        return false;
+    }
+    closing_line(lines, i)
+        .and_then(|end| FrontMatter::parse(&lines[i + 1..end].join("\n")))
+        .is_some_and(|mapping| mapping.contains_key("id"))
+}
+
+/// Takes the title from a leading `# ` heading of `body` if there is none
+/// yet and `titled` is false. Front matter with a `title` key, even an empty
+/// one, settles the title, so an untitled entry keeps a body that starts
+/// with a heading.
+fn entry(mut entry: IncomingEntry, body: &[&str], titled: bool) -> IncomingEntry {
+    let mut body: Vec<&str> = body.to_vec();
+    let first = body.iter().position(|line| !line.trim().is_empty());
+    if entry.title.is_none()
+        && !titled
+        && let Some(first) = first
+        && let Some(title) = body[first].strip_prefix("# ")
+    {
+        entry.title = Some(title.trim().to_string());
+        body.drain(..=first);
+    }
+    let body = body.join("\n").trim().to_string();
+    entry.body = Some(body).filter(|body| !body.is_empty());
+    entry
+}
+
+/// Entries separated by front matter blocks, as our Markdown export writes
+/// them, or a single file with front matter as other note tools do.
+fn with_front_matter(lines: &[&str]) -> Option<Vec<IncomingEntry>> {
+    let mut entries = vec![];
+    let mut start = 0;
+    while start < lines.len() {
+        let end = closing_line(lines, start)?;
+        let mapping = FrontMatter::parse(&lines[start + 1..end].join("\n"))?;
+        let next = (end + 1..lines.len())
+            .find(|&i| starts_exported_entry(lines, i))
+            .unwrap_or(lines.len());
+
+        let incoming = IncomingEntry {
+            id: string(&mapping, "id"),
+            title: string(&mapping, "title"),
+            tags: tags(&mapping),
+            created_at: string(&mapping, "created_at").or_else(|| string(&mapping, "date")),
+            updated_at: string(&mapping, "updated_at"),
+            ..Default::default()
+        };
+        let titled = mapping.contains_key("title");
+        entries.push(entry(incoming, &lines[end + 1..next], titled));
+        start = next;
+    }
+    Some(entries)
+}
+
+/// Entries split on `# ` headings, as the plain Markdown export wrote them.
+/// Headings inside fenced code blocks do not count.
+fn by_headings(lines: &[&str]) -> Vec<IncomingEntry> {
+    let mut entries = vec![];
+    let mut current: Vec<&str> = vec![];
+    let mut fenced = false;
+    for line in lines {
+        if line.starts_with("```") || line.starts_with("~~~") {
+            fenced = !fenced;
+        }
+        if !fenced && line.starts_with("# ") && !current.is_empty() {
+            entries.push(entry(IncomingEntry::default(), &current, false));
+            current.clear();
+        }
+        current.push(line);
+    }
+    if !current.is_empty() {
+        entries.push(entry(IncomingEntry::default(), &current, false));
+    }
+    entries
+        .into_iter()
+        .filter(|entry| entry.title.is_some() || entry.body.is_some())
+        .collect()
+}
+
+/// A single note with optional front matter, titled `name` unless the front
+/// matter or a leading `# ` heading says otherwise.
+pub fn parse_note(name: &str, text: &str) -> IncomingEntry {
+    let text = text.replace("\r\n", "\n");
+    let lines: Vec<&str> = text.lines().collect();
+    let front_matter = lines
+        .first()
+        .filter(|line| line.trim_end() == "---")
+        .and_then(|_| closing_line(&lines, 0))
+        .and_then(|end| Some((end, FrontMatter::parse(&lines[1..end].join("\n"))?)));
+    let (body, mapping) = match front_matter {
+        Some((end, mapping)) => (&lines[end + 1..], mapping),
+        None => (&lines[..], FrontMatter::default()),
+    };
+    let mut note = entry(
+        IncomingEntry {
+            title: string(&mapping, "title"),
+            tags: tags(&mapping),
+            created_at: ["created_at", "created", "date"]
+                .iter()
+                .find_map(|key| string(&mapping, key)),
+            updated_at: ["updated_at", "updated", "modified"]
+                .iter()
+                .find_map(|key| string(&mapping, key)),
+            ..Default::default()
+        },
+        body,
+        mapping.contains_key("title"),
+    );
+    note.title = note.title.or(Some(name.to_string()));
+    note
+}
+
+/// Reads the entries of a Markdown file: our export, with or without front
+/// matter, or any Markdown file, split on its top level headings.
+pub fn parse_markdown(text: &str) -> Vec<IncomingEntry> {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.first().is_some_and(|line| line.trim_end() == "---")
+        && let Some(entries) = with_front_matter(&lines)
+    {
+        return entries;
+    }
+    by_headings(&lines)
+}
+
+pub struct MarkdownImporter;
+
+impl Importer for MarkdownImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        Ok(parse_markdown(text(name, data)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::Journal;
+
+    use super::*;
+    use crate::export::{Exporter, MarkdownExporter};
+
+    fn journal(id: &str, title: Option<&str>, body: Option<&str>, tags: &[&str]) -> Journal {
+        let tags = tags.iter().map(|tag| tag.to_string()).collect();
+        let mut journal = Journal::new(
+            id.to_string(),
+            title.map(str::to_string),
+            body.map(str::to_string),
+            tags,
+        );
+        journal.created_at = Some("2024-03-05T09:30:00+01:00".to_string());
+        journal.updated_at = Some("2024-03-06T10:00:00+01:00".to_string());
+        journal
+    }
+
+    fn export(journals: &[Journal]) -> String {
+        let mut exporter = MarkdownExporter;
+        let mut data = exporter.header().unwrap();
+        for journal in journals {
+            data.extend(exporter.entry(journal).unwrap());
+        }
+        data.extend(exporter.finish().unwrap());
+        String::from_utf8(data).unwrap()
+    }
+
+    #[test]
+    fn reads_back_its_own_export() {
+        let journals = [
+            journal(
+                "6f1c2f7e-3c6b-4f5e-9a8d-1b2c3d4e5f60",
+                Some("Plans: \"Q2\""),
+                Some("- one\n- two\n\n---\n\nAfter a rule"),
+                &["work", "with space"],
+            ),
+            journal(
+                "7a2d3e8f-4d7c-4a6f-8b9e-2c3d4e5f6071",
+                None,
+                Some("# Not a title\n\nUntitled entries stay untitled"),
+                &[],
+            ),
+            journal(
+                "8b3e4f9a-5e8d-4b7a-9caf-3d4e5f607182",
+                Some("Only a title"),
+                None,
+                &["todo"],
+            ),
+        ];
+
+        let entries = parse_markdown(&export(&journals));
+        let expected: Vec<IncomingEntry> = journals
+            .iter()
+            .map(|journal| IncomingEntry {
+                id: Some(journal.id.clone()),
+                title: journal.title.clone(),
+                body: journal.body.clone(),
+                tags: journal.tags.clone(),
+                created_at: journal.created_at.clone(),
+                updated_at: journal.updated_at.clone(),
+            })
+            .collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn splits_plain_markdown_on_headings() {
+        let text = "Preamble\n# First\nOne\n```\n# Not a heading\n```\n# Second\n";
+        let entries = parse_markdown(text);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].title, None);
+        assert_eq!(entries[0].body.as_deref(), Some("Preamble"));
+        assert_eq!(entries[1].title.as_deref(), Some("First"));
+        assert_eq!(
+            entries[1].body.as_deref(),
+            Some("One\n```\n# Not a heading\n```")
+        );
+        assert_eq!(entries[2].title.as_deref(), Some("Second"));
+        assert_eq!(entries[2].body, None);
+    }
+
+    #[test]
+    fn reads_a_note_with_front_matter() {
+        let note = parse_note(
+            "file name",
+            "---\r\ntags: \"#a, b c\"\r\ndate: 2024-03-05\r\nmodified: 2024-03-06 10:00\r\n---\r\n# Heading\r\n\r\nText",
+        );
+        assert_eq!(
+            note,
+            IncomingEntry {
+                title: Some("Heading".to_string()),
+                body: Some("Text".to_string()),
+                tags: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                created_at: Some("2024-03-05".to_string()),
+                updated_at: Some("2024-03-06 10:00".to_string()),
+                ..Default::default()
+            }
+        );
+
+        let note = parse_note(
+            "file name",
+            "---\ntitle: Given\ntags: [1, x]\n---\n# Heading",
+        );
+        assert_eq!(note.title.as_deref(), Some("Given"));
+        assert_eq!(note.body.as_deref(), Some("# Heading"));
+        assert_eq!(note.tags, ["1", "x"]);
+
+        let note = parse_note("file name", "---\n- not a mapping\n---\nText");
+        assert_eq!(note.title.as_deref(), Some("file name"));
+        assert_eq!(
+            note.body.as_deref(),
+            Some("---\n- not a mapping\n---\nText")
+        );
+    }
+}
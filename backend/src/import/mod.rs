@@ -0,0 +1,342 @@
+use std::{
+    collections::HashSet,
+    io::{Cursor, Read},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use zip::{ZipArchive, result::ZipError};
+
+use crate::{
+    error::AppError,
+    types::{
+        Config, EntryFilter, ImportFormat, ImportReport, ImportedEntry, Journal, Records,
+        SkippedEntry,
+    },
+};
+
+mod day_one;
+mod front_matter;
+mod ical;
+mod jrnl;
+mod markdown;
+mod obsidian;
+mod taskwarrior;
+mod todo_txt;
+
+pub use day_one::*;
+pub use front_matter::*;
+pub use ical::*;
+pub use jrnl::*;
+pub use markdown::*;
+pub use obsidian::*;
+pub use taskwarrior::*;
+pub use todo_txt::*;
+
+/// Reads the entries of one uploaded file.
+pub trait Importer: Send {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError>;
+}
+
+pub fn importer_for(format: ImportFormat) -> Box<dyn Importer> {
+    match format {
+        ImportFormat::Markdown => Box::new(MarkdownImporter),
+        ImportFormat::Jrnl => Box::new(JrnlImporter),
+        ImportFormat::DayOne => Box::new(DayOneImporter),
+        ImportFormat::TodoTxt => Box::new(TodoTxtImporter),
+        ImportFormat::Taskwarrior => Box::new(TaskwarriorImporter),
+        ImportFormat::Obsidian => Box::new(ObsidianImporter),
+        ImportFormat::ICalendar => Box::new(IcalImporter),
+    }
+}
+
+/// `data` as text, for the formats that are read as such.
+pub fn text<'a>(name: &str, data: &'a [u8]) -> Result<&'a str, AppError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| AppError::BadRequest(format!("{} is not UTF-8 text", name)))?;
+    Ok(text.trim_start_matches('\u{feff}'))
+}
+
+/// A file of an uploaded zip archive.
+pub struct ArchivedFile {
+    pub path: String,
+    pub data: Vec<u8>,
+    pub modified: Option<NaiveDateTime>,
+}
+
+pub fn is_zip(data: &[u8]) -> bool {
+    data.starts_with(b"PK\x03\x04")
+}
+
+/// The files of the zip archive `data`, leaving out directories.
+pub fn unzip(name: &str, data: &[u8]) -> Result<Vec<ArchivedFile>, AppError> {
+    let invalid =
+        |err: ZipError| AppError::BadRequest(format!("{} is not a valid zip: {}", name, err));
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(invalid)?;
+    let mut files = vec![];
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(invalid)?;
+        if file.is_dir() {
+            continue;
+        }
+        let modified = file.last_modified().and_then(|time| {
+            NaiveDate::from_ymd_opt(time.year().into(), time.month().into(), time.day().into())?
+                .and_hms_opt(
+                    time.hour().into(),
+                    time.minute().into(),
+                    time.second().into(),
+                )
+        });
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        files.push(ArchivedFile {
+            path: file.name().to_string(),
+            data,
+            modified,
+        });
+    }
+    Ok(files)
+}
+
+/// The words of `text` starting with `marker`, such as `#tag` or `@tag`,
+/// without the marker and trailing punctuation.
+pub fn inline_tags(text: &str, marker: char) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for word in text.split_whitespace() {
+        let Some(tag) = word.strip_prefix(marker) else {
+            continue;
+        };
+        let tag = tag.trim_end_matches(|c: char| !c.is_alphanumeric());
+        let valid = tag.starts_with(char::is_alphanumeric)
+            && tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'));
+        if valid && !tags.iter().any(|known| known == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Identifies an entry by what it says, so importing the same file twice
+/// does not create copies.
+fn content_hash(title: Option<&str>, body: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(title.unwrap_or_default().trim());
+    hasher.update([0]);
+    hasher.update(body.unwrap_or_default().trim());
+    format!("{:x}", hasher.finalize())
+}
+
+/// An entry read from an import file, before it is stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncomingEntry {
+    /// Kept when it is a valid id no entry has yet.
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub tags: Vec<String>,
+    /// An RFC 3339 timestamp, a local date and time or a plain date.
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// `value` as an RFC 3339 timestamp, reading dates without a zone as local
+/// time.
+pub fn normalize_timestamp(value: &str) -> Option<String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.to_rfc3339());
+    }
+    let local = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|time| time.to_rfc3339())
+}
+
+impl Records {
+    /// Stores `entries` as new entries, or on a dry run only reports what
+    /// would be stored. Entries without content, ids that are already taken
+    /// and entries with the same title and body as an existing one are
+    /// skipped.
+    pub async fn import(
+        &self,
+        entries: Vec<IncomingEntry>,
+        dry_run: bool,
+        config: Arc<Config>,
+    ) -> Result<ImportReport, AppError> {
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut seen = HashSet::new();
+        let existing = self.find(&EntryFilter::default(), config.clone()).await?;
+        let mut hashes: HashSet<String> = existing
+            .iter()
+            .map(|journal| content_hash(journal.title.as_deref(), journal.body.as_deref()))
+            .collect();
+
+        for entry in entries {
+            let skip = |reason: &str| SkippedEntry {
+                title: entry.title.clone(),
+                reason: reason.to_string(),
+            };
+            if entry.title.is_none() && entry.body.is_none() {
+                report.skipped.push(skip("The entry is empty"));
+                continue;
+            }
+
+            let id = match entry.id.as_deref().map(Uuid::parse_str) {
+                Some(Ok(id)) => id.to_string(),
+                _ => Uuid::new_v4().to_string(),
+            };
+            let taken = config.storage().join(format!("{}.json", id)).exists();
+            if taken || !seen.insert(id.clone()) {
+                report
+                    .skipped
+                    .push(skip(&format!("An entry with id {} already exists", id)));
+                continue;
+            }
+            let hash = content_hash(entry.title.as_deref(), entry.body.as_deref());
+            if !hashes.insert(hash) {
+                report
+                    .skipped
+                    .push(skip("An entry with the same content already exists"));
+                continue;
+            }
+
+            let mut journal = Journal::new(id, entry.title, entry.body, entry.tags);
+            let now = Utc::now().to_rfc3339();
+            journal.created_at = entry
+                .created_at
+                .as_deref()
+                .and_then(normalize_timestamp)
+                .or(Some(now));
+            journal.updated_at = entry
+                .updated_at
+                .as_deref()
+                .and_then(normalize_timestamp)
+                .or(journal.created_at.clone());
+            if !dry_run {
+                self.insert_journal(&journal, config.clone()).await?;
+            }
+            report.created.push(ImportedEntry {
+                id: journal.id,
+                title: journal.title,
+                tags: journal.tags,
+            });
+        }
+        if !dry_run && !report.created.is_empty() {
+            let count = report.created.len();
+            let message = match count {
+                1 => format!("Import entry {}", report.created[0].id),
+                count => format!("Import {} entries", count),
+            };
+            self.commit(&config, message).await;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{DateTime, ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+
+    /// A zip of `files`, all last modified on 2024-03-05 at 21:00.
+    pub(crate) fn zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let modified = DateTime::from_date_and_time(2024, 3, 5, 21, 0, 0).unwrap();
+        let options = SimpleFileOptions::default().last_modified_time(modified);
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, content) in files {
+            zip.start_file(*path, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn reads_inline_tags() {
+        assert_eq!(
+            inline_tags("#work, #work and #home/garden! # #-x #ünïcode", '#'),
+            ["work", "home/garden", "ünïcode"]
+        );
+        assert_eq!(inline_tags("mail@example.com @bob", '@'), ["bob"]);
+    }
+
+    #[test]
+    fn unzips_files_only() {
+        let data = zip(&[("notes/", ""), ("notes/a.md", "A")]);
+        assert!(is_zip(&data));
+        let files = unzip("vault.zip", &data).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "notes/a.md");
+        assert_eq!(files[0].data, b"A");
+        assert!(matches!(
+            unzip("vault.zip", b"PK\x03\x04 truncated"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn normalizes_timestamps() {
+        let local = |value: &str| {
+            let time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap();
+            Some(
+                Local
+                    .from_local_datetime(&time)
+                    .earliest()
+                    .unwrap()
+                    .to_rfc3339(),
+            )
+        };
+        assert_eq!(
+            normalize_timestamp(" 2024-03-05T09:30:00Z "),
+            Some("2024-03-05T09:30:00+00:00".to_string())
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-05T09:30:00+02:00"),
+            Some("2024-03-05T09:30:00+02:00".to_string())
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-05 09:30:15"),
+            local("2024-03-05 09:30:15")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-05 09:30"),
+            local("2024-03-05 09:30:00")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-05T09:30:15"),
+            local("2024-03-05 09:30:15")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-03-05"),
+            local("2024-03-05 00:00:00")
+        );
+        assert_eq!(normalize_timestamp("yesterday"), None);
+        assert_eq!(normalize_timestamp("2024-02-30"), None);
+    }
+
+    #[test]
+    fn rejects_binary_text() {
+        assert_eq!(text("a.txt", "\u{feff}BOM".as_bytes()).unwrap(), "BOM");
+        assert!(matches!(
+            text("a.txt", &[0xff, 0xfe]),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::{
+    error::AppError,
+    import::{Importer, IncomingEntry, inline_tags, is_zip, parse_note, text, unzip},
+};
+
+/// Markdown notes, one per file and titled by its name, uploaded one by one
+/// or as a zip of the whole vault. Front matter and inline `#tags` are kept;
+/// other files, such as attachments, are left out.
+pub struct ObsidianImporter;
+
+fn note(path: &str, data: &[u8]) -> Result<IncomingEntry, AppError> {
+    let title = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut note = parse_note(&title, text(path, data)?);
+    let body = note.body.clone().unwrap_or_default();
+    for tag in inline_tags(&body, '#') {
+        if !note.tags.contains(&tag) {
+            note.tags.push(tag);
+        }
+    }
+    Ok(note)
+}
+
+impl Importer for ObsidianImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        if !is_zip(data) {
+            return Ok(vec![note(name, data)?]);
+        }
+        let mut notes = vec![];
+        for file in unzip(name, data)? {
+            let hidden = file
+                .path
+                .split('/')
+                .any(|component| component.starts_with('.'));
+            if hidden || !file.path.ends_with(".md") {
+                continue;
+            }
+            let mut note = note(&file.path, &file.data)?;
+            if note.created_at.is_none() {
+                note.created_at = file
+                    .modified
+                    .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string());
+            }
+            notes.push(note);
+        }
+        Ok(notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::tests::zip;
+
+    const DAILY: &str = "---\ntags: [daily]\ncreated: 2024-03-01 08:00\n---\nWorked on #project/alpha, and #daily again.\n";
+    const IDEAS: &str = "# Better ideas\n\nSome #idea\n\n## Not a #\n";
+
+    #[test]
+    fn titles_a_single_note_by_its_name() {
+        let notes = ObsidianImporter
+            .parse("Daily.md", DAILY.as_bytes())
+            .unwrap();
+        assert_eq!(
+            notes,
+            [IncomingEntry {
+                title: Some("Daily".to_string()),
+                body: Some("Worked on #project/alpha, and #daily again.".to_string()),
+                tags: vec!["daily".to_string(), "project/alpha".to_string()],
+                created_at: Some("2024-03-01 08:00".to_string()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn reads_the_visible_notes_of_a_vault() {
+        let data = zip(&[
+            ("Vault/Journal/Daily.md", DAILY),
+            ("Vault/Ideas.md", IDEAS),
+            ("Vault/.obsidian/workspace.md", "# Settings"),
+            ("Vault/.trash/Old.md", "# Old"),
+            ("Vault/attachments/map.png", "PNG"),
+        ]);
+        let notes = ObsidianImporter.parse("Vault.zip", &data).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].title.as_deref(), Some("Daily"));
+        assert_eq!(notes[0].created_at.as_deref(), Some("2024-03-01 08:00"));
+        assert_eq!(
+            notes[1],
+            IncomingEntry {
+                title: Some("Better ideas".to_string()),
+                body: Some("Some #idea\n\n## Not a #".to_string()),
+                tags: vec!["idea".to_string()],
+                created_at: Some("2024-03-05 21:00:00".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    import::{Importer, IncomingEntry, text},
+    types::{DONE_TAG, TODO_TAG},
+};
+
+/// The JSON of `task export`, either an array or one task per line. The
+/// project becomes a tag like the task's own tags; deleted tasks are left
+/// out.
+pub struct TaskwarriorImporter;
+
+#[derive(Deserialize)]
+struct Task {
+    uuid: Option<String>,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    modified: Option<String>,
+    end: Option<String>,
+    due: Option<String>,
+    project: Option<String>,
+    priority: Option<String>,
+    recur: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    entry: Option<String>,
+    description: String,
+}
+
+/// Taskwarrior's `20240305T093000Z` as RFC 3339.
+fn timestamp(value: &Option<String>) -> Option<String> {
+    let time = NaiveDateTime::parse_from_str(value.as_deref()?, "%Y%m%dT%H%M%SZ").ok()?;
+    Some(Utc.from_utc_datetime(&time).to_rfc3339())
+}
+
+fn entry(task: Task) -> IncomingEntry {
+    let mut body = vec![];
+    if let Some(due) = timestamp(&task.due) {
+        body.push(format!("- due: {}", due));
+    }
+    if let Some(recur) = &task.recur {
+        body.push(format!("- recur: {}", recur));
+    }
+    if let Some(priority) = &task.priority {
+        body.push(format!("- priority: {}", priority));
+    }
+    for annotation in &task.annotations {
+        match timestamp(&annotation.entry) {
+            Some(time) => body.push(format!("- {}: {}", time, annotation.description)),
+            None => body.push(format!("- {}", annotation.description)),
+        }
+    }
+
+    let mut tags = task.tags;
+    tags.extend(task.project);
+    tags.push(match task.status.as_str() {
+        "completed" => DONE_TAG.to_string(),
+        _ => TODO_TAG.to_string(),
+    });
+    IncomingEntry {
+        id: task.uuid,
+        title: Some(task.description),
+        body: Some(body.join("\n")).filter(|body| !body.is_empty()),
+        tags,
+        created_at: timestamp(&task.entry),
+        updated_at: timestamp(&task.end).or(timestamp(&task.modified)),
+    }
+}
+
+impl Importer for TaskwarriorImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        let text = text(name, data)?.trim();
+        let invalid = |err: serde_json::Error| {
+            AppError::BadRequest(format!("{} is not a Taskwarrior export: {}", name, err))
+        };
+        let tasks: Vec<Task> = match text.starts_with('[') {
+            true => serde_json::from_str(text).map_err(invalid)?,
+            false => text
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| serde_json::from_str(line.trim_end_matches(',')))
+                .collect::<Result<_, _>>()
+                .map_err(invalid)?,
+        };
+        Ok(tasks
+            .into_iter()
+            .filter(|task| task.status != "deleted")
+            .map(entry)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = include_str!("fixtures/taskwarrior.json");
+
+    #[test]
+    fn reads_an_exported_array() {
+        let entries = TaskwarriorImporter
+            .parse("tasks.json", EXPORT.as_bytes())
+            .unwrap();
+        assert_eq!(
+            entries,
+            [
+                IncomingEntry {
+                    id: Some("0b1c6a2e-6f8d-4d1a-9a7e-2f3c4d5e6f70".to_string()),
+                    title: Some("Renew passport".to_string()),
+                    body: Some(
+                        [
+                            "- due: 2024-04-01T00:00:00+00:00",
+                            "- priority: H",
+                            "- 2024-03-02T09:00:00+00:00: Photos taken",
+                            "- Bring the old one",
+                        ]
+                        .join("\n")
+                    ),
+                    tags: vec![
+                        "errand".to_string(),
+                        "admin".to_string(),
+                        "todo".to_string()
+                    ],
+                    created_at: Some("2024-03-01T09:00:00+00:00".to_string()),
+                    updated_at: Some("2024-03-02T09:00:00+00:00".to_string()),
+                },
+                IncomingEntry {
+                    id: Some("1c2d3e4f-5a6b-4c7d-8e9f-0a1b2c3d4e5f".to_string()),
+                    title: Some("Water the plants".to_string()),
+                    body: Some("- recur: weekly".to_string()),
+                    tags: vec!["todo".to_string()],
+                    created_at: Some("2024-03-01T09:00:00+00:00".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    id: Some("2d3e4f5a-6b7c-4d8e-9f0a-1b2c3d4e5f60".to_string()),
+                    title: Some("File taxes".to_string()),
+                    tags: vec!["done".to_string()],
+                    created_at: Some("2024-01-01T09:00:00+00:00".to_string()),
+                    updated_at: Some("2024-03-09T17:00:00+00:00".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_one_task_per_line() {
+        let lines = EXPORT.trim().trim_start_matches('[').trim_end_matches(']');
+        assert_eq!(
+            TaskwarriorImporter
+                .parse("tasks.json", lines.as_bytes())
+                .unwrap(),
+            TaskwarriorImporter
+                .parse("tasks.json", EXPORT.as_bytes())
+                .unwrap()
+        );
+        assert!(matches!(
+            TaskwarriorImporter.parse("tasks.json", b"[{\"status\": \"pending\"}]"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use chrono::NaiveDate;
+
+use crate::{
+    error::AppError,
+    import::{Importer, IncomingEntry, text},
+    types::{DONE_TAG, TODO_TAG},
+};
+
+/// A todo.txt list, one task per line. Projects and contexts become tags,
+/// the priority and `key:value` pairs are listed in the body.
+pub struct TodoTxtImporter;
+
+fn date(word: Option<&&str>) -> Option<NaiveDate> {
+    word.and_then(|word| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok())
+}
+
+fn parse_task(line: &str) -> IncomingEntry {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let done = words.first() == Some(&"x");
+    let mut completed = None;
+    if done {
+        words.remove(0);
+        completed = date(words.first());
+        if completed.is_some() {
+            words.remove(0);
+        }
+    }
+    let priority = words
+        .first()
+        .filter(|word| {
+            let bytes = word.as_bytes();
+            bytes.len() == 3
+                && bytes[0] == b'('
+                && bytes[1].is_ascii_uppercase()
+                && bytes[2] == b')'
+        })
+        .map(|word| word[1..2].to_string());
+    if priority.is_some() {
+        words.remove(0);
+    }
+    let created = date(words.first());
+    if created.is_some() {
+        words.remove(0);
+    }
+
+    let mut title = vec![];
+    let mut tags = vec![];
+    let mut details = vec![];
+    if let Some(priority) = priority {
+        details.push(format!("- priority: {}", priority));
+    }
+    for word in words {
+        let tag = word.strip_prefix('+').or_else(|| word.strip_prefix('@'));
+        match (tag, word.split_once(':')) {
+            (Some(tag), _) if !tag.is_empty() => tags.push(tag.to_string()),
+            (_, Some((key, value)))
+                if !key.is_empty() && !value.is_empty() && !value.starts_with("//") =>
+            {
+                details.push(format!("- {}: {}", key, value));
+            }
+            _ => title.push(word),
+        }
+    }
+    tags.push(if done { DONE_TAG } else { TODO_TAG }.to_string());
+
+    IncomingEntry {
+        title: Some(title.join(" ")).filter(|title| !title.is_empty()),
+        body: Some(details.join("\n")).filter(|body| !body.is_empty()),
+        tags,
+        created_at: created.map(|date| date.to_string()),
+        updated_at: completed.map(|date| date.to_string()),
+        ..Default::default()
+    }
+}
+
+impl Importer for TodoTxtImporter {
+    fn parse(&self, name: &str, data: &[u8]) -> Result<Vec<IncomingEntry>, AppError> {
+        Ok(text(name, data)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(parse_task)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_priorities_dates_and_tags() {
+        let entries = TodoTxtImporter
+            .parse("todo.txt", include_bytes!("fixtures/todo.txt"))
+            .unwrap();
+        assert_eq!(
+            entries,
+            [
+                IncomingEntry {
+                    title: Some("Call the plumber".to_string()),
+                    body: Some("- priority: A\n- due: 2024-03-05".to_string()),
+                    tags: vec!["house".to_string(), "phone".to_string(), "todo".to_string()],
+                    created_at: Some("2024-03-01".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("Pay rent".to_string()),
+                    tags: vec!["house".to_string(), "done".to_string()],
+                    created_at: Some("2024-03-01".to_string()),
+                    updated_at: Some("2024-03-04".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("Water the plants".to_string()),
+                    tags: vec!["done".to_string()],
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("Read https://example.com/article".to_string()),
+                    body: Some("- rec: +1w".to_string()),
+                    tags: vec!["todo".to_string()],
+                    created_at: Some("2024-03-02".to_string()),
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("(b) Lowercase is not a priority".to_string()),
+                    tags: vec!["todo".to_string()],
+                    ..Default::default()
+                },
+                IncomingEntry {
+                    title: Some("(AB) Neither is this".to_string()),
+                    tags: vec!["todo".to_string()],
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_invalid_dates_in_the_title() {
+        let task = parse_task("(C) 2024-02-30 Leap day");
+        assert_eq!(task.title.as_deref(), Some("2024-02-30 Leap day"));
+        assert_eq!(task.body.as_deref(), Some("- priority: C"));
+        assert_eq!(task.created_at, None);
+    }
+}
@@ -1,3 +1,13 @@
+pub mod auth;
+pub mod backup;
+pub mod cli;
+pub mod config;
+pub mod error;
+pub mod export;
 pub mod handlers;
+pub mod import;
+pub mod openapi;
+pub mod reload;
+pub mod server;
 pub mod types;
 pub mod utils;
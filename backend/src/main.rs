@@ -1,37 +1,75 @@
-use actix_cors::Cors;
-use actix_web::{App, HttpServer, web::Data};
+use std::{io, path::PathBuf, process};
+
 use backend::{
-    handlers::{create_journal, delete_by_id, export, get_all, get_by_id, system_info},
-    types::Records,
-    utils::get_config,
+    backup::run_scheduled_backups,
+    cli::{Cli, Command},
+    config::{
+        LiveConfig, default_storage_path, find_config, init_config, load_config, user_config_path,
+    },
+    reload::watch_config,
+    server::{Listener, build_server, cleanup, write_discovery_file},
+    types::{Records, Tokens},
 };
+use clap::Parser;
+
+fn exit_with(err: impl std::fmt::Display) -> ! {
+    eprintln!("{}", err);
+    process::exit(1);
+}
+
+fn init(config: Option<PathBuf>, storage: Option<PathBuf>, force: bool) -> io::Result<()> {
+    let missing_dir = || io::Error::new(io::ErrorKind::NotFound, "No user directory available");
+    let path = config.or_else(user_config_path).ok_or_else(missing_dir)?;
+    let storage = storage
+        .or_else(default_storage_path)
+        .ok_or_else(missing_dir)?;
+    init_config(&path, &storage, force)?;
+    println!(
+        "Wrote {}, journals are kept in {}",
+        path.display(),
+        storage.display()
+    );
+    Ok(())
+}
 
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let records = Data::new(Records::new());
-
-    let config = match get_config() {
-        Ok(c) => Data::new(c),
-        Err(e) => {
-            eprintln!("Config file not found. Exiting server with error {:?}", e);
-            return Err(e);
+async fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    if let Some(Command::Init { storage, force }) = cli.command {
+        return init(cli.config, storage, force).or_else(|err| exit_with(err));
+    }
+
+    let path = find_config(cli.config.as_deref()).unwrap_or_else(|err| exit_with(err));
+    let config = load_config(&path, &cli).unwrap_or_else(|err| exit_with(err));
+    for warning in config.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+
+    if cli.print_config {
+        let mut table = config.without_secrets().map_err(io::Error::other)?;
+        table.insert("secret".to_string(), "<hidden>".into());
+        if config.backup_schedule().is_some() {
+            table.insert("backup_passphrase".to_string(), "<hidden>".into());
         }
-    };
-
-    HttpServer::new(move || {
-        let cors = Cors::permissive();
-        App::new()
-            .wrap(cors)
-            .app_data(records.clone())
-            .app_data(config.clone())
-            .service(create_journal)
-            .service(get_all)
-            .service(get_by_id)
-            .service(delete_by_id)
-            .service(export)
-            .service(system_info)
-    })
-    .bind(("127.0.0.1", 7000))?
-    .run()
-    .await
+        println!("# {}\n{}", path.display(), table);
+        return Ok(());
+    }
+
+    let tokens = Tokens::load(&config)?;
+    let listener = Listener::bind(&config)?;
+    let url = listener.url()?;
+    write_discovery_file(&config, &url)?;
+    println!("Listening on {}", url);
+
+    let workers = config.workers();
+    let config = LiveConfig::new(path, cli, config);
+    let records = Records::new();
+    let _watcher = watch_config(config.clone(), records.clone(), tokens.clone())
+        .inspect_err(|err| eprintln!("Not watching the config file: {}", err));
+
+    actix_web::rt::spawn(run_scheduled_backups(config.clone(), records.clone()));
+
+    let result = build_server(listener, config.clone(), records, tokens, workers)?.await;
+    cleanup(&config.current());
+    result
 }
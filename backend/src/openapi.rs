@@ -0,0 +1,86 @@
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+
+use crate::handlers;
+
+/// The OpenAPI document of every route registered in [`crate::server::configure`].
+///
+/// `backend/openapi.json` is generated from this; the `openapi` test fails
+/// when the two drift apart.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Task Journal API",
+        description = "Encrypted journal entries with attachments, drafts, templates and wiki links."
+    ),
+    paths(
+        handlers::create_journal,
+        handlers::get_all,
+        handlers::get_by_id,
+        handlers::update_journal,
+        handlers::delete_by_id,
+        handlers::export,
+        handlers::system_info,
+        handlers::health,
+        handlers::upload_attachments,
+        handlers::list_attachments,
+        handlers::download_attachment,
+        handlers::delete_attachment,
+        handlers::list_drafts,
+        handlers::create_draft,
+        handlers::get_draft,
+        handlers::save_draft,
+        handlers::delete_draft,
+        handlers::get_backlinks,
+        handlers::get_graph,
+        handlers::list_templates,
+        handlers::create_template,
+        handlers::get_template,
+        handlers::update_template,
+        handlers::delete_template,
+        handlers::render_template,
+        handlers::list_tokens,
+        handlers::create_token,
+        handlers::current_token,
+        handlers::revoke_token,
+        handlers::reload_config,
+        handlers::import_entries,
+        handlers::calendar_feed,
+        handlers::create_backup,
+        handlers::restore_backup,
+        handlers::get_history,
+        handlers::restore_version,
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "entries", description = "Journal entries"),
+        (name = "attachments", description = "Files attached to an entry"),
+        (name = "drafts", description = "Autosaved, unsubmitted entries"),
+        (name = "links", description = "Wiki links between entries"),
+        (name = "templates", description = "Reusable entry skeletons"),
+        (name = "import", description = "Entries from other files and tools"),
+        (name = "calendar", description = "Task entries as an iCalendar feed"),
+        (name = "backup", description = "Encrypted backups of the whole vault"),
+        (name = "history", description = "Versions of entries kept in git"),
+        (name = "tokens", description = "API tokens"),
+        (name = "system", description = "Host and server status"),
+        (name = "admin", description = "Server administration"),
+    )
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{path::Path, sync::mpsc, thread, time::Duration};
+
+use actix_web::rt::System;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    config::LiveConfig,
+    error::AppError,
+    server::move_discovery_file,
+    types::{Records, ReloadReport, Tokens},
+};
+
+/// Editors save in several steps, wait for them to settle before reloading.
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+
+/// Reloads the config file and points the caches and tokens at the new
+/// storage directory when it moved.
+pub async fn reload(
+    config: &LiveConfig,
+    records: &Records,
+    tokens: &Tokens,
+) -> Result<ReloadReport, AppError> {
+    let previous = config.current();
+    let report = config
+        .reload()
+        .map_err(|err| AppError::BadRequest(err.to_string()))?;
+    let current = config.current();
+
+    if current.storage() != previous.storage() {
+        tokens.reload(&current).await?;
+        records.clear_cache().await;
+        move_discovery_file(&previous, &current)?;
+    }
+
+    if !report.applied.is_empty() {
+        eprintln!(
+            "Reloaded {}, applied: {}",
+            config.path().display(),
+            report.applied.join(", ")
+        );
+    }
+    if !report.restart_required.is_empty() {
+        eprintln!(
+            "Restart to apply the changes to: {}",
+            report.restart_required.join(", ")
+        );
+    }
+    Ok(report)
+}
+
+fn concerns(event: &notify::Event, path: &Path) -> bool {
+    event
+        .paths
+        .iter()
+        .any(|changed| changed.file_name() == path.file_name())
+}
+
+/// Reloads the config whenever its file changes, until the returned watcher
+/// is dropped. Invalid versions are logged and ignored.
+pub fn watch_config(
+    config: LiveConfig,
+    records: Records,
+    tokens: Tokens,
+) -> notify::Result<RecommendedWatcher> {
+    let path = config.path().to_path_buf();
+    // Editors often replace the file instead of writing to it, so watch the
+    // directory rather than the file itself.
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => ".".into(),
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+        {
+            let _ = sender.send(event);
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    thread::spawn(move || {
+        while let Ok(event) = receiver.recv() {
+            if !concerns(&event, &path) {
+                continue;
+            }
+            while receiver.recv_timeout(SETTLE_TIME).is_ok() {}
+
+            let result = System::new().block_on(reload(&config, &records, &tokens));
+            if let Err(err) = result {
+                eprintln!("Keeping the current configuration: {}", err);
+            }
+        }
+    });
+
+    Ok(watcher)
+}
@@ -0,0 +1,360 @@
+use std::{
+    fs, io,
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread::{self, JoinHandle},
+};
+
+use actix_cors::Cors;
+use actix_web::{
+    App, HttpRequest, HttpResponse, HttpServer,
+    dev::{Server, ServerHandle},
+    http::{Method, header},
+    middleware::from_fn,
+    rt::System,
+    web::{self, Data, ServiceConfig},
+};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use notify::RecommendedWatcher;
+
+use crate::{
+    auth::authenticate,
+    backup::run_scheduled_backups,
+    cli::Cli,
+    config::LiveConfig,
+    error::{AppError, REQUEST_ID_HEADER, request_id},
+    handlers::{
+        calendar_feed, create_backup, create_draft, create_journal, create_template, create_token,
+        current_token, delete_attachment, delete_by_id, delete_draft, delete_template,
+        download_attachment, export, get_all, get_backlinks, get_by_id, get_draft, get_graph,
+        get_history, get_template, health, import_entries, list_attachments, list_drafts,
+        list_templates, list_tokens, reload_config, render_template, restore_backup,
+        restore_version, revoke_token, save_draft, system_info, update_journal, update_template,
+        upload_attachments,
+    },
+    openapi::ApiDoc,
+    reload::watch_config,
+    types::{Config, Records, Tokens},
+};
+
+/// Worker threads used by the embedded server, which only ever serves one client.
+const EMBEDDED_WORKERS: usize = 2;
+/// Written to the storage directory while the server runs, holding the URL it
+/// listens on, so clients find instances started with `port = 0`.
+const DISCOVERY_FILE: &str = "server.url";
+
+/// A bound socket the server can be started on.
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener, PathBuf),
+}
+
+impl From<TcpListener> for Listener {
+    fn from(listener: TcpListener) -> Self {
+        Listener::Tcp(listener)
+    }
+}
+
+impl Listener {
+    /// Binds the Unix socket or TCP address from `config`. The socket file is
+    /// only accessible to the current user.
+    pub fn bind(config: &Config) -> io::Result<Self> {
+        match config.unix_socket() {
+            Some(path) => bind_unix(path),
+            None => Ok(Listener::Tcp(TcpListener::bind((
+                config.host(),
+                config.port(),
+            ))?)),
+        }
+    }
+
+    /// Where clients reach the server: `http://host:port` or `unix:/path`.
+    pub fn url(&self) -> io::Result<String> {
+        match self {
+            Listener::Tcp(listener) => Ok(format!("http://{}", listener.local_addr()?)),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => Ok(format!("unix:{}", path.display())),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> io::Result<Listener> {
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt};
+
+    // A socket left behind by an instance that did not shut down cleanly.
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        Err(_) => {}
+    }
+
+    // The socket is created with the umask's permissions, so it is bound in
+    // a directory only the current user can enter and moved into place once
+    // it is restricted.
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let private = parent.join(format!(".bind-{}", std::process::id()));
+    fs::DirBuilder::new().mode(0o700).create(&private)?;
+    let bound = bind_restricted(&private.join("socket"), path);
+    let _ = fs::remove_dir_all(&private);
+    Ok(Listener::Unix(bound?, path.to_path_buf()))
+}
+
+#[cfg(unix)]
+fn bind_restricted(temporary: &Path, path: &Path) -> io::Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let listener = std::os::unix::net::UnixListener::bind(temporary)?;
+    fs::set_permissions(temporary, fs::Permissions::from_mode(0o600))?;
+    fs::rename(temporary, path)?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_: &Path) -> io::Result<Listener> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix sockets are not supported on this platform",
+    ))
+}
+
+/// Writes `url` to the discovery file in the storage directory.
+pub fn write_discovery_file(config: &Config, url: &str) -> io::Result<PathBuf> {
+    let path = config.storage().join(DISCOVERY_FILE);
+    fs::write(&path, url)?;
+    Ok(path)
+}
+
+/// Moves the discovery file along when the storage directory changes.
+pub fn move_discovery_file(previous: &Config, current: &Config) -> io::Result<()> {
+    let old_path = previous.storage().join(DISCOVERY_FILE);
+    if let Ok(url) = fs::read_to_string(&old_path) {
+        write_discovery_file(current, &url)?;
+        fs::remove_file(old_path)?;
+    }
+    Ok(())
+}
+
+/// Removes the discovery file and the Unix socket once the server stopped.
+pub fn cleanup(config: &Config) {
+    let _ = fs::remove_file(config.storage().join(DISCOVERY_FILE));
+    if let Some(path) = config.unix_socket() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Registers every route of the API.
+pub fn configure(cfg: &mut ServiceConfig) {
+    cfg.service(create_journal)
+        .service(get_all)
+        .service(get_by_id)
+        .service(update_journal)
+        .service(delete_by_id)
+        .service(export)
+        .service(system_info)
+        .service(health)
+        .service(upload_attachments)
+        .service(list_attachments)
+        .service(download_attachment)
+        .service(delete_attachment)
+        .service(list_drafts)
+        .service(create_draft)
+        .service(get_draft)
+        .service(save_draft)
+        .service(delete_draft)
+        .service(get_backlinks)
+        .service(get_graph)
+        .service(list_templates)
+        .service(create_template)
+        .service(get_template)
+        .service(update_template)
+        .service(delete_template)
+        .service(render_template)
+        .service(list_tokens)
+        .service(create_token)
+        .service(current_token)
+        .service(revoke_token)
+        .service(reload_config)
+        .service(import_entries)
+        .service(calendar_feed)
+        .service(create_backup)
+        .service(restore_backup)
+        .service(get_history)
+        .service(restore_version)
+        .service(SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", ApiDoc::openapi()))
+        .default_service(web::to(unknown_route));
+}
+
+async fn unknown_route(req: HttpRequest) -> Result<HttpResponse, AppError> {
+    Err(AppError::NotFound(format!(
+        "No route for {} {}",
+        req.method(),
+        req.path()
+    )))
+}
+
+/// Only the origins listed in the config, as of the request, may call the API
+/// from a browser.
+fn cors(config: LiveConfig) -> Cors {
+    Cors::default()
+        .allowed_origin_fn(move |origin, _| {
+            origin.to_str().is_ok_and(|origin| {
+                config
+                    .current()
+                    .cors_origins()
+                    .iter()
+                    .any(|allowed| allowed == origin)
+            })
+        })
+        .allowed_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allowed_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .expose_headers([REQUEST_ID_HEADER])
+}
+
+/// Builds the server on an already bound listener. `workers` defaults to
+/// actix's own choice when `None`.
+pub fn build_server(
+    listener: impl Into<Listener>,
+    config: LiveConfig,
+    records: Records,
+    tokens: Tokens,
+    workers: Option<usize>,
+) -> io::Result<Server> {
+    let records = Data::new(records);
+    let config = Data::new(config);
+    let tokens = Data::new(tokens);
+
+    let mut server = HttpServer::new(move || {
+        App::new()
+            .wrap(from_fn(authenticate))
+            .wrap(from_fn(request_id))
+            .wrap(cors(config.get_ref().clone()))
+            .app_data(records.clone())
+            .app_data(config.clone())
+            .app_data(tokens.clone())
+            .configure(configure)
+    });
+    if let Some(workers) = workers {
+        server = server.workers(workers);
+    }
+
+    server = match listener.into() {
+        Listener::Tcp(listener) => server.listen(listener)?,
+        #[cfg(unix)]
+        Listener::Unix(listener, _) => server.listen_uds(listener)?,
+    };
+    Ok(server.run())
+}
+
+/// A server running on its own thread inside another process, listening on
+/// a random local port. Used by the desktop app so no separate backend has to
+/// be started.
+pub struct EmbeddedServer {
+    addr: SocketAddr,
+    token: String,
+    handle: ServerHandle,
+    thread: Option<JoinHandle<io::Result<()>>>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl EmbeddedServer {
+    /// Starts serving `config`, which was read from `path` and is reloaded
+    /// when that file changes.
+    pub fn start(path: PathBuf, config: Config) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let addr = listener.local_addr()?;
+        let tokens = Tokens::load(&config)?;
+        let token = System::new().block_on(tokens.create_session_token("embedded"));
+        let config = LiveConfig::new(path, Cli::default(), config);
+        let records = Records::new();
+        let watcher = watch_config(config.clone(), records.clone(), tokens.clone())
+            .inspect_err(|err| eprintln!("Not watching the config file: {}", err))
+            .ok();
+
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            System::new().block_on(async move {
+                actix_web::rt::spawn(run_scheduled_backups(config.clone(), records.clone()));
+                let server =
+                    build_server(listener, config, records, tokens, Some(EMBEDDED_WORKERS))?;
+                let _ = sender.send(server.handle());
+                server.await
+            })
+        });
+
+        match receiver.recv() {
+            Ok(handle) => Ok(EmbeddedServer {
+                addr,
+                token,
+                handle,
+                thread: Some(thread),
+                _watcher: watcher,
+            }),
+            Err(_) => Err(thread
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("Embedded server panicked")))
+                .err()
+                .unwrap_or_else(|| io::Error::other("Embedded server stopped unexpectedly"))),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A read-write token valid until the server stops.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Stops accepting connections, lets in-flight requests finish and waits
+    /// for the server thread to exit.
+    pub fn shutdown(mut self) {
+        let handle = self.handle.clone();
+        // Stopping is driven from a fresh thread so this can be called from
+        // inside another async runtime, such as the desktop app's.
+        let _ = thread::spawn(move || System::new().block_on(handle.stop(true))).join();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::{fs::PermissionsExt, net::UnixListener};
+
+    use super::*;
+
+    #[test]
+    fn binds_sockets_for_the_current_user_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.sock");
+        // Left behind by an instance that did not shut down cleanly.
+        drop(UnixListener::bind(&path).unwrap());
+
+        let listener = bind_unix(&path).unwrap();
+        assert_eq!(listener.url().unwrap(), format!("unix:{}", path.display()));
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        drop(listener);
+
+        let file = dir.path().join("journal.txt");
+        fs::write(&file, "notes").unwrap();
+        assert!(bind_unix(&file).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "notes");
+    }
+}
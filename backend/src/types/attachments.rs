@@ -0,0 +1,310 @@
+use std::{collections::HashSet, fs, path::PathBuf, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    types::{Config, Journal, Records, save_journal},
+    utils::{blob_address, decrypt_bytes, encrypt_bytes, get_key, list_files_in_a_dir},
+};
+
+pub use shared::Attachment;
+
+pub(super) const BLOBS_DIR: &str = "blobs";
+
+pub(super) fn blob_path(config: &Config, blob: &str) -> PathBuf {
+    config.storage.join(BLOBS_DIR).join(blob)
+}
+
+/// The decrypted contents of `attachment`.
+pub(crate) fn read_blob(config: &Config, attachment: &Attachment) -> Result<Vec<u8>, AppError> {
+    let sealed = fs::read(blob_path(config, &attachment.blob))?;
+    Ok(decrypt_bytes(&sealed, &get_key(config.secret.clone()))?)
+}
+
+/// Stores `data` encrypted, unless an equal blob exists, and returns its
+/// address.
+pub(super) fn write_blob(config: &Config, data: &[u8]) -> Result<String, AppError> {
+    let key = get_key(config.secret.clone());
+    let blob = blob_address(data, &key);
+    let path = blob_path(config, &blob);
+    if !path.exists() {
+        fs::create_dir_all(config.storage.join(BLOBS_DIR))?;
+        let sealed = encrypt_bytes(data, &key)?;
+        fs::write(&path, sealed)?;
+    }
+    Ok(blob)
+}
+
+fn attachment_not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Attachment {} not found", id))
+}
+
+impl Records {
+    pub async fn add_attachment(
+        &self,
+        entry_id: &String,
+        name: String,
+        content_type: String,
+        data: Vec<u8>,
+        config: Arc<Config>,
+    ) -> Result<Attachment, AppError> {
+        if data.len() as u64 > config.max_attachment_size {
+            return Err(AppError::PayloadTooLarge(format!(
+                "{} exceeds the attachment size limit of {} bytes",
+                name, config.max_attachment_size
+            )));
+        }
+
+        let guard = self.lock_entry(entry_id).await;
+        let mut journal = self.find_by_id(entry_id, config.clone()).await?;
+        if journal.attachments.len() >= config.max_attachments_per_entry {
+            return Err(AppError::BadRequest(format!(
+                "Entries can have at most {} attachments",
+                config.max_attachments_per_entry
+            )));
+        }
+
+        // The blob is unreferenced until the entry is saved.
+        let blobs = self.blobs.read().await;
+        let blob = write_blob(&config, &data)?;
+        let attachment = Attachment {
+            id: Uuid::new_v4().to_string(),
+            name,
+            content_type,
+            size: data.len() as u64,
+            blob,
+        };
+        journal.attachments.push(attachment.clone());
+        save_journal(&journal, &config)?;
+        drop((blobs, guard));
+        self.commit(
+            &config,
+            format!("Add attachment {} to entry {}", attachment.id, journal.id),
+        )
+        .await;
+        Ok(attachment)
+    }
+
+    pub async fn list_attachments(
+        &self,
+        entry_id: &String,
+        config: Arc<Config>,
+    ) -> Result<Vec<Attachment>, AppError> {
+        Ok(self.find_by_id(entry_id, config).await?.attachments)
+    }
+
+    pub async fn read_attachment(
+        &self,
+        entry_id: &String,
+        attachment_id: &str,
+        config: Arc<Config>,
+    ) -> Result<(Attachment, Vec<u8>), AppError> {
+        let journal = self.find_by_id(entry_id, config.clone()).await?;
+        let attachment = journal
+            .attachments
+            .into_iter()
+            .find(|attachment| attachment.id == attachment_id)
+            .ok_or_else(|| attachment_not_found(attachment_id))?;
+
+        let data = read_blob(&config, &attachment)?;
+        Ok((attachment, data))
+    }
+
+    pub async fn delete_attachment(
+        &self,
+        entry_id: &String,
+        attachment_id: &str,
+        config: Arc<Config>,
+    ) -> Result<(), AppError> {
+        let guard = self.lock_entry(entry_id).await;
+        let mut journal = self.find_by_id(entry_id, config.clone()).await?;
+        let position = journal
+            .attachments
+            .iter()
+            .position(|attachment| attachment.id == attachment_id)
+            .ok_or_else(|| attachment_not_found(attachment_id))?;
+
+        let removed = journal.attachments.remove(position);
+        save_journal(&journal, &config)?;
+        drop(guard);
+        self.remove_unreferenced_blobs(std::slice::from_ref(&removed), config.clone())
+            .await?;
+        self.commit(
+            &config,
+            format!("Remove attachment {} from entry {}", removed.id, journal.id),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// Deletes the blobs of `attachments` that no entry refers to anymore.
+    /// Waits for blobs being written to be referenced first, an equal one
+    /// may be among them.
+    pub(crate) async fn remove_unreferenced_blobs(
+        &self,
+        attachments: &[Attachment],
+        config: Arc<Config>,
+    ) -> Result<(), AppError> {
+        if attachments.is_empty() {
+            return Ok(());
+        }
+        let _blobs = self.blobs.write().await;
+
+        let journals: Vec<Journal> = list_files_in_a_dir(
+            config.storage.to_string_lossy().as_ref(),
+            get_key(config.secret.clone()),
+        )?;
+        let referenced = journals
+            .iter()
+            .flat_map(|journal| journal.attachments.iter())
+            .map(|attachment| attachment.blob.as_str())
+            .collect::<HashSet<&str>>();
+
+        for attachment in attachments {
+            let path = blob_path(&config, &attachment.blob);
+            if !referenced.contains(attachment.blob.as_str()) && path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::types::test_vault;
+
+    async fn entry(records: &Records, config: &Arc<Config>) -> String {
+        records
+            .insert(None, None, vec![], config.clone())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_changes_to_an_entry_all_persist() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let id = entry(&records, &config).await;
+
+        let uploads = (0..8).map(|i| {
+            let (records, config, id) = (records.clone(), config.clone(), id.clone());
+            tokio::spawn(async move {
+                records
+                    .add_attachment(
+                        &id,
+                        format!("{}.txt", i),
+                        "text/plain".to_string(),
+                        vec![i; 16],
+                        config,
+                    )
+                    .await
+            })
+        });
+        let edit = {
+            let (records, config, id) = (records.clone(), config.clone(), id.clone());
+            tokio::spawn(async move {
+                records
+                    .update(&id, None, Some("Edited".to_string()), vec![], config)
+                    .await
+            })
+        };
+        for upload in futures_util::future::join_all(uploads).await {
+            upload.unwrap().unwrap();
+        }
+        edit.await.unwrap().unwrap();
+
+        let journal = records.find_by_id(&id, config).await.unwrap();
+        assert_eq!(journal.body.as_deref(), Some("Edited"));
+        assert_eq!(journal.attachments.len(), 8);
+    }
+
+    #[actix_web::test]
+    async fn keeps_blobs_other_entries_refer_to() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let (first, second) = (
+            entry(&records, &config).await,
+            entry(&records, &config).await,
+        );
+        let add = |id: String| {
+            let (records, config) = (records.clone(), config.clone());
+            async move {
+                records
+                    .add_attachment(
+                        &id,
+                        "a.txt".to_string(),
+                        "text/plain".to_string(),
+                        b"same".to_vec(),
+                        config,
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+        let attachment = add(first.clone()).await;
+        let copy = add(second.clone()).await;
+        assert_eq!(attachment.blob, copy.blob);
+
+        records
+            .delete_attachment(&first, &attachment.id, config.clone())
+            .await
+            .unwrap();
+        assert!(blob_path(&config, &attachment.blob).exists());
+        records.delete_by_id(&second, config.clone()).await.unwrap();
+        assert!(!blob_path(&config, &attachment.blob).exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn blob_cleanup_waits_for_uploads_in_flight() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let (first, second) = (
+            entry(&records, &config).await,
+            entry(&records, &config).await,
+        );
+        let attachment = records
+            .add_attachment(
+                &first,
+                "a.txt".to_string(),
+                "text/plain".to_string(),
+                b"same".to_vec(),
+                config.clone(),
+            )
+            .await
+            .unwrap();
+
+        // An upload of the same data to the second entry, between writing
+        // the blob and saving the entry.
+        let upload = records.blobs.read().await;
+        let blob = write_blob(&config, b"same").unwrap();
+        let cleanup = {
+            let (records, config, first) = (records.clone(), config.clone(), first.clone());
+            tokio::spawn(async move {
+                records
+                    .delete_attachment(&first, &attachment.id, config)
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!cleanup.is_finished());
+
+        let mut journal = records.find_by_id(&second, config.clone()).await.unwrap();
+        journal.attachments.push(Attachment {
+            id: Uuid::new_v4().to_string(),
+            name: "a.txt".to_string(),
+            content_type: "text/plain".to_string(),
+            size: 4,
+            blob: blob.clone(),
+        });
+        save_journal(&journal, &config).unwrap();
+        drop(upload);
+
+        cleanup.await.unwrap().unwrap();
+        assert!(blob_path(&config, &blob).exists());
+    }
+}
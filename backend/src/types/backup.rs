@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    types::{
+        Config, Draft, EntryFilter, Journal, Records, Template,
+        attachments::write_blob,
+        drafts::{draft_path, write_draft},
+        read_blob, save_journal,
+        templates::{save_template, template_path},
+    },
+};
+
+pub use shared::{BackupRequest, RestoreCounts, RestoreMode, RestoreOptions, RestoreReport};
+
+/// Everything a backup holds, decrypted so it can be restored into a vault
+/// with another secret.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    /// An RFC 3339 timestamp.
+    pub created_at: String,
+    pub entries: Vec<Journal>,
+    pub drafts: Vec<Draft>,
+    pub templates: Vec<Template>,
+    /// Attachment contents, by the blob address the entries refer to.
+    pub blobs: BTreeMap<String, Vec<u8>>,
+    /// The config as TOML, without its secrets. Only kept for reference, a
+    /// restore leaves the config alone.
+    pub config: String,
+}
+
+/// Counts a record that is in the vault already or not, and tells whether
+/// it is to be written.
+fn tally(counts: &mut RestoreCounts, exists: bool, mode: RestoreMode) -> bool {
+    match (exists, mode) {
+        (false, _) => counts.created += 1,
+        (true, RestoreMode::Overwrite) => counts.replaced += 1,
+        (true, RestoreMode::Merge) => {
+            counts.skipped += 1;
+            return false;
+        }
+    }
+    true
+}
+
+fn check_id(kind: &str, id: &str) -> Result<(), AppError> {
+    Uuid::parse_str(id).map(|_| ()).map_err(|_| {
+        AppError::BadRequest(format!("The backup has a {} with invalid id {}", kind, id))
+    })
+}
+
+impl Records {
+    /// The whole vault, for a backup.
+    pub async fn snapshot(&self, config: Arc<Config>) -> Result<Snapshot, AppError> {
+        let entries = self.export(&EntryFilter::default(), config.clone()).await?;
+        let mut blobs = BTreeMap::new();
+        for attachment in entries
+            .iter()
+            .flat_map(|journal| journal.attachments.iter())
+        {
+            if !blobs.contains_key(&attachment.blob) {
+                blobs.insert(attachment.blob.clone(), read_blob(&config, attachment)?);
+            }
+        }
+
+        Ok(Snapshot {
+            created_at: Utc::now().to_rfc3339(),
+            drafts: self.list_drafts(config.clone()).await?,
+            templates: self.list_templates(config.clone()).await?,
+            config: config.without_secrets()?.to_string(),
+            entries,
+            blobs,
+        })
+    }
+
+    /// Writes the entries, drafts and templates of `snapshot` into the vault,
+    /// encrypted with its secret. Records that exist already are kept or
+    /// replaced depending on `mode`.
+    pub async fn restore(
+        &self,
+        snapshot: Snapshot,
+        mode: RestoreMode,
+        config: Arc<Config>,
+    ) -> Result<RestoreReport, AppError> {
+        for journal in &snapshot.entries {
+            check_id("entry", &journal.id)?;
+            for attachment in &journal.attachments {
+                if !snapshot.blobs.contains_key(&attachment.blob) {
+                    return Err(AppError::BadRequest(format!(
+                        "The backup is missing attachment {} of entry {}",
+                        attachment.name, journal.id
+                    )));
+                }
+            }
+        }
+        for draft in &snapshot.drafts {
+            check_id("draft", &draft.id)?;
+        }
+        for template in &snapshot.templates {
+            check_id("template", &template.id)?;
+        }
+
+        let mut report = RestoreReport {
+            mode,
+            created_at: snapshot.created_at.clone(),
+            ..Default::default()
+        };
+        // Blob addresses are keyed with the secret, so they change when the
+        // vault's differs from the one the backup was made in.
+        let mut addresses: HashMap<String, String> = HashMap::new();
+        let mut replaced = vec![];
+
+        for mut journal in snapshot.entries.iter().cloned() {
+            let _guard = self.lock_entry(&journal.id).await;
+            let _blobs = self.blobs.read().await;
+            let previous = self.find_by_id(&journal.id, config.clone()).await.ok();
+            if !tally(&mut report.entries, previous.is_some(), mode) {
+                continue;
+            }
+            for attachment in &mut journal.attachments {
+                let address = match addresses.get(&attachment.blob) {
+                    Some(address) => address.clone(),
+                    None => {
+                        let address = write_blob(&config, &snapshot.blobs[&attachment.blob])?;
+                        addresses.insert(attachment.blob.clone(), address.clone());
+                        address
+                    }
+                };
+                attachment.blob = address;
+            }
+            save_journal(&journal, &config)?;
+            replaced.extend(
+                previous
+                    .into_iter()
+                    .flat_map(|previous| previous.attachments),
+            );
+        }
+        report.attachments = addresses.len();
+
+        for draft in &snapshot.drafts {
+            let exists = Path::new(&draft_path(&config, &draft.id)).exists();
+            if tally(&mut report.drafts, exists, mode) {
+                write_draft(draft, &config)?;
+            }
+        }
+        for template in &snapshot.templates {
+            let exists = Path::new(&template_path(&config, &template.id)).exists();
+            if tally(&mut report.templates, exists, mode) {
+                save_template(template, &config)?;
+            }
+        }
+
+        self.clear_cache().await;
+        self.remove_unreferenced_blobs(&replaced, config.clone())
+            .await?;
+        self.commit(
+            &config,
+            format!("Restore the backup made {}", report.created_at),
+        )
+        .await;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{DraftRequest, TemplateRequest};
+
+    use super::*;
+    use crate::types::test_vault;
+
+    /// A vault with two entries, one with an attachment, a draft and a
+    /// template, and its snapshot.
+    async fn backed_up() -> (tempfile::TempDir, Arc<Config>, Snapshot) {
+        let (dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let id = records
+            .insert(Some("Trip".to_string()), None, vec![], config.clone())
+            .await
+            .unwrap();
+        records
+            .add_attachment(
+                &id,
+                "beach.jpg".to_string(),
+                "image/jpeg".to_string(),
+                vec![0xff, 0xd8, 0xff, 0xe0],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        records
+            .insert(Some("Home".to_string()), None, vec![], config.clone())
+            .await
+            .unwrap();
+        records
+            .insert_draft(DraftRequest::default(), config.clone())
+            .await
+            .unwrap();
+        records
+            .insert_template(
+                TemplateRequest {
+                    name: "Daily".to_string(),
+                    ..Default::default()
+                },
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        let snapshot = records.snapshot(config.clone()).await.unwrap();
+        (dir, config, snapshot)
+    }
+
+    fn counts(created: usize, replaced: usize, skipped: usize) -> RestoreCounts {
+        RestoreCounts {
+            created,
+            replaced,
+            skipped,
+        }
+    }
+
+    #[actix_web::test]
+    async fn restores_into_a_vault_with_another_secret() {
+        let (_source, _, snapshot) = backed_up().await;
+        assert!(!snapshot.config.contains("secret"));
+        let (_dir, config) =
+            test_vault(|config| config.secret = "fedcba9876543210fedcba9876543210".to_string());
+        let records = Records::new();
+
+        let report = records
+            .restore(snapshot.clone(), RestoreMode::Merge, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.entries, counts(2, 0, 0));
+        assert_eq!(report.drafts, counts(1, 0, 0));
+        assert_eq!(report.templates, counts(1, 0, 0));
+        assert_eq!(report.attachments, 1);
+
+        let trip = snapshot
+            .entries
+            .iter()
+            .find(|journal| !journal.attachments.is_empty())
+            .unwrap();
+        let (attachment, data) = records
+            .read_attachment(&trip.id, &trip.attachments[0].id, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(data, [0xff, 0xd8, 0xff, 0xe0]);
+        // Blob addresses are keyed with the secret.
+        assert_ne!(attachment.blob, trip.attachments[0].blob);
+    }
+
+    #[actix_web::test]
+    async fn merges_or_overwrites_existing_records() {
+        let (_dir, config, snapshot) = backed_up().await;
+        let records = Records::new();
+        let trip = &snapshot.entries[0];
+        records
+            .update(
+                &trip.id,
+                Some("Changed".to_string()),
+                None,
+                vec![],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        let added = records
+            .insert(Some("Since".to_string()), None, vec![], config.clone())
+            .await
+            .unwrap();
+
+        let report = records
+            .restore(snapshot.clone(), RestoreMode::Merge, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.entries, counts(0, 0, 2));
+        assert_eq!(report.drafts, counts(0, 0, 1));
+        assert_eq!(report.templates, counts(0, 0, 1));
+        let kept = records.find_by_id(&trip.id, config.clone()).await.unwrap();
+        assert_eq!(kept.title.as_deref(), Some("Changed"));
+
+        let report = records
+            .restore(snapshot.clone(), RestoreMode::Overwrite, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.entries, counts(0, 2, 0));
+        assert_eq!(report.drafts, counts(0, 1, 0));
+        assert_eq!(report.templates, counts(0, 1, 0));
+        let restored = records.find_by_id(&trip.id, config.clone()).await.unwrap();
+        assert_eq!(restored.title, trip.title);
+        // Entries made since the backup are left alone either way.
+        assert!(records.find_by_id(&added, config.clone()).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn rejects_incomplete_snapshots() {
+        let (_dir, config, snapshot) = backed_up().await;
+        let records = Records::new();
+
+        let mut missing_blob = snapshot.clone();
+        missing_blob.blobs.clear();
+        let mut invalid_id = snapshot.clone();
+        invalid_id.templates[0].id = "../escape".to_string();
+        for snapshot in [missing_blob, invalid_id] {
+            assert!(matches!(
+                records
+                    .restore(snapshot, RestoreMode::Overwrite, config.clone())
+                    .await,
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+}
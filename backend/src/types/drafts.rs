@@ -0,0 +1,93 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    types::{Config, Records},
+    utils::{decrypt_file, get_key, list_files_in_a_dir, write_encrypted},
+};
+
+pub use shared::{Draft, DraftRequest};
+
+const DRAFTS_DIR: &str = "drafts";
+
+fn new_draft(id: String, request: DraftRequest) -> Draft {
+    Draft {
+        id,
+        entry_id: request.entry_id,
+        title: request.title,
+        body: request.body,
+        tags: request.tags,
+        updated_at: Utc::now().to_rfc3339(),
+    }
+}
+
+fn drafts_dir(config: &Config) -> PathBuf {
+    config.storage.join(DRAFTS_DIR)
+}
+
+pub(super) fn draft_path(config: &Config, id: &str) -> String {
+    format!("{}/{}.json", drafts_dir(config).to_string_lossy(), id)
+}
+
+pub(super) fn write_draft(draft: &Draft, config: &Config) -> Result<(), AppError> {
+    write_encrypted(
+        &drafts_dir(config),
+        &draft.id,
+        draft,
+        &get_key(config.secret.clone()),
+    )
+}
+
+impl Records {
+    pub async fn insert_draft(
+        &self,
+        request: DraftRequest,
+        config: Arc<Config>,
+    ) -> Result<Draft, AppError> {
+        let draft = new_draft(Uuid::new_v4().to_string(), request);
+        write_draft(&draft, &config)?;
+        Ok(draft)
+    }
+
+    /// Overwrites the draft with the given id, creating it if it does not exist
+    /// yet so the client can keep saving under the same id.
+    pub async fn save_draft(
+        &self,
+        id: &str,
+        request: DraftRequest,
+        config: Arc<Config>,
+    ) -> Result<Draft, AppError> {
+        let draft = new_draft(id.to_string(), request);
+        write_draft(&draft, &config)?;
+        Ok(draft)
+    }
+
+    pub async fn find_draft(&self, id: &str, config: Arc<Config>) -> Result<Draft, AppError> {
+        let key = get_key(config.secret.clone());
+        decrypt_file(&draft_path(&config, id), &key)
+            .map_err(|err| err.with_not_found_message(|| format!("Draft {} not found", id)))
+    }
+
+    pub async fn list_drafts(&self, config: Arc<Config>) -> Result<Vec<Draft>, AppError> {
+        let dir = drafts_dir(&config);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut drafts: Vec<Draft> = list_files_in_a_dir(
+            dir.to_string_lossy().as_ref(),
+            get_key(config.secret.clone()),
+        )?;
+        drafts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(drafts)
+    }
+
+    pub async fn delete_draft(&self, id: &str, config: Arc<Config>) -> Result<(), AppError> {
+        fs::remove_file(draft_path(&config, id)).map_err(|err| {
+            AppError::from(err).with_not_found_message(|| format!("Draft {} not found", id))
+        })
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone};
+
+use crate::{
+    error::AppError,
+    types::{Journal, tasks::Task},
+};
+
+pub use shared::{EntryFilter, EntryOrder, TaskStatus};
+
+fn split_list(list: &Option<String>) -> Vec<String> {
+    list.iter()
+        .flat_map(|list| list.split(','))
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Parses `value` as an instant, or as the start (or with `end_of_day`, the
+/// end) of a local day.
+fn parse_bound(
+    name: &str,
+    value: &str,
+    end_of_day: bool,
+) -> Result<DateTime<FixedOffset>, AppError> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(value) {
+        return Ok(instant);
+    }
+
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        AppError::BadRequest(format!(
+            "`{}` must be a date (YYYY-MM-DD) or an RFC 3339 timestamp",
+            name
+        ))
+    })?;
+    let time = match end_of_day {
+        true => day.and_hms_milli_opt(23, 59, 59, 999),
+        false => day.and_hms_opt(0, 0, 0),
+    };
+    time.and_then(|time| Local.from_local_datetime(&time).earliest())
+        .map(|time| time.fixed_offset())
+        .ok_or_else(|| AppError::BadRequest(format!("`{}` is not a valid local time", name)))
+}
+
+fn timestamp(value: &Option<String>) -> Option<DateTime<FixedOffset>> {
+    value
+        .as_deref()
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+}
+
+/// Keeps the entries matching `filter`, in the order it asks for.
+pub fn filter_entries(
+    journals: Vec<Journal>,
+    filter: &EntryFilter,
+) -> Result<Vec<Journal>, AppError> {
+    let mut tags = split_list(&filter.tags);
+    tags.extend(filter.tag.iter().filter(|tag| !tag.is_empty()).cloned());
+    let ids: HashSet<String> = split_list(&filter.ids).into_iter().collect();
+    let from = filter
+        .from
+        .as_deref()
+        .map(|from| parse_bound("from", from, false))
+        .transpose()?;
+    let to = filter
+        .to
+        .as_deref()
+        .map(|to| parse_bound("to", to, true))
+        .transpose()?;
+
+    let mut journals: Vec<Journal> = journals
+        .into_iter()
+        .filter(|journal| tags.iter().all(|tag| journal.tags.contains(tag)))
+        .filter(|journal| ids.is_empty() || ids.contains(&journal.id))
+        .filter(|journal| {
+            filter.status.is_none_or(|status| {
+                Task::of(journal).is_some_and(|task| task.done == (status == TaskStatus::Done))
+            })
+        })
+        .filter(|journal| {
+            if from.is_none() && to.is_none() {
+                return true;
+            }
+            timestamp(&journal.created_at).is_some_and(|created| {
+                from.is_none_or(|from| created >= from) && to.is_none_or(|to| created <= to)
+            })
+        })
+        .collect();
+
+    match filter.order {
+        Some(EntryOrder::Title) => journals.sort_by(|a, b| a.title.cmp(&b.title)),
+        Some(EntryOrder::Oldest) => journals.sort_by_key(|journal| timestamp(&journal.created_at)),
+        Some(EntryOrder::Newest) => {
+            journals.sort_by_key(|journal| std::cmp::Reverse(timestamp(&journal.created_at)))
+        }
+        Some(EntryOrder::Updated) => journals.sort_by_key(|journal| {
+            std::cmp::Reverse(timestamp(&journal.updated_at).or(timestamp(&journal.created_at)))
+        }),
+        None => {}
+    }
+    Ok(journals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, title: &str, tags: &[&str], created_at: Option<&str>) -> Journal {
+        let tags = tags.iter().map(|tag| tag.to_string()).collect();
+        let mut journal = Journal::new(id.to_string(), Some(title.to_string()), None, tags);
+        journal.created_at = created_at.map(str::to_string);
+        journal.updated_at = journal.created_at.clone();
+        journal
+    }
+
+    fn entries() -> Vec<Journal> {
+        vec![
+            entry(
+                "a",
+                "Beta",
+                &["work", "todo"],
+                Some("2025-01-10T09:00:00+00:00"),
+            ),
+            entry(
+                "b",
+                "Alpha",
+                &["work", "done"],
+                Some("2025-02-10T09:00:00+00:00"),
+            ),
+            entry("c", "Gamma", &["home"], Some("2025-03-10T09:00:00+00:00")),
+            entry("d", "Delta", &["work"], None),
+        ]
+    }
+
+    fn ids(filter: EntryFilter) -> Vec<String> {
+        filter_entries(entries(), &filter)
+            .unwrap()
+            .into_iter()
+            .map(|journal| journal.id)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_everything_without_criteria() {
+        assert_eq!(ids(EntryFilter::default()), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn matches_tags_and_ids() {
+        let filter = EntryFilter {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["a", "b", "d"]);
+
+        let filter = EntryFilter {
+            tags: Some("work, todo".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["a"]);
+
+        let filter = EntryFilter {
+            ids: Some("c,,b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["b", "c"]);
+    }
+
+    #[test]
+    fn matches_task_status() {
+        let filter = EntryFilter {
+            status: Some(TaskStatus::Todo),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["a"]);
+
+        let filter = EntryFilter {
+            status: Some(TaskStatus::Done),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["b"]);
+    }
+
+    #[test]
+    fn matches_date_ranges() {
+        let filter = EntryFilter {
+            from: Some("2025-02-01T00:00:00+00:00".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["b", "c"]);
+
+        let filter = EntryFilter {
+            from: Some("2025-01-10T09:00:00+00:00".to_string()),
+            to: Some("2025-02-10T09:00:00+00:00".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(filter), ["a", "b"]);
+
+        let filter = EntryFilter {
+            to: Some("2025-12-31".to_string()),
+            ..Default::default()
+        };
+        // Entries without a creation time never fall in a range.
+        assert_eq!(ids(filter), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn rejects_malformed_dates() {
+        let filter = EntryFilter {
+            from: Some("last week".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            filter_entries(entries(), &filter),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn orders_entries() {
+        let order = |order| {
+            ids(EntryFilter {
+                order: Some(order),
+                ..Default::default()
+            })
+        };
+        assert_eq!(order(EntryOrder::Title), ["b", "a", "d", "c"]);
+        assert_eq!(order(EntryOrder::Oldest), ["d", "a", "b", "c"]);
+        assert_eq!(order(EntryOrder::Newest), ["c", "b", "a", "d"]);
+        assert_eq!(order(EntryOrder::Updated), ["c", "b", "a", "d"]);
+    }
+}
@@ -0,0 +1,498 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use actix_web::web;
+use chrono::{DateTime, FixedOffset, Utc};
+use git2::{Commit, ErrorCode, IndexAddOption, Oid, Repository, Signature, Sort, Tree};
+
+use crate::{
+    error::AppError,
+    types::{
+        Config, EncryptedFile, Journal, Records,
+        attachments::{BLOBS_DIR, blob_path},
+        save_journal,
+    },
+    utils::{decrypt_data, get_key},
+};
+
+pub use shared::{EntryChange, HistoryCommit};
+
+const IGNORE_FILE: &str = ".gitignore";
+/// Kept out of the repository: `auth` holds the first token in plain text,
+/// drafts are autosaved every few seconds and the rest is written at runtime.
+const IGNORED: [&str; 4] = ["/auth/", "/drafts/", "/server.url", "/.write-test-*"];
+
+/// Committer used unless git is configured with a name and email.
+const COMMITTER_NAME: &str = "task-journal";
+const COMMITTER_EMAIL: &str = "task-journal@localhost";
+
+fn history_off() -> AppError {
+    AppError::BadRequest("History is off, set `git = true` in the config".to_string())
+}
+
+fn entry_file(id: &str) -> String {
+    format!("{}.json", id)
+}
+
+/// Whether `path`, relative to the storage directory, matches `IGNORED`.
+fn is_ignored(path: &str) -> bool {
+    IGNORED
+        .iter()
+        .map(|pattern| pattern.trim_start_matches('/'))
+        .any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None if pattern.ends_with('/') => path.starts_with(pattern),
+            None => path == pattern,
+        })
+}
+
+/// Adds the `IGNORED` patterns missing from the ignore file, which may have
+/// come with a repository made by hand.
+fn ensure_ignored(storage: &Path) -> Result<(), AppError> {
+    let path = storage.join(IGNORE_FILE);
+    let mut content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let missing: Vec<&str> = IGNORED
+        .into_iter()
+        .filter(|pattern| !content.lines().any(|line| line.trim() == *pattern))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for pattern in missing {
+        content.push_str(pattern);
+        content.push('\n');
+    }
+    Ok(fs::write(path, content)?)
+}
+
+/// The repository in `storage`, created when there is none. The flag tells
+/// whether it was just created.
+fn open_or_init(storage: &Path) -> Result<(Repository, bool), AppError> {
+    let opened = match Repository::open(storage) {
+        Ok(repo) => (repo, false),
+        Err(err) if err.code() == ErrorCode::NotFound => (Repository::init(storage)?, true),
+        Err(err) => return Err(err.into()),
+    };
+    ensure_ignored(storage)?;
+    Ok(opened)
+}
+
+fn head_commit(repo: &Repository) -> Result<Option<Commit<'_>>, AppError> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        Err(err) if matches!(err.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Commits every change in `storage`, unless nothing changed. Ignored paths
+/// are left out even when the ignore file was edited, and dropped from the
+/// index when an earlier commit made by hand tracked them.
+fn commit_all(storage: &Path, message: &str) -> Result<(), AppError> {
+    let (repo, created) = open_or_init(storage)?;
+    let mut index = repo.index()?;
+    let mut skip_ignored = |path: &Path, _: &[u8]| -> i32 {
+        match is_ignored(&path.to_string_lossy()) {
+            true => 1,
+            false => 0,
+        }
+    };
+    index.add_all(["*"], IndexAddOption::DEFAULT, Some(&mut skip_ignored))?;
+    index.update_all(["*"], None)?;
+    let tracked_ignored: Vec<PathBuf> = index
+        .iter()
+        .filter_map(|entry| String::from_utf8(entry.path).ok())
+        .filter(|path| is_ignored(path))
+        .map(PathBuf::from)
+        .collect();
+    for path in tracked_ignored {
+        index.remove_path(&path)?;
+    }
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let parent = head_commit(&repo)?;
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.tree_id() == tree.id())
+    {
+        return Ok(());
+    }
+    let message = match created {
+        true => format!(
+            "{}\n\nStarts the history, with every file already in the vault.",
+            message
+        ),
+        false => message.to_string(),
+    };
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now(COMMITTER_NAME, COMMITTER_EMAIL))?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+    Ok(())
+}
+
+fn file_in(tree: &Tree, path: &str) -> Option<Oid> {
+    tree.get_path(Path::new(path)).ok().map(|entry| entry.id())
+}
+
+/// The entry stored in the git blob `oid`.
+fn read_version(repo: &Repository, oid: Oid, key: &[u8; 32]) -> Result<Journal, AppError> {
+    let blob = repo.find_blob(oid)?;
+    let encrypted: EncryptedFile = serde_json::from_slice(blob.content())?;
+    let stringified = decrypt_data(&encrypted.content, &encrypted.nonce, key)?;
+    Ok(serde_json::from_str(&stringified)?)
+}
+
+fn commit_time(commit: &Commit) -> String {
+    let time = commit.time();
+    DateTime::from_timestamp(time.seconds(), 0)
+        .zip(FixedOffset::east_opt(time.offset_minutes() * 60))
+        .map(|(time, offset)| time.with_timezone(&offset).to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// The commits of the repository in `storage` that created, changed or
+/// deleted the entry, newest first.
+fn list_history(storage: &Path, key: &[u8; 32], id: &str) -> Result<Vec<HistoryCommit>, AppError> {
+    let not_found = || AppError::NotFound(format!("No history for entry {}", id));
+    let repo = Repository::open(storage).map_err(|_| not_found())?;
+    let Some(head) = head_commit(&repo)? else {
+        return Err(not_found());
+    };
+
+    let path = entry_file(id);
+    let mut walk = repo.revwalk()?;
+    walk.push(head.id())?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut commits = vec![];
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let after = file_in(&commit.tree()?, &path);
+        let before = match commit.parent(0) {
+            Ok(parent) => file_in(&parent.tree()?, &path),
+            Err(_) => None,
+        };
+        let change = match (before, after) {
+            (None, Some(_)) => EntryChange::Created,
+            (Some(before), Some(after)) if before != after => EntryChange::Updated,
+            (Some(_), None) => EntryChange::Deleted,
+            _ => continue,
+        };
+        // Versions written under another secret still show up, untitled.
+        let title = after
+            .and_then(|oid| read_version(&repo, oid, key).ok())
+            .and_then(|journal| journal.title);
+        commits.push(HistoryCommit {
+            id: commit.id().to_string(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            time: commit_time(&commit),
+            change,
+            title,
+        });
+    }
+
+    match commits.is_empty() {
+        true => Err(not_found()),
+        false => Ok(commits),
+    }
+}
+
+/// The entry as it was in `commit`, writing back those of its attachments
+/// that were deleted since, and the abbreviated hash of the commit.
+fn checkout_version(
+    config: &Config,
+    id: &str,
+    commit: &str,
+) -> Result<(Journal, String), AppError> {
+    if commit.len() < 4 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::BadRequest(format!(
+            "{} is not a commit hash",
+            commit
+        )));
+    }
+    let commit_not_found = || AppError::NotFound(format!("Commit {} not found", commit));
+    let repo = Repository::open(&config.storage).map_err(|_| commit_not_found())?;
+    let version = repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| commit_not_found())?;
+    let tree = version.tree()?;
+    let oid = file_in(&tree, &entry_file(id))
+        .ok_or_else(|| AppError::NotFound(format!("Entry {} is not in commit {}", id, commit)))?;
+
+    let journal = read_version(&repo, oid, &get_key(config.secret.clone()))?;
+    for attachment in &journal.attachments {
+        let path = blob_path(config, &attachment.blob);
+        if path.exists() {
+            continue;
+        }
+        let oid =
+            file_in(&tree, &format!("{}/{}", BLOBS_DIR, attachment.blob)).ok_or_else(|| {
+                AppError::NotFound(format!(
+                    "Attachment {} is not in commit {}",
+                    attachment.id, commit
+                ))
+            })?;
+        fs::create_dir_all(config.storage.join(BLOBS_DIR))?;
+        fs::write(path, repo.find_blob(oid)?.content())?;
+    }
+
+    let short = version.id().to_string()[..7].to_string();
+    Ok((journal, short))
+}
+
+impl Records {
+    /// Commits the storage directory when history is on. Messages name
+    /// records by id only: titles and file names are journal content, and
+    /// the repository may be pushed anywhere. A failed commit is only
+    /// logged: the change itself is stored, and the next commit picks it up.
+    pub(crate) async fn commit(&self, config: &Config, message: String) {
+        if !config.git {
+            return;
+        }
+        let _guard = self.git.lock().await;
+        let storage = config.storage.clone();
+        let result = web::block({
+            let message = message.clone();
+            move || commit_all(&storage, &message)
+        })
+        .await
+        .map_err(AppError::from)
+        .and_then(|result| result);
+        if let Err(err) = result {
+            eprintln!("Could not commit \"{}\": {}", message, err);
+        }
+    }
+
+    /// The commits that created, changed or deleted the entry, newest first.
+    pub async fn history(
+        &self,
+        id: &str,
+        config: Arc<Config>,
+    ) -> Result<Vec<HistoryCommit>, AppError> {
+        if !config.git {
+            return Err(history_off());
+        }
+        let key = get_key(config.secret.clone());
+        let id = id.to_string();
+        web::block(move || list_history(&config.storage, &key, &id)).await?
+    }
+
+    /// Stores the entry as it was in `commit`, along with any of its
+    /// attachments deleted since, as a new change.
+    pub async fn restore_version(
+        &self,
+        id: &str,
+        commit: &str,
+        config: Arc<Config>,
+    ) -> Result<Journal, AppError> {
+        if !config.git {
+            return Err(history_off());
+        }
+        let guard = self.lock_entry(id).await;
+        // The checkout writes back the blobs of deleted attachments.
+        let blobs = self.blobs.read().await;
+        let (mut journal, short) = web::block({
+            let config = config.clone();
+            let (id, commit) = (id.to_string(), commit.to_string());
+            move || checkout_version(&config, &id, &commit)
+        })
+        .await??;
+
+        journal.updated_at = Some(Utc::now().to_rfc3339());
+        save_journal(&journal, &config)?;
+        drop((blobs, guard));
+        self.index_links(&journal, config.clone()).await?;
+        self.commit(
+            &config,
+            format!("Restore entry {} from {}", journal.id, short),
+        )
+        .await;
+        Ok(journal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_vault;
+
+    fn summaries(storage: &Path) -> Vec<String> {
+        let repo = Repository::open(storage).unwrap();
+        let mut walk = repo.revwalk().unwrap();
+        walk.push_head().unwrap();
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).unwrap();
+        walk.map(|oid| {
+            let commit = repo.find_commit(oid.unwrap()).unwrap();
+            commit.summary().unwrap().to_string()
+        })
+        .collect()
+    }
+
+    #[actix_web::test]
+    async fn commits_every_change_by_id() {
+        let (_dir, config) = test_vault(|config| config.git = true);
+        let records = Records::new();
+        let id = records
+            .insert(
+                Some("Secret plans".to_string()),
+                None,
+                vec![],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        records
+            .update(
+                &id,
+                Some("Other plans".to_string()),
+                None,
+                vec![],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        records.delete_by_id(&id, config.clone()).await.unwrap();
+
+        assert_eq!(
+            summaries(&config.storage),
+            vec![
+                format!("Delete entry {}", id),
+                format!("Update entry {}", id),
+                format!("Create entry {}", id),
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn skips_unchanged_trees_and_ignored_files() {
+        let (_dir, config) = test_vault(|config| config.git = true);
+        fs::create_dir_all(config.storage.join("auth")).unwrap();
+        fs::write(config.storage.join("auth/token"), "token").unwrap();
+        fs::create_dir_all(config.storage.join("drafts")).unwrap();
+        fs::write(config.storage.join("drafts/draft.json"), "{}").unwrap();
+        let records = Records::new();
+        records
+            .insert(None, Some("Body".to_string()), vec![], config.clone())
+            .await
+            .unwrap();
+        records.commit(&config, "Nothing".to_string()).await;
+
+        assert_eq!(summaries(&config.storage).len(), 1);
+        let repo = Repository::open(&config.storage).unwrap();
+        let tree = head_commit(&repo).unwrap().unwrap().tree().unwrap();
+        assert!(file_in(&tree, "auth/token").is_none());
+        assert!(file_in(&tree, "drafts/draft.json").is_none());
+        assert!(file_in(&tree, IGNORE_FILE).is_some());
+    }
+
+    #[actix_web::test]
+    async fn lists_and_restores_versions() {
+        let (_dir, config) = test_vault(|config| config.git = true);
+        let records = Records::new();
+        let id = records
+            .insert(Some("First".to_string()), None, vec![], config.clone())
+            .await
+            .unwrap();
+        records
+            .update(
+                &id,
+                Some("Second".to_string()),
+                None,
+                vec![],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        records.delete_by_id(&id, config.clone()).await.unwrap();
+
+        let history = records.history(&id, config.clone()).await.unwrap();
+        let changes: Vec<_> = history
+            .iter()
+            .map(|commit| (commit.change, commit.title.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (EntryChange::Deleted, None),
+                (EntryChange::Updated, Some("Second".to_string())),
+                (EntryChange::Created, Some("First".to_string())),
+            ]
+        );
+
+        let restored = records
+            .restore_version(&id, &history[2].id, config.clone())
+            .await
+            .unwrap();
+        assert_eq!(restored.title.as_deref(), Some("First"));
+        let stored = records.find_by_id(&id, config.clone()).await.unwrap();
+        assert_eq!(stored.title.as_deref(), Some("First"));
+        assert_eq!(
+            summaries(&config.storage)[0],
+            format!("Restore entry {} from {}", id, &history[2].id[..7])
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_unknown_versions() {
+        let (_dir, config) = test_vault(|config| config.git = true);
+        let records = Records::new();
+        let id = records
+            .insert(None, Some("Body".to_string()), vec![], config.clone())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            records
+                .restore_version(&id, "not-hex", config.clone())
+                .await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            records
+                .restore_version(&id, "0000000", config.clone())
+                .await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            records.history("missing", config.clone()).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn history_is_off_without_git() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let id = records
+            .insert(None, Some("Body".to_string()), vec![], config.clone())
+            .await
+            .unwrap();
+
+        assert!(!config.storage.join(".git").exists());
+        assert!(matches!(
+            records.history(&id, config).await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
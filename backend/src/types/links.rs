@@ -0,0 +1,366 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::{MappedMutexGuard, MutexGuard};
+
+use crate::{
+    error::AppError,
+    types::{Config, Journal, Records, save_journal},
+    utils::{get_key, list_files_in_a_dir, parse_wiki_links, rename_wiki_links},
+};
+
+pub use shared::{AmbiguousLink, Graph, GraphEdge, GraphNode, UnresolvedLink};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    Resolved(String),
+    Ambiguous(Vec<String>),
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Default)]
+struct IndexedEntry {
+    title: Option<String>,
+    links: Vec<String>,
+}
+
+/// Titles and outgoing `[[links]]` of every entry, kept in memory so links can
+/// be resolved without decrypting the whole storage on each request.
+#[derive(Debug, Clone, Default)]
+pub struct LinkIndex {
+    entries: HashMap<String, IndexedEntry>,
+}
+
+fn normalize(text: &str) -> String {
+    text.trim().to_lowercase()
+}
+
+impl LinkIndex {
+    pub fn from_journals(journals: &[Journal]) -> Self {
+        let mut index = LinkIndex::default();
+        for journal in journals {
+            index.upsert(journal);
+        }
+        index
+    }
+
+    pub fn upsert(&mut self, journal: &Journal) {
+        let links = journal
+            .body
+            .as_deref()
+            .map(parse_wiki_links)
+            .unwrap_or_default();
+        self.entries.insert(
+            journal.id.clone(),
+            IndexedEntry {
+                title: journal.title.clone(),
+                links,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.entries.remove(id);
+    }
+
+    /// Resolves the text of a link, first as an entry id and then as a
+    /// case-insensitive title.
+    pub fn resolve(&self, text: &str) -> Resolution {
+        let text = text.trim();
+        if self.entries.contains_key(text) {
+            return Resolution::Resolved(text.to_string());
+        }
+
+        let wanted = normalize(text);
+        let mut candidates = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.title.as_deref().map(normalize) == Some(wanted.clone()))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<String>>();
+        candidates.sort();
+
+        match candidates.len() {
+            0 => Resolution::Unresolved,
+            1 => Resolution::Resolved(candidates.remove(0)),
+            _ => Resolution::Ambiguous(candidates),
+        }
+    }
+
+    pub fn graph(&self) -> Graph {
+        let mut graph = Graph::default();
+
+        for (id, entry) in self.entries.iter() {
+            graph.nodes.push(GraphNode {
+                id: id.clone(),
+                title: entry.title.clone(),
+            });
+
+            for text in entry.links.iter() {
+                match self.resolve(text) {
+                    Resolution::Resolved(target) => graph.edges.push(GraphEdge {
+                        source: id.clone(),
+                        target,
+                        text: text.clone(),
+                    }),
+                    Resolution::Ambiguous(candidates) => graph.ambiguous.push(AmbiguousLink {
+                        source: id.clone(),
+                        text: text.clone(),
+                        candidates,
+                    }),
+                    Resolution::Unresolved => graph.unresolved.push(UnresolvedLink {
+                        source: id.clone(),
+                        text: text.clone(),
+                    }),
+                }
+            }
+        }
+
+        graph
+            .nodes
+            .sort_by(|a, b| a.title.cmp(&b.title).then(a.id.cmp(&b.id)));
+        graph
+            .edges
+            .sort_by(|a, b| a.source.cmp(&b.source).then(a.target.cmp(&b.target)));
+        graph
+    }
+
+    pub fn backlinks(&self, id: &str) -> Vec<GraphNode> {
+        let mut sources = self
+            .entries
+            .iter()
+            .filter(|(source, entry)| {
+                *source != id
+                    && entry
+                        .links
+                        .iter()
+                        .any(|text| self.resolve(text) == Resolution::Resolved(id.to_string()))
+            })
+            .map(|(source, entry)| GraphNode {
+                id: source.clone(),
+                title: entry.title.clone(),
+            })
+            .collect::<Vec<GraphNode>>();
+        sources.sort_by(|a, b| a.title.cmp(&b.title).then(a.id.cmp(&b.id)));
+        sources
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+}
+
+impl Records {
+    async fn link_index(
+        &self,
+        config: &Config,
+    ) -> Result<MappedMutexGuard<'_, LinkIndex>, AppError> {
+        let mut guard = self.links.lock().await;
+        if guard.is_none() {
+            let journals: Vec<Journal> = list_files_in_a_dir(
+                config.storage.to_string_lossy().as_ref(),
+                get_key(config.secret.clone()),
+            )?;
+            *guard = Some(LinkIndex::from_journals(&journals));
+        }
+        Ok(MutexGuard::map(guard, |index| {
+            index.get_or_insert_with(LinkIndex::default)
+        }))
+    }
+
+    pub(crate) async fn index_links(
+        &self,
+        journal: &Journal,
+        config: Arc<Config>,
+    ) -> Result<(), AppError> {
+        self.link_index(&config).await?.upsert(journal);
+        Ok(())
+    }
+
+    /// The other entries with a `[[old_title]]` link, when it currently
+    /// resolves to `id`.
+    pub(crate) async fn link_sources(
+        &self,
+        id: &String,
+        old_title: &str,
+        config: Arc<Config>,
+    ) -> Result<Vec<String>, AppError> {
+        let index = self.link_index(&config).await?;
+        if index.resolve(old_title) != Resolution::Resolved(id.clone()) {
+            return Ok(vec![]);
+        }
+
+        Ok(index
+            .entries
+            .iter()
+            .filter(|(source, entry)| {
+                *source != id
+                    && entry
+                        .links
+                        .iter()
+                        .any(|text| normalize(text) == normalize(old_title))
+            })
+            .map(|(source, _)| source.clone())
+            .collect())
+    }
+
+    /// Rewrites `[[old_title]]` to `[[new_title]]` in the entries `sources`.
+    pub(crate) async fn rename_links(
+        &self,
+        sources: &[String],
+        old_title: &str,
+        new_title: &str,
+        config: Arc<Config>,
+    ) -> Result<(), AppError> {
+        for source in sources {
+            let _guard = self.lock_entry(source).await;
+            let mut journal = match self.find_by_id(source, config.clone()).await {
+                Ok(journal) => journal,
+                // Deleted since the sources were looked up.
+                Err(AppError::NotFound(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            let body = journal
+                .body
+                .as_deref()
+                .map(|body| rename_wiki_links(body, old_title, new_title));
+            if body == journal.body {
+                continue;
+            }
+            journal.body = body;
+            save_journal(&journal, &config)?;
+            self.index_links(&journal, config.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn backlinks(
+        &self,
+        id: &str,
+        config: Arc<Config>,
+    ) -> Result<Vec<GraphNode>, AppError> {
+        let index = self.link_index(&config).await?;
+        if !index.contains(id) {
+            return Err(AppError::NotFound(format!("Journal {} not found", id)));
+        }
+        Ok(index.backlinks(id))
+    }
+
+    pub async fn graph(&self, config: Arc<Config>) -> Result<Graph, AppError> {
+        Ok(self.link_index(&config).await?.graph())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_vault;
+
+    fn journal(id: &str, title: Option<&str>, body: &str) -> Journal {
+        Journal::new(
+            id.to_string(),
+            title.map(str::to_string),
+            Some(body.to_string()),
+            vec![],
+        )
+    }
+
+    fn index() -> LinkIndex {
+        LinkIndex::from_journals(&[
+            journal("a", Some("Paris"), "[[Lyon]] and [[lyon|the city]]"),
+            journal(
+                "b",
+                Some("Lyon"),
+                "Back to [[ PARIS ]], [[c]] and [[Nowhere]]",
+            ),
+            journal("c", Some("Notes"), "[[Notes]]"),
+            journal("d", Some("notes"), "[[Paris|home]]"),
+        ])
+    }
+
+    #[test]
+    fn resolves_ids_before_titles() {
+        let index = index();
+        assert_eq!(index.resolve(" c "), Resolution::Resolved("c".to_string()));
+        assert_eq!(
+            index.resolve("pARIS"),
+            Resolution::Resolved("a".to_string())
+        );
+        assert_eq!(
+            index.resolve("Notes"),
+            Resolution::Ambiguous(vec!["c".to_string(), "d".to_string()])
+        );
+        assert_eq!(index.resolve("Nowhere"), Resolution::Unresolved);
+    }
+
+    #[test]
+    fn builds_the_graph_and_backlinks() {
+        let graph = index().graph();
+        let edges: Vec<(&str, &str, &str)> = graph
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.source.as_str(),
+                    edge.target.as_str(),
+                    edge.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("a", "b", "Lyon"),
+                ("a", "b", "lyon"),
+                ("b", "a", "PARIS"),
+                ("b", "c", "c"),
+                ("d", "a", "Paris"),
+            ]
+        );
+        assert_eq!(graph.ambiguous.len(), 1);
+        assert_eq!(graph.ambiguous[0].source, "c");
+        assert_eq!(graph.unresolved.len(), 1);
+        assert_eq!(graph.unresolved[0].text, "Nowhere");
+
+        let backlinks: Vec<String> = index()
+            .backlinks("a")
+            .into_iter()
+            .map(|node| node.id)
+            .collect();
+        assert_eq!(backlinks, ["b", "d"]);
+    }
+
+    #[actix_web::test]
+    async fn renames_links_with_their_labels() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let paris = records
+            .insert(Some("Paris".to_string()), None, vec![], config.clone())
+            .await
+            .unwrap();
+        let body = "See [[paris]], [[Paris|the capital]] and [[Parisian]]";
+        let source = records
+            .insert(None, Some(body.to_string()), vec![], config.clone())
+            .await
+            .unwrap();
+
+        records
+            .update(
+                &paris,
+                Some("Lutetia".to_string()),
+                None,
+                vec![],
+                config.clone(),
+            )
+            .await
+            .unwrap();
+        let renamed = records.find_by_id(&source, config.clone()).await.unwrap();
+        assert_eq!(
+            renamed.body.as_deref(),
+            Some("See [[Lutetia]], [[Lutetia|the capital]] and [[Parisian]]")
+        );
+        let backlinks = records.backlinks(&paris, config).await.unwrap();
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].id, source);
+    }
+}
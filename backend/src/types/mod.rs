@@ -1,197 +1,540 @@
 use std::{
-    collections::HashMap,
-    fs,
-    io::{self, Error},
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedMutexGuard, RwLock};
 use uuid::Uuid;
 
-use crate::utils::{
-    decrypt_data, encrypt_data, get_key, list_files_in_a_dir, read_file, write_to_file,
-    write_to_md_file,
+use crate::{
+    cli::Cli,
+    error::AppError,
+    utils::{decrypt_data, encrypt_data, get_key, list_files_in_a_dir, read_file, write_to_file},
 };
 
+mod attachments;
+mod backup;
+mod drafts;
+mod filters;
+mod history;
+mod links;
+mod tasks;
+mod templates;
+mod tokens;
+
+pub use attachments::*;
+pub use backup::*;
+pub use drafts::*;
+pub use filters::*;
+pub use history::*;
+pub use links::*;
+pub use shared::{
+    CreateUpdateRequest, DiskInfo, ErrorResponse, ExportOptions, ImportFormat, ImportOptions,
+    ImportReport, ImportedEntry, Journal, ProcessInfo, ReloadReport, SkippedEntry, SystemInfo,
+};
+pub use tasks::*;
+pub use templates::*;
+pub use tokens::*;
+
+/// Every key `Config` reads, for reporting unknown ones.
+pub const CONFIG_KEYS: &[&str] = &[
+    "storage",
+    "secret",
+    "max_attachment_size",
+    "max_attachments_per_entry",
+    "max_import_size",
+    "cors_origins",
+    "host",
+    "port",
+    "unix_socket",
+    "workers",
+    "backup_dir",
+    "backup_interval_hours",
+    "backup_keep",
+    "backup_passphrase",
+    "git",
+];
+
+/// Keys whose values are never shown or backed up.
+const SECRET_KEYS: &[&str] = &["secret", "backup_passphrase"];
+
+/// Shortest passphrase a backup is encrypted with.
+pub const MIN_PASSPHRASE_LENGTH: usize = 8;
+
+/// The example secret this repository used to ship in `conf.toml`.
+const EXAMPLE_SECRET: &str = "example32bytekey1234567890abcdef";
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     storage: PathBuf,
     secret: String,
+    /// Largest accepted attachment, in bytes.
+    #[serde(default = "default_max_attachment_size")]
+    max_attachment_size: u64,
+    #[serde(default = "default_max_attachments_per_entry")]
+    max_attachments_per_entry: usize,
+    /// Largest accepted import file, in bytes. Vault zips and Day One
+    /// exports are often far larger than any single attachment.
+    #[serde(default = "default_max_import_size")]
+    max_import_size: u64,
+    /// Origins, such as `http://localhost:8080`, allowed to call the API from
+    /// a browser. None are by default.
+    #[serde(default)]
+    cors_origins: Vec<String>,
+    #[serde(default = "default_host")]
+    host: String,
+    /// 0 picks a free port, see `<storage>/server.url` for the one in use.
+    #[serde(default = "default_port")]
+    port: u16,
+    /// Listen on this Unix domain socket instead of `host` and `port`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    unix_socket: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    workers: Option<usize>,
+    /// Directory scheduled backups are written to. They are off when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backup_dir: Option<PathBuf>,
+    #[serde(default = "default_backup_interval_hours")]
+    backup_interval_hours: u64,
+    /// How many scheduled backups are kept, older ones are deleted.
+    #[serde(default = "default_backup_keep")]
+    backup_keep: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backup_passphrase: Option<String>,
+    /// Keep the storage directory in a git repository, committing every
+    /// change.
+    #[serde(default)]
+    git: bool,
 }
 
-impl std::str::FromStr for Config {
-    type Err = io::Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        toml::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
-    }
+/// When and where scheduled backups are made.
+#[derive(Debug, Clone, Copy)]
+pub struct BackupSchedule<'a> {
+    pub dir: &'a Path,
+    pub interval: Duration,
+    pub keep: usize,
+    pub passphrase: &'a str,
 }
 
-#[derive(Serialize)]
-pub struct SystemInfo {
-    pub disk_usage: Vec<DiskInfo>,
-    pub current_user: String,
-    pub top_cpu_processes: Vec<ProcessInfo>,
+fn default_host() -> String {
+    "127.0.0.1".to_string()
 }
 
-#[derive(Serialize)]
-pub struct DiskInfo {
-    pub mount_point: String,
-    pub total_space: u64,
-    pub available_space: u64,
-    pub used_percentage: f64,
+fn default_port() -> u16 {
+    7000
 }
 
-#[derive(Serialize)]
-pub struct ProcessInfo {
-    pub pid: u32,
-    pub name: String,
-    pub cpu_usage: f32,
+fn default_max_attachment_size() -> u64 {
+    10 * 1024 * 1024
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
-pub struct CreateUpdateRequest {
-    pub title: Option<String>,
-    pub body: Option<String>,
-    #[serde(default)]
-    pub tags: Vec<String>,
+fn default_max_attachments_per_entry() -> usize {
+    20
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
-pub struct EncryptedFile {
-    pub content: String,
-    pub nonce: String,
+fn default_max_import_size() -> u64 {
+    100 * 1024 * 1024
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
-pub struct Journal {
-    pub id: String,
-    pub title: Option<String>,
-    pub body: Option<String>,
-    pub tags: Vec<String>,
+fn default_backup_interval_hours() -> u64 {
+    24
+}
+
+fn default_backup_keep() -> usize {
+    7
 }
 
-impl Journal {
-    fn new(id: String, title: Option<String>, body: Option<String>, tags: Vec<String>) -> Self {
-        Journal {
-            id,
-            title,
-            body,
-            tags: tags,
+impl Config {
+    pub fn new(storage: PathBuf, secret: String) -> Self {
+        Config {
+            storage,
+            secret,
+            max_attachment_size: default_max_attachment_size(),
+            max_attachments_per_entry: default_max_attachments_per_entry(),
+            max_import_size: default_max_import_size(),
+            cors_origins: vec![],
+            host: default_host(),
+            port: default_port(),
+            unix_socket: None,
+            workers: None,
+            backup_dir: None,
+            backup_interval_hours: default_backup_interval_hours(),
+            backup_keep: default_backup_keep(),
+            backup_passphrase: None,
+            git: false,
+        }
+    }
+
+    /// Applies the settings given on the command line or in the environment.
+    pub fn with_overrides(mut self, cli: &Cli) -> Self {
+        if let Some(host) = &cli.host {
+            self.host = host.clone();
+        }
+        if let Some(port) = cli.port {
+            self.port = port;
+        }
+        if let Some(unix_socket) = &cli.unix_socket {
+            self.unix_socket = Some(unix_socket.clone());
+        }
+        if let Some(workers) = cli.workers {
+            self.workers = Some(workers);
+        }
+        self
+    }
+
+    pub fn storage(&self) -> &Path {
+        &self.storage
+    }
+
+    pub fn max_attachment_size(&self) -> u64 {
+        self.max_attachment_size
+    }
+
+    pub fn max_import_size(&self) -> u64 {
+        self.max_import_size
+    }
+
+    pub fn cors_origins(&self) -> &[String] {
+        &self.cors_origins
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn unix_socket(&self) -> Option<&Path> {
+        self.unix_socket.as_deref()
+    }
+
+    pub fn workers(&self) -> Option<usize> {
+        self.workers
+    }
+
+    pub fn git(&self) -> bool {
+        self.git
+    }
+
+    /// `None` unless scheduled backups are configured.
+    pub fn backup_schedule(&self) -> Option<BackupSchedule<'_>> {
+        Some(BackupSchedule {
+            dir: self.backup_dir.as_deref()?,
+            interval: Duration::from_secs(self.backup_interval_hours * 60 * 60),
+            keep: self.backup_keep,
+            passphrase: self.backup_passphrase.as_deref()?,
+        })
+    }
+
+    /// The config as TOML, leaving out the secret and backup passphrase.
+    pub fn without_secrets(&self) -> Result<toml::Table, AppError> {
+        let mut table = toml::Table::try_from(self)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        for key in SECRET_KEYS {
+            table.remove(*key);
+        }
+        Ok(table)
+    }
+
+    /// Settings that work but should not. A weak secret is only warned
+    /// about: the journals are encrypted with it and can't be read with
+    /// another one.
+    pub fn warnings(&self) -> Vec<String> {
+        let weak =
+            self.secret == EXAMPLE_SECRET || self.secret.chars().collect::<HashSet<_>>().len() < 8;
+        match self.secret.len() == 32 && weak {
+            true => vec![
+                "`secret` is weak. Existing journals can only be read with it, so keep it, but \
+                 start new vaults with a random one like `backend init` generates"
+                    .to_string(),
+            ],
+            false => vec![],
+        }
+    }
+
+    /// Everything wrong with the values, as opposed to the syntax, of the config.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        if !self.storage.is_dir() {
+            problems.push(format!(
+                "`storage`: {} does not exist or is not a directory",
+                self.storage.display()
+            ));
+        } else {
+            let probe = self.storage.join(format!(".write-test-{}", Uuid::new_v4()));
+            match fs::write(&probe, b"") {
+                Ok(_) => {
+                    let _ = fs::remove_file(probe);
+                }
+                Err(err) => problems.push(format!(
+                    "`storage`: {} is not writable: {}",
+                    self.storage.display(),
+                    err
+                )),
+            }
+        }
+
+        if self.secret.len() != 32 {
+            problems.push(format!(
+                "`secret` must be exactly 32 bytes long, it is {}",
+                self.secret.len()
+            ));
+        }
+
+        if let Some(dir) = &self.backup_dir {
+            if !dir.is_dir() {
+                problems.push(format!(
+                    "`backup_dir`: {} does not exist or is not a directory",
+                    dir.display()
+                ));
+            }
+            match &self.backup_passphrase {
+                None => problems
+                    .push("`backup_passphrase` is required when `backup_dir` is set".to_string()),
+                Some(passphrase) if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH => problems
+                    .push(format!(
+                        "`backup_passphrase` must be at least {} characters long",
+                        MIN_PASSPHRASE_LENGTH
+                    )),
+                Some(_) => {}
+            }
+        }
+        if self.backup_interval_hours == 0 {
+            problems.push("`backup_interval_hours` must be at least 1".to_string());
+        }
+        if self.backup_keep == 0 {
+            problems.push("`backup_keep` must be at least 1".to_string());
         }
+
+        if self.workers == Some(0) {
+            problems.push("`workers` must be at least 1".to_string());
+        }
+        if let Some(parent) = self.unix_socket.as_deref().and_then(Path::parent)
+            && !parent.as_os_str().is_empty()
+            && !parent.is_dir()
+        {
+            problems.push(format!(
+                "`unix_socket`: directory {} does not exist",
+                parent.display()
+            ));
+        }
+        for origin in &self.cors_origins {
+            if !origin.starts_with("http://") && !origin.starts_with("https://") {
+                problems.push(format!(
+                    "`cors_origins`: {} must start with http:// or https://",
+                    origin
+                ));
+            }
+        }
+
+        problems
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct EncryptedFile {
+    pub content: String,
+    pub nonce: String,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Records {
-    pub records: Arc<Mutex<HashMap<String, Journal>>>,
+    links: Arc<Mutex<Option<LinkIndex>>>,
+    /// A lock per entry id, held from reading an entry until it is written
+    /// back, so concurrent changes to it don't overwrite each other.
+    entries: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    /// Shared while blobs are written and the entries referring to them are
+    /// saved, exclusive while unreferenced blobs are deleted.
+    blobs: Arc<RwLock<()>>,
+    /// Held while committing, so concurrent changes get commits of their own.
+    git: Arc<Mutex<()>>,
 }
 
 impl Records {
     pub fn new() -> Self {
         Records {
-            records: Arc::new(Mutex::new(HashMap::new())),
+            links: Arc::new(Mutex::new(None)),
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            blobs: Arc::new(RwLock::new(())),
+            git: Arc::new(Mutex::new(())),
         }
     }
 
+    /// Waits until no other change to entry `id` is in progress, and keeps
+    /// others out until the guard is dropped.
+    pub(crate) async fn lock_entry(&self, id: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.entries.lock().await;
+            // Locks only the map refers to are neither held nor awaited.
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(id.to_string()).or_default().clone()
+        };
+        lock.lock_owned().await
+    }
+
+    /// Drops what is cached about the storage directory, after it changed.
+    pub async fn clear_cache(&self) {
+        *self.links.lock().await = None;
+    }
+
     pub async fn insert(
         &self,
         title: Option<String>,
         body: Option<String>,
         tags: Vec<String>,
         config: Arc<Config>,
-    ) -> Result<String, Error> {
+    ) -> Result<String, AppError> {
         let id = Uuid::new_v4().to_string();
-        let journal = Journal::new(id.clone(), title, body, tags);
-        if let Ok(stringified) = serde_json::to_string(&journal) {
-            let key = get_key(config.secret.clone());
-            match encrypt_data(&stringified, &key) {
-                Ok((text, nonce)) => {
-                    write_to_file(
-                        config.storage.to_string_lossy().to_string(),
-                        id.clone(),
-                        text,
-                        nonce,
-                    )?;
-                    Ok(id)
-                }
-                Err(err) => Err(Error::new(io::ErrorKind::Other, err.to_string())),
-            }
-        } else {
-            Err(Error::new(
-                io::ErrorKind::InvalidData,
-                "Unable to convert data to string",
-            ))
-        }
-    }
-
-    pub async fn find_by_id(&self, id: &String, config: Arc<Config>) -> Result<Journal, io::Error> {
-        match read_file(&format!("{}/{}.txt", config.storage.to_string_lossy(), id)) {
-            Ok(encrypted) => {
-                let key = get_key(config.secret.clone());
-                match decrypt_data(&encrypted.content, &encrypted.nonce, &key) {
-                    Ok(stringified) => {
-                        let parsed =
-                            serde_json::from_str::<Journal>(&stringified).map_err(|err| {
-                                io::Error::new(io::ErrorKind::InvalidData, err.to_string())
-                            })?;
-                        Ok(parsed)
-                    }
-                    Err(err) => Err(io::Error::new(io::ErrorKind::Other, err.to_string())),
-                }
+        let mut journal = Journal::new(id.clone(), title, body, tags);
+        journal.created_at = Some(Utc::now().to_rfc3339());
+        journal.updated_at = journal.created_at.clone();
+        self.insert_journal(&journal, config.clone()).await?;
+        self.commit(&config, format!("Create entry {}", journal.id))
+            .await;
+        Ok(id)
+    }
+
+    /// Stores `journal` as a new entry, keeping its id and timestamps.
+    pub async fn insert_journal(
+        &self,
+        journal: &Journal,
+        config: Arc<Config>,
+    ) -> Result<(), AppError> {
+        save_journal(journal, &config)?;
+        self.index_links(journal, config).await
+    }
+
+    /// Replaces the stored entry and, when its title changes, rewrites the
+    /// `[[Old Title]]` links pointing at it in every other entry.
+    pub async fn update(
+        &self,
+        id: &String,
+        title: Option<String>,
+        body: Option<String>,
+        tags: Vec<String>,
+        config: Arc<Config>,
+    ) -> Result<Journal, AppError> {
+        let guard = self.lock_entry(id).await;
+        let previous = self.find_by_id(id, config.clone()).await?;
+        let mut journal = Journal::new(id.clone(), title, body, tags);
+        journal.attachments = previous.attachments.clone();
+        journal.created_at = previous.created_at.clone();
+        journal.updated_at = Some(Utc::now().to_rfc3339());
+
+        // Looked up while the old title still resolves to this entry.
+        let renamed = match (&previous.title, &journal.title) {
+            (Some(old_title), Some(new_title)) if old_title != new_title => {
+                let sources = self.link_sources(id, old_title, config.clone()).await?;
+                Some((sources, old_title, new_title))
             }
-            Err(err) => Err(err),
+            _ => None,
+        };
+
+        save_journal(&journal, &config)?;
+        self.index_links(&journal, config.clone()).await?;
+        // The linking entries are locked one at a time, without holding this
+        // one, so two entries renamed at once can't wait on each other.
+        drop(guard);
+        if let Some((sources, old_title, new_title)) = renamed {
+            self.rename_links(&sources, old_title, new_title, config.clone())
+                .await?;
         }
+        self.commit(&config, format!("Update entry {}", journal.id))
+            .await;
+        Ok(journal)
     }
 
-    pub async fn find_by_tag(
+    pub async fn find_by_id(&self, id: &String, config: Arc<Config>) -> Result<Journal, AppError> {
+        let encrypted = read_file(&format!("{}/{}.json", config.storage.to_string_lossy(), id))
+            .map_err(|err| {
+                AppError::from(err).with_not_found_message(|| format!("Journal {} not found", id))
+            })?;
+        let key = get_key(config.secret.clone());
+        let stringified = decrypt_data(&encrypted.content, &encrypted.nonce, &key)?;
+        Ok(serde_json::from_str::<Journal>(&stringified)?)
+    }
+
+    pub async fn find(
         &self,
-        tag: Option<String>,
+        filter: &EntryFilter,
         config: Arc<Config>,
-    ) -> Result<Vec<Journal>, io::Error> {
-        let mut result = vec![];
-
-        let files_list = list_files_in_a_dir(
-            &config.storage.to_string_lossy().to_string(),
+    ) -> Result<Vec<Journal>, AppError> {
+        let files_list: Vec<Journal> = list_files_in_a_dir(
+            config.storage.to_string_lossy().as_ref(),
             get_key(config.secret.clone()),
         )?;
 
-        if let Some(tag) = tag {
-            for journal in files_list.iter() {
-                if journal.tags.contains(&tag) {
-                    result.push(journal.clone());
-                }
-            }
-            return Ok(result);
-        }
+        *self.links.lock().await = Some(LinkIndex::from_journals(&files_list));
 
-        Ok(files_list)
+        filter_entries(files_list, filter)
     }
 
-    pub async fn delete_by_id(&self, id: &String, config: Arc<Config>) -> Result<(), io::Error> {
-        let path = format!(
-            "{}/{}.json",
-            config.storage.to_string_lossy().to_string(),
-            id
-        );
+    pub async fn delete_by_id(&self, id: &String, config: Arc<Config>) -> Result<(), AppError> {
+        let _guard = self.lock_entry(id).await;
+        let attachments = self
+            .find_by_id(id, config.clone())
+            .await
+            .map(|journal| journal.attachments)
+            .unwrap_or_default();
+
+        let path = format!("{}/{}.json", config.storage.to_string_lossy(), id);
         match fs::remove_file(path) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(io::Error::new(io::ErrorKind::NotFound, err.to_string())),
+            Ok(_) => {
+                if let Some(index) = self.links.lock().await.as_mut() {
+                    index.remove(id);
+                }
+                self.remove_unreferenced_blobs(&attachments, config.clone())
+                    .await?;
+                self.commit(&config, format!("Delete entry {}", id)).await;
+                Ok(())
+            }
+            Err(_) => Err(AppError::NotFound(format!("Journal {} not found", id))),
         }
     }
 
-    pub async fn export(&self, file_name: &str, config: Arc<Config>) -> Result<(), std::io::Error> {
-        let files_list = list_files_in_a_dir(
-            &config.storage.to_string_lossy().to_string(),
+    /// The entries to export, selected the same way as for listing.
+    pub async fn export(
+        &self,
+        filter: &EntryFilter,
+        config: Arc<Config>,
+    ) -> Result<Vec<Journal>, AppError> {
+        let files_list: Vec<Journal> = list_files_in_a_dir(
+            config.storage.to_string_lossy().as_ref(),
             get_key(config.secret.clone()),
         )?;
 
-        let mut result = vec![];
-        for journal in files_list {
-            result.push(journal.clone());
-        }
-
-        write_to_md_file(result, file_name)
+        filter_entries(files_list, filter)
     }
 }
+
+fn save_journal(journal: &Journal, config: &Config) -> Result<(), AppError> {
+    let stringified = serde_json::to_string(journal)?;
+    let key = get_key(config.secret.clone());
+    let (text, nonce) = encrypt_data(&stringified, &key)?;
+    Ok(write_to_file(
+        config.storage.to_string_lossy().to_string(),
+        journal.id.clone(),
+        text,
+        nonce,
+    )?)
+}
+
+/// A vault in a temporary directory, with the config adjusted by `configure`.
+#[cfg(test)]
+pub(crate) fn test_vault(configure: impl FnOnce(&mut Config)) -> (tempfile::TempDir, Arc<Config>) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let mut config = Config::new(
+        dir.path().to_path_buf(),
+        "0123456789abcdef0123456789abcdef".to_string(),
+    );
+    configure(&mut config);
+    (dir, Arc::new(config))
+}
@@ -0,0 +1,165 @@
+use shared::Journal;
+
+/// Tags marking an entry as a task, and whether it is done.
+pub const DONE_TAG: &str = "done";
+pub const TODO_TAG: &str = "todo";
+
+/// Body lines such as `- due: 2024-03-10` that describe a task, as the
+/// importers write them.
+const DUE_KEY: &str = "due";
+const RECURRENCE_KEYS: [&str; 3] = ["rrule", "recur", "rec"];
+
+/// The task side of an entry tagged `todo` or `done`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub done: bool,
+    /// A date, or an RFC 3339 timestamp.
+    pub due: Option<String>,
+    /// An iCalendar RRULE value, such as `FREQ=WEEKLY;INTERVAL=2`.
+    pub rrule: Option<String>,
+}
+
+impl Task {
+    pub fn of(journal: &Journal) -> Option<Task> {
+        let done = journal.tags.iter().any(|tag| tag == DONE_TAG);
+        if !done && !journal.tags.iter().any(|tag| tag == TODO_TAG) {
+            return None;
+        }
+
+        let (due, rrule) = journal.body.as_deref().map(task_fields).unwrap_or_default();
+        Some(Task { done, due, rrule })
+    }
+}
+
+/// The due date and RRULE given by the lines of `body`.
+pub fn task_fields(body: &str) -> (Option<String>, Option<String>) {
+    let (mut due, mut rrule) = (None, None);
+    for (key, value) in body.lines().filter_map(field) {
+        match key.as_str() {
+            DUE_KEY => due = due.or(Some(value.to_string())),
+            "rrule" => rrule = Some(value.to_string()),
+            _ => rrule = rrule.or_else(|| recurrence_rule(value)),
+        }
+    }
+    (due, rrule)
+}
+
+/// The key and value of a `- key: value` line describing the task.
+fn field(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    let (key, value) = line.strip_prefix("- ").unwrap_or(line).split_once(':')?;
+    let key = key.trim().to_lowercase();
+    let value = value.trim();
+    let known = key == DUE_KEY || RECURRENCE_KEYS.contains(&key.as_str());
+    (known && !value.is_empty()).then_some((key, value))
+}
+
+/// A recurrence as Taskwarrior (`weekly`, `2wk`) or todo.txt (`+1m`) write
+/// it, as an RRULE value.
+pub fn recurrence_rule(value: &str) -> Option<String> {
+    let value = value.trim().trim_start_matches('+').to_lowercase();
+    let named = match value.as_str() {
+        "daily" => Some("FREQ=DAILY"),
+        "weekdays" => Some("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR"),
+        "weekly" => Some("FREQ=WEEKLY"),
+        "biweekly" | "fortnight" => Some("FREQ=WEEKLY;INTERVAL=2"),
+        "monthly" => Some("FREQ=MONTHLY"),
+        "quarterly" => Some("FREQ=MONTHLY;INTERVAL=3"),
+        "yearly" | "annual" => Some("FREQ=YEARLY"),
+        _ => None,
+    };
+    if let Some(named) = named {
+        return Some(named.to_string());
+    }
+
+    let unit_start = value.find(|c: char| !c.is_ascii_digit())?;
+    let interval: u32 = match &value[..unit_start] {
+        "" => 1,
+        number => number.parse().ok()?,
+    };
+    if interval == 0 {
+        return None;
+    }
+    let frequency = match &value[unit_start..] {
+        "d" | "day" | "days" => "DAILY",
+        "b" => return Some("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR".to_string()),
+        "w" | "wk" | "wks" | "week" | "weeks" => "WEEKLY",
+        "m" | "mo" | "mos" | "month" | "months" => "MONTHLY",
+        "q" | "qtr" | "qtrs" | "quarter" | "quarters" => {
+            return Some(format!("FREQ=MONTHLY;INTERVAL={}", interval * 3));
+        }
+        "y" | "yr" | "yrs" | "year" | "years" => "YEARLY",
+        _ => return None,
+    };
+    Some(match interval {
+        1 => format!("FREQ={}", frequency),
+        interval => format!("FREQ={};INTERVAL={}", frequency, interval),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_recurrences() {
+        let rule = |value| recurrence_rule(value);
+        assert_eq!(rule("weekly").as_deref(), Some("FREQ=WEEKLY"));
+        assert_eq!(rule("Biweekly").as_deref(), Some("FREQ=WEEKLY;INTERVAL=2"));
+        assert_eq!(rule("+1m").as_deref(), Some("FREQ=MONTHLY"));
+        assert_eq!(rule("2wk").as_deref(), Some("FREQ=WEEKLY;INTERVAL=2"));
+        assert_eq!(rule("3days").as_deref(), Some("FREQ=DAILY;INTERVAL=3"));
+        assert_eq!(rule("2q").as_deref(), Some("FREQ=MONTHLY;INTERVAL=6"));
+        assert_eq!(
+            rule("b").as_deref(),
+            Some("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR")
+        );
+        assert_eq!(rule("y").as_deref(), Some("FREQ=YEARLY"));
+        for invalid in ["0d", "0q", "5", "fortnightly", "1x", ""] {
+            assert_eq!(rule(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn reads_task_fields() {
+        assert_eq!(
+            task_fields("Notes\n- due: 2024-03-10\n- Due: 2024-04-01\n- rec: +1w"),
+            (
+                Some("2024-03-10".to_string()),
+                Some("FREQ=WEEKLY".to_string())
+            )
+        );
+        // An RRULE wins over the shorthand, whatever the order.
+        assert_eq!(
+            task_fields("recur: daily\n- rrule: FREQ=MONTHLY;BYMONTHDAY=1\n- recur: weekly"),
+            (None, Some("FREQ=MONTHLY;BYMONTHDAY=1".to_string()))
+        );
+        assert_eq!(
+            task_fields("- due:\n- priority: H\n- rec: whenever"),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn only_tagged_entries_are_tasks() {
+        let entry = |tags: &[&str]| {
+            let tags = tags.iter().map(|tag| tag.to_string()).collect();
+            Journal::new(
+                "id".to_string(),
+                None,
+                Some("- due: 2024-03-10".to_string()),
+                tags,
+            )
+        };
+        assert_eq!(Task::of(&entry(&["work"])), None);
+        assert_eq!(
+            Task::of(&entry(&["todo"])),
+            Some(Task {
+                done: false,
+                due: Some("2024-03-10".to_string()),
+                rrule: None,
+            })
+        );
+        assert!(Task::of(&entry(&["todo", "done"])).unwrap().done);
+    }
+}
@@ -0,0 +1,266 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    types::{Config, CreateUpdateRequest, Records},
+    utils::{
+        builtin_placeholders, decrypt_file, find_placeholders, get_key, list_files_in_a_dir,
+        substitute_placeholders, write_encrypted,
+    },
+};
+
+pub use shared::{RenderTemplateRequest, Template, TemplateRequest};
+
+const TEMPLATES_DIR: &str = "templates";
+
+fn new_template(id: String, request: TemplateRequest) -> Template {
+    let builtins = builtin_placeholders();
+    let mut variables: Vec<String> = vec![];
+    let sources = request
+        .title
+        .iter()
+        .chain(request.body.iter())
+        .chain(request.tags.iter());
+    for text in sources {
+        for name in find_placeholders(text) {
+            if !builtins.contains_key(&name) && !variables.contains(&name) {
+                variables.push(name);
+            }
+        }
+    }
+
+    Template {
+        id,
+        name: request.name,
+        title: request.title,
+        body: request.body,
+        tags: request.tags,
+        variables,
+    }
+}
+
+fn templates_dir(config: &Config) -> PathBuf {
+    config.storage.join(TEMPLATES_DIR)
+}
+
+pub(super) fn template_path(config: &Config, id: &str) -> String {
+    format!("{}/{}.json", templates_dir(config).to_string_lossy(), id)
+}
+
+pub(super) fn save_template(template: &Template, config: &Config) -> Result<(), AppError> {
+    write_encrypted(
+        &templates_dir(config),
+        &template.id,
+        template,
+        &get_key(config.secret.clone()),
+    )
+}
+
+impl Records {
+    pub async fn insert_template(
+        &self,
+        request: TemplateRequest,
+        config: Arc<Config>,
+    ) -> Result<Template, AppError> {
+        let template = new_template(Uuid::new_v4().to_string(), request);
+        save_template(&template, &config)?;
+        self.commit(&config, format!("Create template {}", template.id))
+            .await;
+        Ok(template)
+    }
+
+    pub async fn update_template(
+        &self,
+        id: &str,
+        request: TemplateRequest,
+        config: Arc<Config>,
+    ) -> Result<Template, AppError> {
+        self.find_template(id, config.clone()).await?;
+
+        let template = new_template(id.to_string(), request);
+        save_template(&template, &config)?;
+        self.commit(&config, format!("Update template {}", template.id))
+            .await;
+        Ok(template)
+    }
+
+    pub async fn find_template(&self, id: &str, config: Arc<Config>) -> Result<Template, AppError> {
+        let key = get_key(config.secret.clone());
+        decrypt_file(&template_path(&config, id), &key)
+            .map_err(|err| err.with_not_found_message(|| format!("Template {} not found", id)))
+    }
+
+    pub async fn list_templates(&self, config: Arc<Config>) -> Result<Vec<Template>, AppError> {
+        let dir = templates_dir(&config);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut templates: Vec<Template> = list_files_in_a_dir(
+            dir.to_string_lossy().as_ref(),
+            get_key(config.secret.clone()),
+        )?;
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    pub async fn delete_template(&self, id: &str, config: Arc<Config>) -> Result<(), AppError> {
+        let template = self.find_template(id, config.clone()).await?;
+        fs::remove_file(template_path(&config, id))?;
+        self.commit(&config, format!("Delete template {}", template.id))
+            .await;
+        Ok(())
+    }
+
+    /// Fills the template's placeholders with the built-in values and the
+    /// supplied prompt variables, producing a request ready to be submitted.
+    pub async fn render_template(
+        &self,
+        id: &str,
+        request: RenderTemplateRequest,
+        config: Arc<Config>,
+    ) -> Result<CreateUpdateRequest, AppError> {
+        let template = self.find_template(id, config).await?;
+
+        let missing = template
+            .variables
+            .iter()
+            .filter(|name| !request.variables.contains_key(*name))
+            .cloned()
+            .collect::<Vec<String>>();
+        if !missing.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "Missing template variables: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let mut values = request.variables;
+        values.extend(builtin_placeholders());
+
+        Ok(CreateUpdateRequest {
+            title: template
+                .title
+                .map(|title| substitute_placeholders(&title, &values)),
+            body: template
+                .body
+                .map(|body| substitute_placeholders(&body, &values)),
+            tags: template
+                .tags
+                .iter()
+                .map(|tag| substitute_placeholders(tag, &values))
+                .filter(|tag| !tag.trim().is_empty())
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::test_vault;
+
+    fn variables(pairs: &[(&str, &str)]) -> RenderTemplateRequest {
+        RenderTemplateRequest {
+            variables: pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<String, String>>(),
+        }
+    }
+
+    async fn template(records: &Records, config: &Arc<Config>) -> Template {
+        let request = TemplateRequest {
+            name: "Meeting".to_string(),
+            title: Some("{{weekday}} meeting with {{ person }}".to_string()),
+            body: Some("By {{user}} on {{date}} at {{time}}.\n\nAgenda: {{agenda}}".to_string()),
+            tags: vec!["meeting".to_string(), "{{project}}".to_string()],
+        };
+        records
+            .insert_template(request, config.clone())
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn prompts_for_everything_but_the_builtins() {
+        let (_dir, config) = test_vault(|_| {});
+        let template = template(&Records::new(), &config).await;
+        assert_eq!(template.variables, ["person", "agenda", "project"]);
+    }
+
+    #[actix_web::test]
+    async fn fills_in_builtins_and_variables() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let id = template(&records, &config).await.id;
+
+        let request = variables(&[
+            ("person", "Ada"),
+            ("agenda", "{{date}} stays as typed"),
+            ("project", " "),
+            ("unused", "ignored"),
+        ]);
+        let rendered = records.render_template(&id, request, config).await.unwrap();
+
+        let builtins = builtin_placeholders();
+        assert_eq!(
+            rendered.title.unwrap(),
+            format!("{} meeting with Ada", builtins["weekday"])
+        );
+        let body = rendered.body.unwrap();
+        assert!(body.starts_with(&format!(
+            "By {} on {} at ",
+            builtins["user"], builtins["date"]
+        )));
+        assert!(body.ends_with("Agenda: {{date}} stays as typed"));
+        assert!(!body.contains("{{time}}"));
+        assert_eq!(rendered.tags, ["meeting"]);
+    }
+
+    #[actix_web::test]
+    async fn rejects_missing_variables() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let id = template(&records, &config).await.id;
+
+        let request = variables(&[("person", "Ada")]);
+        match records.render_template(&id, request, config).await {
+            Err(AppError::BadRequest(message)) => {
+                assert_eq!(message, "Missing template variables: agenda, project")
+            }
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[actix_web::test]
+    async fn leaves_text_that_is_no_placeholder_alone() {
+        let (_dir, config) = test_vault(|_| {});
+        let records = Records::new();
+        let request = TemplateRequest {
+            name: "Braces".to_string(),
+            title: None,
+            body: Some("{{}} {{two\nlines}} \\{{date}} {{open".to_string()),
+            tags: vec![],
+        };
+        let template = records
+            .insert_template(request, config.clone())
+            .await
+            .unwrap();
+        assert!(template.variables.is_empty());
+
+        let rendered = records
+            .render_template(&template.id, variables(&[]), config)
+            .await
+            .unwrap();
+        assert_eq!(rendered.title, None);
+        assert_eq!(
+            rendered.body.unwrap(),
+            "{{}} {{two\nlines}} \\{{date}} {{open"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use aes_gcm::aead::{OsRng, rand_core::RngCore};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use shared::write_private;
+
+use crate::{error::AppError, types::Config};
+
+pub use shared::{ApiToken, CreatedToken, TokenRequest, TokenScope};
+
+const AUTH_DIR: &str = "auth";
+const TOKENS_FILE: &str = "tokens.json";
+/// Holds the secret of the token generated on first run, for the first client
+/// to pick up.
+const FIRST_TOKEN_FILE: &str = "token";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    #[serde(flatten)]
+    token: ApiToken,
+    /// SHA-256 of the secret; the secret itself is never stored.
+    hash: String,
+    /// Session tokens live in memory only and are gone after a restart.
+    #[serde(skip)]
+    session: bool,
+}
+
+#[derive(Debug)]
+struct TokenStore {
+    dir: PathBuf,
+    tokens: Vec<StoredToken>,
+}
+
+/// The API tokens accepted by the server, kept in `<storage>/auth/tokens.json`.
+#[derive(Debug, Clone)]
+pub struct Tokens {
+    store: Arc<Mutex<TokenStore>>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex(&Sha256::digest(secret.as_bytes()))
+}
+
+fn new_token(request: TokenRequest, session: bool) -> (StoredToken, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let secret = format!("tj_{}", hex(&bytes));
+
+    let token = StoredToken {
+        token: ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name: request.name,
+            scope: request.scope,
+            created_at: Utc::now().to_rfc3339(),
+        },
+        hash: hash_secret(&secret),
+        session,
+    };
+    (token, secret)
+}
+
+fn write_tokens(dir: &Path, tokens: &[StoredToken]) -> io::Result<()> {
+    let persisted: Vec<&StoredToken> = tokens.iter().filter(|token| !token.session).collect();
+    let content = serde_json::to_vec_pretty(&persisted)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    write_private(&dir.join(TOKENS_FILE), &content)
+}
+
+/// Loads the stored tokens. On first run a read-write token is generated and
+/// its secret written to `<storage>/auth/token`.
+fn load_store(config: &Config) -> io::Result<TokenStore> {
+    let dir = config.storage().join(AUTH_DIR);
+    let path = dir.join(TOKENS_FILE);
+
+    let tokens = if path.exists() {
+        serde_json::from_str::<Vec<StoredToken>>(&fs::read_to_string(&path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+    } else {
+        fs::create_dir_all(&dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+        }
+
+        let (token, secret) = new_token(
+            TokenRequest {
+                name: "default".to_string(),
+                scope: TokenScope::ReadWrite,
+            },
+            false,
+        );
+        let tokens = vec![token];
+        write_tokens(&dir, &tokens)?;
+        let first_token = dir.join(FIRST_TOKEN_FILE);
+        write_private(&first_token, secret.as_bytes())?;
+        eprintln!("Generated an API token in {}", first_token.display());
+        tokens
+    };
+
+    Ok(TokenStore { dir, tokens })
+}
+
+impl Tokens {
+    pub fn load(config: &Config) -> io::Result<Self> {
+        Ok(Tokens {
+            store: Arc::new(Mutex::new(load_store(config)?)),
+        })
+    }
+
+    /// Switches to the tokens stored under the storage directory of `config`,
+    /// keeping the session tokens.
+    pub async fn reload(&self, config: &Config) -> io::Result<()> {
+        let mut loaded = load_store(config)?;
+        let mut store = self.store.lock().await;
+        loaded
+            .tokens
+            .extend(store.tokens.iter().filter(|token| token.session).cloned());
+        *store = loaded;
+        Ok(())
+    }
+
+    /// Adds a read-write token that is not persisted, for a client that lives
+    /// exactly as long as this server, such as the desktop app embedding it.
+    pub async fn create_session_token(&self, name: &str) -> String {
+        let (token, secret) = new_token(
+            TokenRequest {
+                name: name.to_string(),
+                scope: TokenScope::ReadWrite,
+            },
+            true,
+        );
+        self.store.lock().await.tokens.push(token);
+        secret
+    }
+
+    /// The token with this secret, unless it is unknown or revoked.
+    pub async fn authenticate(&self, secret: &str) -> Option<ApiToken> {
+        let hash = hash_secret(secret);
+        self.store
+            .lock()
+            .await
+            .tokens
+            .iter()
+            .find(|token| token.hash == hash)
+            .map(|token| token.token.clone())
+    }
+
+    pub async fn list(&self) -> Vec<ApiToken> {
+        self.store
+            .lock()
+            .await
+            .tokens
+            .iter()
+            .map(|token| token.token.clone())
+            .collect()
+    }
+
+    pub async fn create(&self, request: TokenRequest) -> Result<CreatedToken, AppError> {
+        if request.name.trim().is_empty() {
+            return Err(AppError::BadRequest("Token name is required".to_string()));
+        }
+
+        let (token, secret) = new_token(request, false);
+        let created = CreatedToken {
+            token: token.token.clone(),
+            secret,
+        };
+
+        let mut store = self.store.lock().await;
+        store.tokens.push(token);
+        write_tokens(&store.dir, &store.tokens)?;
+        Ok(created)
+    }
+
+    pub async fn revoke(&self, id: &str) -> Result<(), AppError> {
+        let mut store = self.store.lock().await;
+        let index = store
+            .tokens
+            .iter()
+            .position(|token| token.token.id == id)
+            .ok_or_else(|| AppError::NotFound(format!("Token {} not found", id)))?;
+        let revoked = store.tokens.remove(index);
+        write_tokens(&store.dir, &store.tokens)?;
+
+        // Don't leave the secret of a revoked first-run token lying around.
+        let first_token = store.dir.join(FIRST_TOKEN_FILE);
+        if fs::read_to_string(&first_token)
+            .is_ok_and(|secret| hash_secret(secret.trim()) == revoked.hash)
+        {
+            fs::remove_file(first_token)?;
+        }
+        Ok(())
+    }
+}
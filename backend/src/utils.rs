@@ -1,6 +1,7 @@
 use std::{
+    collections::HashMap,
     fs::{self, File},
-    io::{self, Error, ErrorKind, Read, Write},
+    io::{self, Read, Write},
     path::Path,
 };
 
@@ -9,38 +10,19 @@ use aes_gcm::{
     aead::{Aead, OsRng},
 };
 use base64::{Engine, engine::general_purpose};
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::Sha256;
+use shared::{delimited_spans, wiki_link_target};
 use sysinfo::{Disks, System};
 
-use crate::types::{Config, DiskInfo, EncryptedFile, Journal, ProcessInfo};
-
-pub fn get_config() -> Result<Config, Error> {
-    let config = fs::read_to_string("conf.toml").map_err(|e| {
-        Error::new(
-            ErrorKind::NotFound,
-            format!("The system cannot find the path conf.toml, {}", e),
-        )
-    })?;
-
-    config.parse::<Config>()
-}
+use crate::{
+    error::AppError,
+    types::{DiskInfo, EncryptedFile, ProcessInfo},
+};
 
-pub fn write_to_md_file(records: Vec<Journal>, filename: &str) -> io::Result<()> {
-    let mut file = File::create(filename)?;
-
-    for journal in records {
-        let mut title = "Untitled".to_string();
-        let mut body = "".to_string();
-        if journal.title.is_some() {
-            title = journal.title.unwrap();
-        }
-        if journal.body.is_some() {
-            body = journal.body.unwrap();
-        }
-        writeln!(file, "# {}\n\n{}\n", title, body)?;
-    }
-
-    Ok(())
-}
+const NONCE_LENGTH: usize = 12;
 
 pub fn get_disk_details() -> Vec<DiskInfo> {
     let disks = Disks::new_with_refreshed_list();
@@ -96,7 +78,7 @@ pub fn encrypt_data(plaintext: &str, key: &[u8; 32]) -> Result<(String, String),
 
     // Encode ciphertext and nonce as base64 for storage
     let ciphertext_b64 = general_purpose::STANDARD.encode(&ciphertext);
-    let nonce_b64 = general_purpose::STANDARD.encode(&nonce);
+    let nonce_b64 = general_purpose::STANDARD.encode(nonce);
 
     Ok((ciphertext_b64, nonce_b64))
 }
@@ -105,17 +87,61 @@ pub fn decrypt_data(
     ciphertext_b64: &str,
     nonce_b64: &str,
     key: &[u8; 32],
-) -> Result<String, aes_gcm::Error> {
+) -> Result<String, AppError> {
     let cipher = Aes256Gcm::new_from_slice(key).unwrap();
 
     // Decode from base64
-    let ciphertext = general_purpose::STANDARD.decode(ciphertext_b64).unwrap();
-    let nonce = general_purpose::STANDARD.decode(nonce_b64).unwrap();
+    let ciphertext = general_purpose::STANDARD
+        .decode(ciphertext_b64)
+        .map_err(|err| AppError::CorruptData(format!("the content is not base64: {}", err)))?;
+    let nonce = general_purpose::STANDARD
+        .decode(nonce_b64)
+        .map_err(|err| AppError::CorruptData(format!("the nonce is not base64: {}", err)))?;
+    if nonce.len() != NONCE_LENGTH {
+        return Err(AppError::CorruptData(format!(
+            "the nonce is {} bytes long, not {}",
+            nonce.len(),
+            NONCE_LENGTH
+        )));
+    }
 
     let nonce = Nonce::from_slice(&nonce);
     let plaintext = cipher.decrypt(nonce, ciphertext.as_ref())?;
 
-    Ok(String::from_utf8(plaintext).unwrap())
+    String::from_utf8(plaintext)
+        .map_err(|err| AppError::CorruptData(format!("the content is not UTF-8: {}", err)))
+}
+
+/// Encrypts binary data, returning the nonce followed by the ciphertext.
+pub fn encrypt_bytes(data: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, aes_gcm::Error> {
+    let cipher = Aes256Gcm::new_from_slice(key).unwrap();
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, data)?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub fn decrypt_bytes(sealed: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, aes_gcm::Error> {
+    if sealed.len() < NONCE_LENGTH {
+        return Err(aes_gcm::Error);
+    }
+    let cipher = Aes256Gcm::new_from_slice(key).unwrap();
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+    cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+}
+
+/// Content address of a blob. Keyed with the storage secret so that equal files
+/// are deduplicated without the address revealing a plain hash of the content.
+pub fn blob_address(data: &[u8], key: &[u8; 32]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
 }
 
 pub fn get_key(secret: String) -> [u8; 32] {
@@ -152,14 +178,44 @@ pub fn write_to_file(
     Ok(())
 }
 
-pub fn list_files_in_a_dir(dir_path: &str, key: [u8; 32]) -> Result<Vec<Journal>, io::Error> {
+pub fn decrypt_file<T: DeserializeOwned>(file_path: &str, key: &[u8; 32]) -> Result<T, AppError> {
+    let encrypted = read_file(file_path)?;
+    let stringified = decrypt_data(&encrypted.content, &encrypted.nonce, key)?;
+    Ok(serde_json::from_str::<T>(&stringified)?)
+}
+
+/// Encrypts `value` as JSON into `<dir>/<id>.json`, creating `dir` if needed.
+pub fn write_encrypted<T: Serialize>(
+    dir: &Path,
+    id: &str,
+    value: &T,
+    key: &[u8; 32],
+) -> Result<(), AppError> {
+    fs::create_dir_all(dir)?;
+
+    let stringified = serde_json::to_string(value)?;
+    let (text, nonce) = encrypt_data(&stringified, key)?;
+
+    Ok(write_to_file(
+        dir.to_string_lossy().to_string(),
+        id.to_string(),
+        text,
+        nonce,
+    )?)
+}
+
+pub fn list_files_in_a_dir<T: DeserializeOwned>(
+    dir_path: &str,
+    key: [u8; 32],
+) -> Result<Vec<T>, AppError> {
     let path = Path::new(dir_path);
     let metadata = path.metadata()?;
     if !metadata.is_dir() {
         return Err(io::Error::new(
             io::ErrorKind::NotADirectory,
             format!("{} is not a directory", dir_path),
-        ));
+        )
+        .into());
     }
 
     let permissions = metadata.permissions();
@@ -167,7 +223,8 @@ pub fn list_files_in_a_dir(dir_path: &str, key: [u8; 32]) -> Result<Vec<Journal>
         return Err(io::Error::new(
             io::ErrorKind::PermissionDenied,
             format!("Directory {} is not readable", dir_path),
-        ));
+        )
+        .into());
     }
 
     let mut results = Vec::new();
@@ -176,17 +233,195 @@ pub fn list_files_in_a_dir(dir_path: &str, key: [u8; 32]) -> Result<Vec<Journal>
         let file_path = entry.path();
         if file_path.is_file() && file_path.extension().and_then(|s| s.to_str()) == Some("json") {
             let path_str = file_path.to_string_lossy().to_string();
-            let encrypted = read_file(&path_str)?;
-            match decrypt_data(&encrypted.content, &encrypted.nonce, &key) {
-                Ok(stringified) => {
-                    let parsed = serde_json::from_str::<Journal>(&stringified).map_err(|err| {
-                        io::Error::new(io::ErrorKind::InvalidData, err.to_string())
-                    })?;
-                    results.push(parsed);
-                }
-                Err(err) => return Err(io::Error::new(io::ErrorKind::Other, err.to_string())),
-            }
+            results.push(decrypt_file(&path_str, &key)?);
         }
     }
     Ok(results)
 }
+
+/// Replaces each `open ... close` token with the result of `replace`, which gets
+/// the trimmed text between the delimiters. Returning `None` keeps the token.
+fn replace_delimited(
+    text: &str,
+    open: &str,
+    close: &str,
+    replace: impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, end) in delimited_spans(text, open, close) {
+        result.push_str(&text[last..start]);
+        let inner = text[start + open.len()..end - close.len()].trim();
+        match replace(inner) {
+            Some(replacement) => result.push_str(&replacement),
+            None => result.push_str(&text[start..end]),
+        }
+        last = end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+fn delimited_names(text: &str, open: &str, close: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for (start, end) in delimited_spans(text, open, close) {
+        let name = text[start + open.len()..end - close.len()]
+            .trim()
+            .to_string();
+        if !name.is_empty() && !name.contains('\n') && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Returns the names of all `{{placeholder}}` occurrences in `text`, in order of
+/// first appearance and without duplicates.
+pub fn find_placeholders(text: &str) -> Vec<String> {
+    delimited_names(text, "{{", "}}")
+}
+
+/// Replaces every `{{placeholder}}` in `text` whose name is present in `values`.
+/// Unknown placeholders are left untouched.
+pub fn substitute_placeholders(text: &str, values: &HashMap<String, String>) -> String {
+    replace_delimited(text, "{{", "}}", |name| values.get(name).cloned())
+}
+
+/// Returns the targets of all `[[Title]]`, `[[id]]` or `[[Title|label]]` links
+/// in `body`.
+pub fn parse_wiki_links(body: &str) -> Vec<String> {
+    let mut targets: Vec<String> = vec![];
+    for text in delimited_names(body, "[[", "]]") {
+        let target = wiki_link_target(&text);
+        if !target.is_empty() && !targets.iter().any(|known| known == target) {
+            targets.push(target.to_string());
+        }
+    }
+    targets
+}
+
+/// Points every `[[old_title]]` link (compared case-insensitively) at
+/// `new_title`, keeping the label of `[[old_title|label]]` links.
+pub fn rename_wiki_links(body: &str, old_title: &str, new_title: &str) -> String {
+    let old_title = old_title.trim().to_lowercase();
+    replace_delimited(body, "[[", "]]", |text| {
+        (wiki_link_target(text).to_lowercase() == old_title).then(|| match text.split_once('|') {
+            Some((_, label)) => format!("[[{}|{}]]", new_title.trim(), label.trim()),
+            None => format!("[[{}]]", new_title.trim()),
+        })
+    })
+}
+
+/// Values for the placeholders every template can use without prompting.
+pub fn builtin_placeholders() -> HashMap<String, String> {
+    let now = Local::now();
+    HashMap::from([
+        ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+        ("time".to_string(), now.format("%H:%M").to_string()),
+        ("weekday".to_string(), now.format("%A").to_string()),
+        ("user".to_string(), whoami::username()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Records, test_vault};
+
+    const KEY: &[u8; 32] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn decrypts_what_it_encrypts() {
+        let (content, nonce) = encrypt_data("Dear diary", KEY).unwrap();
+        assert_eq!(decrypt_data(&content, &nonce, KEY).unwrap(), "Dear diary");
+        assert!(matches!(
+            decrypt_data(&content, &nonce, b"fedcba9876543210fedcba9876543210"),
+            Err(AppError::Crypto(_))
+        ));
+    }
+
+    #[test]
+    fn reports_corrupt_data() {
+        let (content, nonce) = encrypt_data("Dear diary", KEY).unwrap();
+        let short_nonce = general_purpose::STANDARD.encode([0; 4]);
+        let (not_utf8, not_utf8_nonce) = {
+            let cipher = Aes256Gcm::new_from_slice(KEY).unwrap();
+            let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+            let ciphertext = cipher.encrypt(&nonce, [0xff, 0xfe].as_ref()).unwrap();
+            (
+                general_purpose::STANDARD.encode(ciphertext),
+                general_purpose::STANDARD.encode(nonce),
+            )
+        };
+
+        for (content, nonce) in [
+            ("not base64!", nonce.as_str()),
+            (content.as_str(), "not base64!"),
+            (content.as_str(), short_nonce.as_str()),
+            (not_utf8.as_str(), not_utf8_nonce.as_str()),
+        ] {
+            assert!(matches!(
+                decrypt_data(content, nonce, KEY),
+                Err(AppError::CorruptData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn finds_and_substitutes_placeholders() {
+        let text = "{{ date }} {{name}}\n{{date}} {{}} {{two\nlines}} {{unknown}} {{open";
+        assert_eq!(find_placeholders(text), ["date", "name", "unknown"]);
+
+        let values = HashMap::from([
+            ("date".to_string(), "2024-03-05".to_string()),
+            ("name".to_string(), "{{date}}".to_string()),
+        ]);
+        assert_eq!(
+            substitute_placeholders(text, &values),
+            "2024-03-05 {{date}}\n2024-03-05 {{}} {{two\nlines}} {{unknown}} {{open"
+        );
+        assert_eq!(substitute_placeholders(r"\{{date}}", &values), r"\{{date}}");
+    }
+
+    #[test]
+    fn parses_wiki_links() {
+        assert_eq!(
+            parse_wiki_links("[[Paris]], [[ paris ]], [[Paris|the capital]] and [[Lyon | there]]"),
+            ["Paris", "paris", "Lyon"]
+        );
+        assert_eq!(
+            parse_wiki_links(r"[[|label]] \[[escaped]] [[a [[b]] c]] [[open"),
+            ["b"]
+        );
+    }
+
+    #[test]
+    fn renames_wiki_links() {
+        let body = "[[Old]], [[ old ]], [[OLD|see here]], [[Older]], `\\[[Old]]`, [[Old";
+        assert_eq!(
+            rename_wiki_links(body, " Old ", "New title"),
+            "[[New title]], [[New title]], [[New title|see here]], [[Older]], `\\[[Old]]`, [[Old"
+        );
+    }
+
+    #[actix_web::test]
+    async fn reports_a_corrupt_entry_file() {
+        let (_dir, config) = test_vault(|_| {});
+        let id = "3f0c5a0e-6d2b-4b8e-9a57-0f6f7f6d1c2a".to_string();
+        fs::write(
+            config.storage().join(format!("{}.json", id)),
+            r#"{"content":"%%%","nonce":"%%%"}"#,
+        )
+        .unwrap();
+
+        let records = Records::new();
+        assert!(matches!(
+            records.find_by_id(&id, config.clone()).await,
+            Err(AppError::CorruptData(_))
+        ));
+        assert!(matches!(
+            records.find(&Default::default(), config).await,
+            Err(AppError::CorruptData(_))
+        ));
+    }
+}
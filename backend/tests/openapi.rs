@@ -0,0 +1,70 @@
+//! Keeps `backend/openapi.json` in step with the handlers.
+//!
+//! Regenerate the committed spec after changing a route or an API type with
+//! `UPDATE_OPENAPI=1 cargo test -p backend --test openapi`.
+
+use std::{collections::BTreeSet, env, fs, path::Path};
+
+use backend::openapi::ApiDoc;
+use utoipa::OpenApi;
+
+const HANDLER_SOURCES: [&str; 11] = [
+    include_str!("../src/handlers/mod.rs"),
+    include_str!("../src/handlers/admin.rs"),
+    include_str!("../src/handlers/attachments.rs"),
+    include_str!("../src/handlers/backup.rs"),
+    include_str!("../src/handlers/calendar.rs"),
+    include_str!("../src/handlers/drafts.rs"),
+    include_str!("../src/handlers/history.rs"),
+    include_str!("../src/handlers/import.rs"),
+    include_str!("../src/handlers/links.rs"),
+    include_str!("../src/handlers/templates.rs"),
+    include_str!("../src/handlers/tokens.rs"),
+];
+
+#[test]
+fn committed_spec_matches_the_handlers() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+    let generated = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+
+    if env::var_os("UPDATE_OPENAPI").is_some() {
+        fs::write(&path, generated).unwrap();
+        return;
+    }
+
+    let committed = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "openapi.json is out of date, regenerate it with \
+         `UPDATE_OPENAPI=1 cargo test -p backend --test openapi`"
+    );
+}
+
+#[test]
+fn every_route_is_documented() {
+    let routed: BTreeSet<(String, String)> = HANDLER_SOURCES
+        .iter()
+        .flat_map(|source| source.lines())
+        .filter_map(|line| {
+            let (method, rest) = line.trim().strip_prefix("#[")?.split_once("(\"")?;
+            let path = rest.strip_suffix("\")]")?;
+            matches!(method, "get" | "post" | "put" | "delete")
+                .then(|| (method.to_string(), path.to_string()))
+        })
+        .collect();
+
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    let documented: BTreeSet<(String, String)> = spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .map(move |method| (method.clone(), path.clone()))
+        })
+        .collect();
+
+    assert_eq!(routed, documented);
+}
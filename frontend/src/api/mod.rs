@@ -0,0 +1,504 @@
+use std::{collections::HashMap, fmt, fs, path::PathBuf, time::Duration};
+
+use dioxus::prelude::*;
+use reqwest::{
+    multipart::{Form, Part},
+    Method, RequestBuilder, Response, StatusCode,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    settings::{active_settings, Settings},
+    types::{
+        ApiToken, CreateUpdateRequest, Draft, DraftRequest, EntryFilter, ErrorCode, ErrorResponse,
+        ExportFormat, ExportOptions, Graph, GraphNode, ImportOptions, ImportReport, Journal,
+        RenderTemplateRequest, SystemInfo, Template, TemplateRequest,
+    },
+};
+
+const RETRY_DELAY_MS: u64 = 250;
+
+/// The last failed request, shown as a banner until the next successful one.
+pub static API_ERROR: GlobalSignal<Option<String>> = Signal::global(|| None);
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The backend could not be reached at all.
+    Unreachable(String),
+    Timeout(String),
+    /// The backend answered with a non-success status.
+    Status {
+        status: StatusCode,
+        code: ErrorCode,
+        message: String,
+        request_id: Option<String>,
+    },
+    /// The response body was not what the client expected.
+    Decode(String),
+    Io(std::io::Error),
+}
+
+impl ApiError {
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            ApiError::Status {
+                code: ErrorCode::NotFound,
+                ..
+            }
+        )
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Unreachable(url) => write!(f, "Backend unreachable at {}", url),
+            ApiError::Timeout(url) => write!(f, "Request to {} timed out", url),
+            ApiError::Status {
+                status,
+                code,
+                message,
+                request_id,
+            } => {
+                match code {
+                    _ if message.is_empty() => write!(f, "Backend returned {}", status)?,
+                    ErrorCode::Locked => write!(
+                        f,
+                        "{}. The journal may be in use by another instance of the app",
+                        message
+                    )?,
+                    ErrorCode::Unauthorized => write!(
+                        f,
+                        "{}. Set the backend's API token on the Settings screen",
+                        message
+                    )?,
+                    ErrorCode::Crypto => write!(
+                        f,
+                        "{}. Check that the secret in conf.toml has not been changed",
+                        message
+                    )?,
+                    ErrorCode::Io | ErrorCode::Internal => write!(f, "Backend error: {}", message)?,
+                    _ => write!(f, "{}", message)?,
+                }
+                match request_id {
+                    Some(id) if status.is_server_error() => write!(f, " (request {})", id),
+                    _ => Ok(()),
+                }
+            }
+            ApiError::Decode(err) => write!(f, "Unexpected response from backend: {}", err),
+            ApiError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(err: reqwest::Error) -> Self {
+        let url = err.url().map(|url| url.to_string()).unwrap_or_default();
+        if err.is_timeout() {
+            ApiError::Timeout(url)
+        } else if err.is_connect() {
+            ApiError::Unreachable(url)
+        } else if err.is_decode() {
+            ApiError::Decode(err.to_string())
+        } else if let Some(status) = err.status() {
+            ApiError::Status {
+                status,
+                code: ErrorCode::Internal,
+                message: err.to_string(),
+                request_id: None,
+            }
+        } else {
+            ApiError::Unreachable(url)
+        }
+    }
+}
+
+impl From<std::io::Error> for ApiError {
+    fn from(err: std::io::Error) -> Self {
+        ApiError::Io(err)
+    }
+}
+
+/// Records the outcome of a request for the error banner. Missing resources
+/// are left to the screens, which render their own "not found" state.
+fn report<T>(result: Result<T, ApiError>) -> Result<T, ApiError> {
+    match &result {
+        Ok(_) => *API_ERROR.write() = None,
+        Err(err) if err.is_not_found() => {}
+        Err(err) => *API_ERROR.write() = Some(err.to_string()),
+    }
+    result
+}
+
+pub struct ApiClient {
+    client: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    retries: u32,
+}
+
+impl ApiClient {
+    pub fn new(settings: &Settings) -> Result<Self, ApiError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_secs))
+            .build()?;
+
+        Ok(ApiClient {
+            client,
+            base_url: settings.backend_url.trim_end_matches('/').to_string(),
+            token: Some(settings.api_token.trim().to_string()).filter(|token| !token.is_empty()),
+            retries: settings.retries,
+        })
+    }
+
+    pub fn from_settings() -> Result<Self, ApiError> {
+        ApiClient::new(&active_settings())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(method, self.url(path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    pub fn put(&self, path: &str) -> RequestBuilder {
+        self.request(Method::PUT, path)
+    }
+
+    pub fn delete(&self, path: &str) -> RequestBuilder {
+        self.request(Method::DELETE, path)
+    }
+
+    /// Sends the request, retrying with a growing delay while the backend is
+    /// unreachable. Requests with streaming bodies are only attempted once.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, ApiError> {
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let retry = if attempt < self.retries {
+                request.try_clone()
+            } else {
+                None
+            };
+
+            match request.send().await {
+                Ok(response) => return check_status(response).await,
+                Err(err) if err.is_connect() => match retry {
+                    Some(next) => {
+                        attempt += 1;
+                        tokio::time::sleep(Duration::from_millis(RETRY_DELAY_MS * attempt as u64))
+                            .await;
+                        request = next;
+                    }
+                    None => return Err(err.into()),
+                },
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    pub async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ApiError> {
+        let response = self.send(request).await?;
+        response
+            .json::<T>()
+            .await
+            .map_err(|err| ApiError::Decode(err.to_string()))
+    }
+}
+
+async fn check_status(response: Response) -> Result<Response, ApiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    // Backends predating structured errors answer with a plain text body.
+    let text = response.text().await.unwrap_or_default();
+    let body = serde_json::from_str::<ErrorResponse>(&text).unwrap_or(ErrorResponse {
+        code: if status == StatusCode::NOT_FOUND {
+            ErrorCode::NotFound
+        } else {
+            ErrorCode::Internal
+        },
+        message: text,
+        request_id: None,
+    });
+    Err(ApiError::Status {
+        status,
+        code: body.code,
+        message: body.message,
+        request_id: body.request_id,
+    })
+}
+
+async fn fetch<T: DeserializeOwned>(path: &str) -> Result<T, ApiError> {
+    let client = ApiClient::from_settings()?;
+    client.json(client.get(path)).await
+}
+
+/// Checks that a backend answers at `settings` and accepts its token, without
+/// touching the saved settings.
+pub async fn check_connection(settings: &Settings) -> Result<ApiToken, ApiError> {
+    let client = ApiClient::new(settings)?;
+    client.json(client.get("/tokens/current")).await
+}
+
+pub async fn get_entries(filter: EntryFilter) -> Result<Vec<Journal>, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        client
+            .json::<Vec<Journal>>(client.get("/entries").query(&filter))
+            .await
+    };
+    report(result.await)
+}
+
+pub async fn create_journal(
+    body: String,
+    title: String,
+    tags: Vec<String>,
+) -> Result<String, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let payload = CreateUpdateRequest {
+            title: Some(title),
+            body: Some(body),
+            tags,
+        };
+        client
+            .json::<String>(client.post("/entry").json(&payload))
+            .await
+    };
+    report(result.await)
+}
+
+pub async fn get_journal(id: String) -> Result<Journal, ApiError> {
+    report(fetch(&format!("/entry/{}", id)).await)
+}
+
+pub async fn update_journal(
+    id: String,
+    body: String,
+    title: String,
+    tags: Vec<String>,
+) -> Result<Journal, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let payload = CreateUpdateRequest {
+            title: Some(title),
+            body: Some(body),
+            tags,
+        };
+        client
+            .json::<Journal>(client.put(&format!("/entry/{}", id)).json(&payload))
+            .await
+    };
+    report(result.await)
+}
+
+pub async fn delete_journal(id: String) -> Result<(), ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        client
+            .send(client.delete(&format!("/entry/{}", id)))
+            .await?;
+        Ok::<(), ApiError>(())
+    };
+    report(result.await)
+}
+
+pub async fn get_backlinks(id: String) -> Result<Vec<GraphNode>, ApiError> {
+    report(fetch(&format!("/entry/{}/backlinks", id)).await)
+}
+
+pub async fn get_graph() -> Result<Graph, ApiError> {
+    report(fetch("/graph").await)
+}
+
+/// A multipart form with one `file` part per path.
+fn file_form(paths: &[PathBuf]) -> Result<Form, ApiError> {
+    let mut form = Form::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or("attachment".to_string());
+        let bytes = fs::read(path)?;
+        form = form.part("file", Part::bytes(bytes).file_name(name));
+    }
+    Ok(form)
+}
+
+pub async fn upload_attachments(id: String, paths: Vec<PathBuf>) -> Result<(), ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let form = file_form(&paths)?;
+
+        client
+            .send(
+                client
+                    .post(&format!("/entry/{}/attachments", id))
+                    .multipart(form),
+            )
+            .await?;
+        Ok::<(), ApiError>(())
+    };
+    report(result.await)
+}
+
+/// Imports the entries of the files at `paths`, or on a dry run only
+/// reports what would be imported.
+pub async fn import_files(
+    paths: Vec<PathBuf>,
+    options: ImportOptions,
+) -> Result<ImportReport, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let request = client
+            .post("/import")
+            .query(&options)
+            .multipart(file_form(&paths)?);
+        client.json::<ImportReport>(request).await
+    };
+    report(result.await)
+}
+
+pub async fn download_attachment(id: String, attachment_id: String) -> Result<Vec<u8>, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let response = client
+            .send(client.get(&format!("/entry/{}/attachments/{}", id, attachment_id)))
+            .await?;
+        Ok::<Vec<u8>, ApiError>(response.bytes().await?.to_vec())
+    };
+    report(result.await)
+}
+
+/// A file served by the backend, with the name it suggests saving it under.
+pub struct Download {
+    pub file_name: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+fn suggested_file_name(response: &Response) -> Option<String> {
+    let disposition = response
+        .headers()
+        .get(reqwest::header::CONTENT_DISPOSITION)?
+        .to_str()
+        .ok()?;
+    disposition
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("filename="))
+        .map(|name| name.trim_matches('"').to_string())
+        .next()
+}
+
+pub async fn export(filter: EntryFilter, options: ExportOptions) -> Result<Download, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let request = client.get("/export").query(&filter).query(&options);
+        let response = client.send(request).await?;
+        let file_name = suggested_file_name(&response);
+        Ok::<Download, ApiError>(Download {
+            file_name,
+            bytes: response.bytes().await?.to_vec(),
+        })
+    };
+    report(result.await)
+}
+
+pub async fn get_system_info() -> Result<SystemInfo, ApiError> {
+    report(fetch("/sysinfo").await)
+}
+
+pub async fn get_templates() -> Result<Vec<Template>, ApiError> {
+    report(fetch("/templates").await)
+}
+
+pub async fn create_template(
+    name: String,
+    title: String,
+    body: String,
+    tags: Vec<String>,
+) -> Result<Template, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let payload = TemplateRequest {
+            name,
+            title: Some(title),
+            body: Some(body),
+            tags,
+        };
+        client
+            .json::<Template>(client.post("/templates").json(&payload))
+            .await
+    };
+    report(result.await)
+}
+
+pub async fn render_template(
+    id: String,
+    variables: HashMap<String, String>,
+) -> Result<CreateUpdateRequest, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let payload = RenderTemplateRequest { variables };
+        client
+            .json::<CreateUpdateRequest>(
+                client
+                    .post(&format!("/templates/{}/render", id))
+                    .json(&payload),
+            )
+            .await
+    };
+    report(result.await)
+}
+
+pub async fn get_drafts() -> Result<Vec<Draft>, ApiError> {
+    report(fetch("/drafts").await)
+}
+
+pub async fn get_draft(id: String) -> Result<Draft, ApiError> {
+    report(fetch(&format!("/drafts/{}", id)).await)
+}
+
+/// Saves the draft, creating it first when `id` is `None`. Returns the stored draft.
+pub async fn save_draft(id: Option<String>, payload: DraftRequest) -> Result<Draft, ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        let request = match id {
+            Some(id) => client.put(&format!("/drafts/{}", id)),
+            None => client.post("/drafts"),
+        };
+        client.json::<Draft>(request.json(&payload)).await
+    };
+    report(result.await)
+}
+
+pub async fn delete_draft(id: String) -> Result<(), ApiError> {
+    let result = async {
+        let client = ApiClient::from_settings()?;
+        client
+            .send(client.delete(&format!("/drafts/{}", id)))
+            .await?;
+        Ok::<(), ApiError>(())
+    };
+    report(result.await)
+}
@@ -1,28 +1,78 @@
+use std::fs;
+
 use dioxus::prelude::*;
+use rfd::FileDialog;
 
 use crate::{
-    types::{JournalProps, Screen},
-    utils::delete_journal,
-    CURRENT_SCREEN,
+    api::{delete_journal, download_attachment, API_ERROR},
+    types::{BodySegment, Draft, GraphEdge, GraphNode, Journal},
+    utils::{link_wiki_references, markdown_to_html, split_wiki_links},
+    Route,
 };
 const DELETE: Asset = asset!("/assets/delete.png");
 
 #[component]
-pub fn JournalComponent(journal: JournalProps) -> Element {
+pub fn JournalComponent(
+    journal: Journal,
+    links: Vec<GraphEdge>,
+    backlinks: Vec<GraphNode>,
+    on_delete: EventHandler<String>,
+) -> Element {
     let title = journal.title.unwrap_or("Untitled".to_string());
     let body = journal.body.unwrap_or("".to_string());
     let tags = journal.tags;
+    let attachments = journal.attachments;
+    let anchor = journal.id.clone();
+    let segments = split_wiki_links(&body);
+    let rendered = markdown_to_html(&link_wiki_references(&body, &links));
+    let mut show_raw = use_signal(|| false);
+    let backlinks = backlinks
+        .into_iter()
+        .map(|node| (node.id, node.title.unwrap_or("Untitled".to_string())))
+        .collect::<Vec<(String, String)>>();
+
+    let navigator = use_navigator();
+    let detail_id = journal.id.clone();
 
     let handle_delete = move |_ev| {
         let id = journal.id.clone();
         spawn(async move {
-            delete_journal(id.clone()).await;
-            *CURRENT_SCREEN.write() = Screen::MainMenu;
+            if delete_journal(id.clone()).await.is_ok() {
+                on_delete.call(id);
+            }
+        });
+    };
+
+    // The rendered body is plain HTML, so its wiki links are `#<id>` anchors. Catch
+    // clicks on them in the page and route them like the raw view's links.
+    let container_id = serde_json::to_string(&anchor).unwrap_or_default();
+    use_effect(move || {
+        let mut clicks = document::eval(&format!(
+            r#"
+            document.getElementById({container_id})?.addEventListener("click", (event) => {{
+                const link = event.target.closest(".markdown a[href^='#']");
+                if (!link) return;
+                event.preventDefault();
+                dioxus.send(decodeURIComponent(link.getAttribute("href").slice(1)));
+            }});
+            "#
+        ));
+        spawn(async move {
+            while let Ok(id) = clicks.recv::<String>().await {
+                navigator.push(Route::EntryDetail { id });
+            }
+        });
+    });
+
+    let open_detail = move |_ev| {
+        navigator.push(Route::EntryDetail {
+            id: detail_id.clone(),
         });
     };
 
     rsx!(
         div {
+            id: "{anchor}",
             class: "journal-container",
             img {
                 style:"cursor: pointer;",
@@ -31,7 +81,11 @@ pub fn JournalComponent(journal: JournalProps) -> Element {
                 height: "24",
                 onclick: handle_delete
             }
-            h2 { style:"text-decoration:underline;", "{title}" }
+            h2 {
+                style:"text-decoration:underline; cursor: pointer;",
+                onclick: open_detail,
+                "{title}"
+            }
             div {
                 style:"display: flex; flex-direction: row; justify-content: center; align-items: center; gap: 4px; flex: 1",
                 h5 { "Tags : "}
@@ -42,7 +96,118 @@ pub fn JournalComponent(journal: JournalProps) -> Element {
                     }
                 }
             }
-            pre {"{body}"}
+            button {
+                class: "refresh-button",
+                onclick: move |_| show_raw.toggle(),
+                if show_raw() { "Rendered" } else { "Raw" }
+            }
+            if show_raw() {
+                pre {
+                    {segments.into_iter().map(|segment| match segment {
+                        BodySegment::Text(text) => rsx! { "{text}" },
+                        BodySegment::Link { target, label } => {
+                            match links.iter().find(|link| link.text == target) {
+                                Some(link) => rsx! {
+                                    Link {
+                                        class: "wiki-link",
+                                        to: Route::EntryDetail { id: link.target.clone() },
+                                        "{label}"
+                                    }
+                                },
+                                None => rsx! {
+                                    span {
+                                        class: "wiki-link-missing",
+                                        title: "No single entry matches this link",
+                                        "{label}"
+                                    }
+                                },
+                            }
+                        }
+                    })}
+                }
+            } else {
+                div {
+                    class: "markdown",
+                    dangerous_inner_html: "{rendered}"
+                }
+            }
+            if !attachments.is_empty() {
+                div {
+                    class: "backlinks",
+                    h5 { "Attachments:" }
+                    for (entry_id, attachment) in attachments.into_iter().map(|attachment| (anchor.clone(), attachment)) {
+                        span {
+                            key: "{attachment.id}",
+                            class: "wiki-link",
+                            onclick: move |_| {
+                                save_attachment(entry_id.clone(), attachment.id.clone(), attachment.name.clone())
+                            },
+                            "{attachment.name} ({attachment.size / 1024} KB)"
+                        }
+                    }
+                }
+            }
+            if !backlinks.is_empty() {
+                div {
+                    class: "backlinks",
+                    h5 { "Linked from:" }
+                    for (id, title) in backlinks {
+                        Link {
+                            key: "{id}",
+                            class: "wiki-link",
+                            to: Route::EntryDetail { id: id.clone() },
+                            "{title}"
+                        }
+                    }
+                }
+            }
         }
     )
 }
+
+#[component]
+pub fn DraftRow(draft: Draft, on_discard: EventHandler<String>) -> Element {
+    let navigator = use_navigator();
+    let title = draft
+        .title
+        .clone()
+        .filter(|title| !title.is_empty())
+        .unwrap_or("Untitled".to_string());
+    let resume_id = draft.id.clone();
+    let discard_id = draft.id.clone();
+
+    rsx!(
+        div {
+            class: "button-container",
+            span { "{title}" }
+            span { class: "draft-date", "{draft.updated_at}" }
+            button {
+                class: "export-button",
+                onclick: move |_| {
+                    navigator.push(Route::ResumeDraft {
+                        id: resume_id.clone(),
+                    });
+                },
+                "Resume"
+            }
+            button {
+                class: "cancel-button",
+                onclick: move |_| on_discard.call(discard_id.clone()),
+                "Discard"
+            }
+        }
+    )
+}
+
+fn save_attachment(id: String, attachment_id: String, name: String) {
+    spawn(async move {
+        let Ok(bytes) = download_attachment(id, attachment_id).await else {
+            return;
+        };
+        if let Some(path) = FileDialog::new().set_file_name(name).save_file() {
+            if let Err(err) = fs::write(path, bytes) {
+                *API_ERROR.write() = Some(format!("Failed to save attachment: {}", err));
+            }
+        }
+    });
+}
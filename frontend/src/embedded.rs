@@ -0,0 +1,54 @@
+use std::sync::Mutex;
+
+use backend::{config::get_or_create_config, server::EmbeddedServer};
+
+use crate::settings::{app_config_dir, app_data_dir, set_embedded_backend};
+
+const CONFIG_FILE: &str = "conf.toml";
+const STORAGE_DIR: &str = "journals";
+
+static SERVER: Mutex<Option<EmbeddedServer>> = Mutex::new(None);
+static STARTUP_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Starts the backend on a random local port, using the `conf.toml` in the
+/// app's config directory. The file is created on first start with a new
+/// secret and journals kept in the app's data directory.
+pub fn start_embedded_backend() {
+    let (Some(config_dir), Some(data_dir)) = (app_config_dir(), app_data_dir()) else {
+        set_startup_error("No configuration directory available".to_string());
+        return;
+    };
+
+    let path = config_dir.join(CONFIG_FILE);
+    let server = get_or_create_config(&path, &data_dir.join(STORAGE_DIR))
+        .and_then(|config| EmbeddedServer::start(path, config));
+
+    match server {
+        Ok(server) => {
+            set_embedded_backend(server.url(), server.token().to_string());
+            if let Ok(mut current) = SERVER.lock() {
+                *current = Some(server);
+            }
+        }
+        Err(err) => set_startup_error(err.to_string()),
+    }
+}
+
+pub fn stop_embedded_backend() {
+    let server = SERVER.lock().ok().and_then(|mut server| server.take());
+    if let Some(server) = server {
+        server.shutdown();
+    }
+}
+
+/// Why the embedded backend could not be started, if it failed.
+pub fn embedded_startup_error() -> Option<String> {
+    STARTUP_ERROR.lock().ok().and_then(|error| error.clone())
+}
+
+fn set_startup_error(err: String) {
+    eprintln!("Failed to start the embedded backend: {}", err);
+    if let Ok(mut error) = STARTUP_ERROR.lock() {
+        *error = Some(format!("Failed to start the embedded backend: {}", err));
+    }
+}
@@ -1,45 +1,88 @@
 use dioxus::prelude::*;
 
 use crate::{
-    pages::{Create, MainMenu, Sysinfo},
-    types::Screen,
+    api::API_ERROR,
+    pages::{Create, EditEntry, EntryDetail, MainMenu, ResumeDraft, Settings, Sysinfo},
 };
 
+pub mod api;
 pub mod components;
+#[cfg(feature = "embedded")]
+pub mod embedded;
 pub mod pages;
+pub mod settings;
 pub mod types;
 pub mod utils;
 
 const FAVICON: Asset = asset!("/assets/favicon.ico");
 const MAIN_CSS: Asset = asset!("/assets/main.css");
 
-pub static CURRENT_SCREEN: GlobalSignal<Screen> = Signal::global(|| Screen::MainMenu);
+#[derive(Routable, Clone, PartialEq, Debug)]
+pub enum Route {
+    #[route("/")]
+    MainMenu {},
+    #[route("/create")]
+    Create {},
+    #[route("/entry/:id")]
+    EntryDetail { id: String },
+    #[route("/entry/:id/edit")]
+    EditEntry { id: String },
+    #[route("/drafts/:id")]
+    ResumeDraft { id: String },
+    #[route("/sysinfo")]
+    Sysinfo {},
+    #[route("/settings")]
+    Settings {},
+}
 
 fn main() {
+    #[cfg(feature = "embedded")]
+    if settings::current_settings().embedded {
+        embedded::start_embedded_backend();
+    }
+
     dioxus::launch(App);
 }
 
 #[component]
 fn App() -> Element {
+    #[cfg(feature = "embedded")]
+    {
+        use dioxus::desktop::{
+            tao::event::{Event, WindowEvent},
+            use_wry_event_handler,
+        };
+
+        use_hook(|| {
+            if let Some(error) = embedded::embedded_startup_error() {
+                *API_ERROR.write() = Some(error);
+            }
+        });
+        use_wry_event_handler(|event, _| {
+            if let Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } = event
+            {
+                embedded::stop_embedded_backend();
+            }
+        });
+    }
+
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: MAIN_CSS }
-        match *CURRENT_SCREEN.read() {
-            Screen::MainMenu => {
-                rsx!{
-                    MainMenu {}
-                }
-            }
-            Screen::Create => {
-                rsx!{
-                    Create { }
-                }
-            }
-            Screen::SysInfo => {
-                rsx!{
-                    Sysinfo { }
+        if let Some(error) = API_ERROR.read().clone() {
+            div {
+                class: "error-banner",
+                span { "{error}" }
+                button {
+                    class: "cancel-button",
+                    onclick: move |_| *API_ERROR.write() = None,
+                    "Dismiss"
                 }
             }
         }
+        Router::<Route> {}
     }
 }
@@ -0,0 +1,146 @@
+use dioxus::prelude::*;
+
+use crate::{
+    api::{get_draft, get_graph, get_journal},
+    components::JournalComponent,
+    pages::{backlinks_of, JournalForm},
+    types::GraphEdge,
+    Route,
+};
+
+#[component]
+pub fn EntryDetail(id: String) -> Element {
+    let navigator = use_navigator();
+
+    let journal = use_resource(use_reactive!(
+        |id| async move { get_journal(id).await.ok() }
+    ));
+    let graph = use_resource(|| async move { get_graph().await.unwrap_or_default() });
+
+    let edit_id = id.clone();
+    let goto_edit_page = move |_evt| {
+        navigator.push(Route::EditEntry {
+            id: edit_id.clone(),
+        });
+    };
+
+    let goto_main_menu = move |_evt| {
+        navigator.push(Route::MainMenu {});
+    };
+
+    let graph = graph.read().clone().unwrap_or_default();
+    let links = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.source == id)
+        .cloned()
+        .collect::<Vec<GraphEdge>>();
+    let backlinks = backlinks_of(&graph, &id);
+
+    rsx! {
+        div {
+            class: "main-menu",
+            div {
+                class: "button-container",
+                button {
+                    class: "cancel-button",
+                    onclick: goto_main_menu,
+                    "Back"
+                }
+                button {
+                    class: "export-button",
+                    onclick: goto_edit_page,
+                    "Edit"
+                }
+            }
+            match journal.read().clone() {
+                Some(Some(entry)) => rsx! {
+                    JournalComponent {
+                        key: "{entry.id}",
+                        links,
+                        backlinks,
+                        on_delete: move |_| {
+                            navigator.push(Route::MainMenu {});
+                        },
+                        journal: entry,
+                    }
+                },
+                Some(None) => rsx! {
+                    div {
+                        h2 { ">__<" }
+                        h4 { "This journal could not be found" }
+                    }
+                },
+                None => rsx! {
+                    p { "Loading" }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+pub fn EditEntry(id: String) -> Element {
+    let navigator = use_navigator();
+
+    let journal = use_resource(use_reactive!(
+        |id| async move { get_journal(id).await.ok() }
+    ));
+
+    let goto_main_menu = move |_evt| {
+        navigator.push(Route::MainMenu {});
+    };
+
+    match journal.read().clone() {
+        Some(Some(entry)) => rsx! {
+            JournalForm { key: "{entry.id}", journal: Some(entry) }
+        },
+        Some(None) => rsx! {
+            div {
+                class: "main-menu",
+                h2 { ">__<" }
+                h4 { "This journal could not be found" }
+                button {
+                    class: "cancel-button",
+                    onclick: goto_main_menu,
+                    "Back"
+                }
+            }
+        },
+        None => rsx! {
+            p { "Loading" }
+        },
+    }
+}
+
+#[component]
+pub fn ResumeDraft(id: String) -> Element {
+    let navigator = use_navigator();
+
+    let draft = use_resource(use_reactive!(|id| async move { get_draft(id).await.ok() }));
+
+    let goto_main_menu = move |_evt| {
+        navigator.push(Route::MainMenu {});
+    };
+
+    match draft.read().clone() {
+        Some(Some(draft)) => rsx! {
+            JournalForm { key: "{draft.id}", draft: Some(draft) }
+        },
+        Some(None) => rsx! {
+            div {
+                class: "main-menu",
+                h2 { ">__<" }
+                h4 { "This draft could not be found" }
+                button {
+                    class: "cancel-button",
+                    onclick: goto_main_menu,
+                    "Back"
+                }
+            }
+        },
+        None => rsx! {
+            p { "Loading" }
+        },
+    }
+}
@@ -1,68 +1,152 @@
-use std::{fs::File, io::Write};
+use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
 
 use dioxus::prelude::*;
-use rfd::FileDialog;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult};
 
 use crate::{
-    components::JournalComponent,
-    types::{JournalProps, Screen, SystemInfo},
-    utils::{create_journal, export, remove_from_vec},
-    CURRENT_SCREEN,
+    api::{
+        create_journal, create_template, delete_draft, export, get_drafts, get_entries, get_graph,
+        get_system_info, get_templates, import_files, render_template, save_draft, update_journal,
+        upload_attachments, API_ERROR,
+    },
+    components::{DraftRow, JournalComponent},
+    types::{
+        Draft, DraftRequest, EntryFilter, ExportFormat, ExportOptions, Graph, GraphEdge, GraphNode,
+        ImportFormat, ImportOptions, Journal, Template,
+    },
+    utils::{markdown_to_html, remove_from_vec},
+    Route,
 };
 
+mod entry;
+mod settings;
+
+pub use entry::*;
+pub use settings::*;
+
 const REFRESH: Asset = asset!("/assets/refresh.png");
+const AUTOSAVE_INTERVAL_SECS: u64 = 3;
 
 #[component]
 pub fn MainMenu() -> Element {
     let mut query = use_signal(|| "".to_string());
+    let mut export_all = use_signal(|| false);
+    let mut export_format = use_signal(ExportFormat::default);
+    let mut group_by_tag = use_signal(|| false);
+    let mut import_format = use_signal(ImportFormat::default);
 
-    let entries = use_resource(move || async move {
-        let query = query.read().clone();
+    let filter = move || EntryFilter {
+        tag: Some(query.read().clone()).filter(|tag| !tag.is_empty()),
+        ..Default::default()
+    };
 
-        if query.is_empty() {
-            reqwest::get("http://127.0.0.1:7000/entries")
-                .await
-                .unwrap()
-                .json::<Vec<JournalProps>>()
-                .await
-                .unwrap()
-        } else {
-            reqwest::get(format!("http://127.0.0.1:7000/entries?tag={query}"))
-                .await
-                .unwrap()
-                .json::<Vec<JournalProps>>()
-                .await
-                .unwrap()
-        }
-    });
+    let entries =
+        use_resource(move || async move { get_entries(filter()).await.unwrap_or_default() });
 
-    let refresh = move |_evt| entries.clone().restart();
+    let graph = use_resource(|| async move { get_graph().await.unwrap_or_default() });
+    let mut drafts = use_resource(|| async move { get_drafts().await.unwrap_or_default() });
 
-    use_effect(move || {
-        let _ = CURRENT_SCREEN();
+    let discard_draft = move |id: String| {
+        spawn(async move {
+            if delete_draft(id).await.is_ok() {
+                drafts.restart();
+            }
+        });
+    };
+
+    let refresh = move |_evt| {
         entries.clone().restart();
-    });
+        graph.clone().restart();
+    };
+
+    let reload = move |_id: String| {
+        entries.clone().restart();
+        graph.clone().restart();
+    };
+
+    let navigator = use_navigator();
 
     let goto_create_page = move |_evt| {
-        *CURRENT_SCREEN.write() = Screen::Create;
+        navigator.push(Route::Create {});
     };
 
     let goto_sysinfo_page = move |_evt| {
-        *CURRENT_SCREEN.write() = Screen::SysInfo;
+        navigator.push(Route::Sysinfo {});
+    };
+
+    let goto_settings_page = move |_evt| {
+        navigator.push(Route::Settings {});
     };
 
     let export_as_file = move |_evt| {
         spawn(async move {
-            match export().await {
-                Ok(response) => {
-                    let bytes = response.bytes().await.expect("Failed to read bytes");
-                    if let Some(path) = FileDialog::new().set_file_name("journal.md").save_file() {
-                        let mut file = File::create(path).expect("Failed to create file");
-                        file.write_all(&bytes).expect("Failed to write to file");
-                    }
+            let filter = match export_all() {
+                true => EntryFilter::default(),
+                false => filter(),
+            };
+            let format = export_format();
+            let options = ExportOptions {
+                format,
+                group_by_tag: group_by_tag(),
+            };
+            let Ok(download) = export(filter, options).await else {
+                return;
+            };
+            let file_name = download
+                .file_name
+                .unwrap_or_else(|| format!("journal.{}", format.extension()));
+            let dialog = FileDialog::new()
+                .set_file_name(file_name)
+                .add_filter(format.label(), &[format.extension()]);
+            if let Some(path) = dialog.save_file() {
+                if let Err(err) = fs::write(path, download.bytes) {
+                    *API_ERROR.write() = Some(format!("Failed to save export: {}", err));
                 }
-                Err(err) => eprintln!("{}", err.to_string()),
+            }
+        });
+    };
+
+    let import_entries = move |_evt| {
+        spawn(async move {
+            let format = import_format();
+            let Some(paths) = FileDialog::new()
+                .add_filter(format.label(), format.extensions())
+                .pick_files()
+            else {
+                return;
+            };
+            let options = ImportOptions {
+                format,
+                dry_run: true,
+            };
+            let Ok(preview) = import_files(paths.clone(), options.clone()).await else {
+                return;
             };
+            let mut description = format!("Import {} entries?", preview.created.len());
+            for skipped in &preview.skipped {
+                let title = skipped.title.as_deref().unwrap_or("Untitled");
+                description.push_str(&format!("\nSkipping {}: {}", title, skipped.reason));
+            }
+            let confirmed = MessageDialog::new()
+                .set_title("Import")
+                .set_description(description)
+                .set_buttons(MessageButtons::OkCancel)
+                .show();
+            if confirmed == MessageDialogResult::Ok
+                && !preview.created.is_empty()
+                && import_files(
+                    paths,
+                    ImportOptions {
+                        dry_run: false,
+                        ..options
+                    },
+                )
+                .await
+                .is_ok()
+            {
+                entries.clone().restart();
+                graph.clone().restart();
+            }
         });
     };
 
@@ -94,10 +178,70 @@ pub fn MainMenu() -> Element {
                     }
                     button {
                         class:"export-button",
-                        disabled: entries.read().clone().unwrap_or_default().is_empty(),
+                        onclick: goto_settings_page,
+                        "Settings"
+                    }
+                    button {
+                        class:"export-button",
+                        disabled: !export_all() && entries.read().clone().unwrap_or_default().is_empty(),
                         onclick: export_as_file,
                         "Export"
                     }
+                    button {
+                        class:"export-button",
+                        onclick: import_entries,
+                        "Import"
+                    }
+                    select {
+                        class: "input-field",
+                        onchange: move |e| {
+                            let format = ImportFormat::ALL
+                                .into_iter()
+                                .find(|format| format.label() == e.value());
+                            import_format.set(format.unwrap_or_default());
+                        },
+                        for format in ImportFormat::ALL {
+                            option {
+                                value: format.label(),
+                                selected: format == import_format(),
+                                "{format.label()}"
+                            }
+                        }
+                    }
+                    select {
+                        class: "input-field",
+                        onchange: move |e| {
+                            let format = ExportFormat::ALL
+                                .into_iter()
+                                .find(|format| format.extension() == e.value());
+                            export_format.set(format.unwrap_or_default());
+                        },
+                        for format in ExportFormat::ALL {
+                            option {
+                                value: format.extension(),
+                                selected: format == export_format(),
+                                "{format.label()}"
+                            }
+                        }
+                    }
+                    if export_format() == ExportFormat::Zip {
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: group_by_tag(),
+                                onchange: move |e| group_by_tag.set(e.checked()),
+                            }
+                            " Folders by tag"
+                        }
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: export_all(),
+                            onchange: move |e| export_all.set(e.checked()),
+                        }
+                        " All entries"
+                    }
                     button {
                         class:"create-button",
                         onclick: goto_create_page,
@@ -106,6 +250,20 @@ pub fn MainMenu() -> Element {
                 }
             }
 
+            if !drafts.read().clone().unwrap_or_default().is_empty() {
+                div {
+                    class: "drafts",
+                    h3 { "Unfinished drafts" }
+                    for draft in drafts.read().clone().unwrap_or_default() {
+                        DraftRow {
+                            key: "{draft.id}",
+                            on_discard: move |id| discard_draft(id),
+                            draft,
+                        }
+                    }
+                }
+            }
+
             match entries.state().cloned() {
                 UseResourceState::Ready => {
                     rsx!{
@@ -124,7 +282,20 @@ pub fn MainMenu() -> Element {
                             }
                         }else{
                             for entry in entries.read().clone().unwrap_or_default() {
-                                JournalComponent {key: entry.id, journal: entry}
+                                JournalComponent {
+                                    key: "{entry.id}",
+                                    links: graph
+                                        .read()
+                                        .clone()
+                                        .unwrap_or_default()
+                                        .edges
+                                        .into_iter()
+                                        .filter(|edge| edge.source == entry.id)
+                                        .collect::<Vec<GraphEdge>>(),
+                                    backlinks: backlinks_of(&graph.read().clone().unwrap_or_default(), &entry.id),
+                                    on_delete: reload,
+                                    journal: entry,
+                                }
                             }
                         }
                     }
@@ -139,12 +310,182 @@ pub fn MainMenu() -> Element {
     }
 }
 
+pub(crate) fn backlinks_of(graph: &Graph, id: &str) -> Vec<GraphNode> {
+    graph
+        .nodes
+        .iter()
+        .filter(|node| {
+            node.id != id
+                && graph
+                    .edges
+                    .iter()
+                    .any(|edge| edge.source == node.id && edge.target == id)
+        })
+        .cloned()
+        .collect()
+}
+
 #[component]
 pub fn Create() -> Element {
-    let mut title = use_signal(|| "".to_string());
-    let mut body = use_signal(|| "".to_string());
+    rsx! {
+        JournalForm { journal: None }
+    }
+}
+
+/// The entry form behind both the Create and Edit screens. Passing `journal`
+/// pre-fills the form and saves changes to that entry instead of creating one.
+#[component]
+pub fn JournalForm(journal: Option<Journal>, draft: Option<Draft>) -> Element {
+    let editing = match &draft {
+        Some(draft) => draft.entry_id.clone(),
+        None => journal.as_ref().map(|journal| journal.id.clone()),
+    };
+    let is_editing = editing.is_some();
+    let cancel_target = match editing.clone() {
+        Some(id) => Route::EntryDetail { id },
+        None => Route::MainMenu {},
+    };
+    let mut title = use_signal(|| match &draft {
+        Some(draft) => draft.title.clone().unwrap_or_default(),
+        None => journal
+            .as_ref()
+            .and_then(|journal| journal.title.clone())
+            .unwrap_or_default(),
+    });
+    let mut body = use_signal(|| match &draft {
+        Some(draft) => draft.body.clone().unwrap_or_default(),
+        None => journal
+            .as_ref()
+            .and_then(|journal| journal.body.clone())
+            .unwrap_or_default(),
+    });
     let mut tag_input = use_signal(|| "".to_string());
-    let mut tags: Signal<Vec<String>> = use_signal(|| vec![]);
+    let mut tags: Signal<Vec<String>> = use_signal(|| match &draft {
+        Some(draft) => draft.tags.clone(),
+        None => journal
+            .as_ref()
+            .map(|journal| journal.tags.clone())
+            .unwrap_or_default(),
+    });
+    let navigator = use_navigator();
+
+    let mut draft_id = use_signal(|| draft.as_ref().map(|draft| draft.id.clone()));
+    let mut draft_status = use_signal(|| "".to_string());
+    let mut submit_error: Signal<Option<String>> = use_signal(|| None);
+    // Set while the entry is being saved, so autosave doesn't race it and leave
+    // a draft behind.
+    let mut submitting = use_signal(|| false);
+    let mut last_saved = use_signal(|| {
+        (
+            title.peek().clone(),
+            body.peek().clone(),
+            tags.peek().clone(),
+        )
+    });
+
+    let draft_entry_id = editing.clone();
+    use_future(move || {
+        let entry_id = draft_entry_id.clone();
+        async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(AUTOSAVE_INTERVAL_SECS)).await;
+
+                let current = (
+                    title.peek().clone(),
+                    body.peek().clone(),
+                    tags.peek().clone(),
+                );
+                if *submitting.peek() || current == *last_saved.peek() {
+                    continue;
+                }
+
+                let payload = DraftRequest {
+                    entry_id: entry_id.clone(),
+                    title: Some(current.0.clone()),
+                    body: Some(current.1.clone()),
+                    tags: current.2.clone(),
+                };
+                let id = draft_id.peek().clone();
+                match save_draft(id.clone(), payload).await {
+                    // Submitted while saving. The submit deletes the draft it
+                    // knows of, only one this save created is left to remove.
+                    Ok(saved) if *submitting.peek() => {
+                        if id.is_none() {
+                            let _ = delete_draft(saved.id).await;
+                        }
+                    }
+                    Err(_) if *submitting.peek() => {}
+                    Ok(saved) => {
+                        draft_id.set(Some(saved.id));
+                        last_saved.set(current);
+                        draft_status.set("Draft saved".to_string());
+                    }
+                    Err(err) => {
+                        draft_status.set(format!("Draft not saved: {}", err));
+                    }
+                }
+            }
+        }
+    });
+    let mut selected_template: Signal<Option<Template>> = use_signal(|| None);
+    let mut template_values: Signal<HashMap<String, String>> = use_signal(HashMap::new);
+    let mut template_name = use_signal(|| "".to_string());
+    let mut files: Signal<Vec<PathBuf>> = use_signal(|| vec![]);
+
+    let mut templates = use_resource(|| async move { get_templates().await.unwrap_or_default() });
+
+    let handle_select_template = move |evt: Event<FormData>| {
+        let id = evt.value();
+        let template = templates
+            .read()
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|template| template.id == id);
+        template_values.set(HashMap::new());
+        selected_template.set(template);
+    };
+
+    let handle_apply_template = move |_evt| {
+        if let Some(template) = selected_template.read().clone() {
+            let variables = template_values.read().clone();
+            spawn(async move {
+                match render_template(template.id, variables).await {
+                    Ok(rendered) => {
+                        title.set(rendered.title.unwrap_or_default());
+                        body.set(rendered.body.unwrap_or_default());
+                        let mut new_tags = tags.read().clone();
+                        for tag in rendered.tags {
+                            if !new_tags.contains(&tag) {
+                                new_tags.push(tag);
+                            }
+                        }
+                        tags.set(new_tags);
+                    }
+                    Err(err) => submit_error.set(Some(err.to_string())),
+                }
+            });
+        }
+    };
+
+    let handle_save_template = move |_evt| {
+        let name = template_name.read().clone().trim().to_string();
+        let new_title = title.read().clone();
+        let new_body = body.read().clone();
+        let new_tags = tags.read().clone();
+
+        if !name.is_empty() {
+            spawn(async move {
+                if create_template(name, new_title, new_body, new_tags)
+                    .await
+                    .is_ok()
+                {
+                    template_name.set("".to_string());
+                    templates.restart();
+                }
+            });
+        }
+    };
 
     let handle_add_tag = move |_evt| {
         let new_tag = tag_input.read().clone().trim().to_string();
@@ -159,28 +500,127 @@ pub fn Create() -> Element {
         *tags.write() = remove_from_vec(old_tags, remove_tag);
     };
 
+    let handle_pick_files = move |_evt| {
+        if let Some(picked) = FileDialog::new().pick_files() {
+            let mut new_files = files.read().clone();
+            for path in picked {
+                if !new_files.contains(&path) {
+                    new_files.push(path);
+                }
+            }
+            files.set(new_files);
+        }
+    };
+
     let handle_submit = move |_evt| {
         let new_title = title.read().clone();
         let new_body = body.read().clone();
         let new_tags = tags.read().clone();
+        let new_files = files.read().clone();
 
         if !new_title.is_empty() && !new_body.is_empty() {
+            let editing = editing.clone();
+            submitting.set(true);
             spawn(async move {
-                create_journal(new_body, new_title, new_tags).await;
-                *CURRENT_SCREEN.write() = Screen::MainMenu;
+                let saved = match editing.clone() {
+                    Some(id) => update_journal(id, new_body, new_title, new_tags)
+                        .await
+                        .map(|journal| journal.id),
+                    None => create_journal(new_body, new_title, new_tags).await,
+                };
+                let id = match saved {
+                    Ok(id) => id,
+                    Err(err) => {
+                        submit_error.set(Some(format!(
+                            "Could not save the journal, your draft has been kept. {}",
+                            err
+                        )));
+                        submitting.set(false);
+                        return;
+                    }
+                };
+
+                if !new_files.is_empty() {
+                    if let Err(err) = upload_attachments(id.clone(), new_files).await {
+                        submit_error.set(Some(format!(
+                            "The journal was saved but its attachments were not. {}",
+                            err
+                        )));
+                        submitting.set(false);
+                        return;
+                    }
+                }
+                if let Some(draft) = draft_id.peek().clone() {
+                    let _ = delete_draft(draft).await;
+                }
+                match editing {
+                    Some(id) => navigator.push(Route::EntryDetail { id }),
+                    None => navigator.push(Route::MainMenu {}),
+                };
             });
         }
     };
 
     let goto_main_menu = move |_evt| {
-        *CURRENT_SCREEN.write() = Screen::MainMenu;
+        let target = cancel_target.clone();
+        spawn(async move {
+            if let Some(draft) = draft_id.peek().clone() {
+                let _ = delete_draft(draft).await;
+            }
+            navigator.push(target);
+        });
     };
 
     rsx! {
         div {
             class: "main-menu",
             div{
-                h1 { "Create new Journal" }
+                if is_editing {
+                    h1 { "Edit Journal" }
+                } else {
+                    h1 { "Create new Journal" }
+                }
+                div {
+                    style: "display: flex; flex-direction: row; gap: 8px;",
+                    select {
+                        class: "input-field",
+                        onchange: handle_select_template,
+                        option { value: "", "Start from a template..." }
+                        for template in templates.read().clone().unwrap_or_default() {
+                            option {
+                                key: "{template.id}",
+                                value: "{template.id}",
+                                "{template.name}"
+                            }
+                        }
+                    }
+                    button {
+                        class: "export-button",
+                        disabled: selected_template.read().is_none(),
+                        onclick: handle_apply_template,
+                        "Use Template"
+                    }
+                }
+                if let Some(template) = selected_template.read().clone() {
+                    for (variable, name, value) in template.variables.into_iter().map(|variable| {
+                        let value = template_values.read().get(&variable).cloned().unwrap_or_default();
+                        (variable.clone(), variable, value)
+                    }) {
+                        div {
+                            key: "{variable}",
+                            style: "margin-top: 8px;",
+                            input {
+                                class: "input-field",
+                                value: "{value}",
+                                oninput: move |e| {
+                                    template_values.write().insert(name.clone(), e.value());
+                                },
+                                placeholder: "{variable}"
+                            }
+                        }
+                    }
+                }
+                br {}
                 div {
                     input {
                         class: "input-field",
@@ -191,6 +631,7 @@ pub fn Create() -> Element {
                 }
                 br {}
                 div {
+                    class: "editor",
                     textarea {
                         class: "input-field",
                         rows: "10",
@@ -199,6 +640,10 @@ pub fn Create() -> Element {
                         oninput: move |e| body.set(e.value()),
                         placeholder: "Enter Content here"
                     }
+                    div {
+                        class: "markdown markdown-preview",
+                        dangerous_inner_html: markdown_to_html(&body.read())
+                    }
                 }
                 br {}
                 div {
@@ -229,10 +674,52 @@ pub fn Create() -> Element {
                         }
                     }
                 }
+                br {}
+                div {
+                    span { "Attachments :" }
+                    div {
+                        for (path, name) in files.read().clone().into_iter().map(|path| {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                            (path, name)
+                        }) {
+                            span {
+                                class: "tag",
+                                onclick: move |_| files.write().retain(|file| *file != path),
+                                "{name}, "
+                            }
+                        }
+                    }
+                    br {}
+                    button {
+                        class: "export-button",
+                        onclick: handle_pick_files,
+                        "Attach Files"
+                    }
+                }
+                br {}
+                div {
+                    style: "display: flex; flex-direction: row; gap: 8px;",
+                    input {
+                        class: "input-field",
+                        value: template_name,
+                        oninput: move |e| template_name.set(e.value()),
+                        placeholder: "Template name"
+                    }
+                    button {
+                        class: "export-button",
+                        disabled: template_name.read().trim().is_empty(),
+                        onclick: handle_save_template,
+                        "Save as Template"
+                    }
+                }
+                if let Some(error) = submit_error.read().clone() {
+                    p { class: "error-message", "{error}" }
+                }
                 div {
                     class: "button-container-parent",
                     div {
                         class: "button-container",
+                        span { "{draft_status}" }
                         button {
                             class: "cancel-button",
                             onclick: goto_main_menu,
@@ -253,21 +740,14 @@ pub fn Create() -> Element {
 
 #[component]
 pub fn Sysinfo() -> Element {
-    let infos = use_resource(|| async move {
-        reqwest::get("http://127.0.0.1:7000/sysinfo")
-            .await
-            .unwrap()
-            .json::<SystemInfo>()
-            .await
-            .unwrap()
-    });
+    let infos = use_resource(|| async move { get_system_info().await.ok() });
 
     let mut disk_usage = use_signal(|| vec![]);
     let mut current_user = use_signal(|| "".to_string());
     let mut cpu_processes = use_signal(|| vec![]);
 
     use_effect(move || {
-        let info = infos.read().clone();
+        let info = infos.read().clone().flatten();
 
         if let Some(info) = info {
             let mut disk_result = vec![];
@@ -296,8 +776,10 @@ pub fn Sysinfo() -> Element {
 
     let refresh = move |_evt| infos.clone().restart();
 
+    let navigator = use_navigator();
+
     let goto_main_menu = move |_evt| {
-        *CURRENT_SCREEN.write() = Screen::MainMenu;
+        navigator.push(Route::MainMenu {});
     };
 
     rsx! {
@@ -0,0 +1,175 @@
+use dioxus::prelude::*;
+
+use crate::{
+    api::check_connection,
+    settings::{
+        current_settings, save_settings, with_embedded_backend, Settings as BackendSettings,
+    },
+    types::TokenScope,
+    Route,
+};
+
+#[component]
+pub fn Settings() -> Element {
+    let navigator = use_navigator();
+    let initial = current_settings();
+
+    let mut backend_url = use_signal(|| initial.backend_url.clone());
+    let mut timeout_secs = use_signal(|| initial.timeout_secs.to_string());
+    let mut retries = use_signal(|| initial.retries.to_string());
+    let mut api_token = use_signal(|| initial.api_token.clone());
+    let mut embedded = use_signal(|| initial.embedded);
+    let mut status: Signal<Option<String>> = use_signal(|| None);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+
+    let read_form = move || -> Result<BackendSettings, String> {
+        let url = backend_url.read().trim().to_string();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err("The backend URL must start with http:// or https://".to_string());
+        }
+        let timeout_secs = timeout_secs
+            .read()
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .ok_or("The timeout must be a positive number of seconds".to_string())?;
+        let retries = retries
+            .read()
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| "Retries must be a whole number".to_string())?;
+
+        Ok(BackendSettings {
+            backend_url: url,
+            timeout_secs,
+            retries,
+            embedded: embedded(),
+            api_token: api_token.read().trim().to_string(),
+        })
+    };
+
+    let test_connection = move |_evt| {
+        status.set(None);
+        match read_form() {
+            Ok(settings) => {
+                error.set(None);
+                let settings = with_embedded_backend(settings);
+                spawn(async move {
+                    match check_connection(&settings).await {
+                        Ok(token) => status.set(Some(format!(
+                            "Connected with token \"{}\" ({})",
+                            token.name,
+                            match token.scope {
+                                TokenScope::Read => "read-only",
+                                TokenScope::ReadWrite => "read-write",
+                            }
+                        ))),
+                        Err(err) => error.set(Some(err.to_string())),
+                    }
+                });
+            }
+            Err(err) => error.set(Some(err)),
+        }
+    };
+
+    let handle_save = move |_evt| {
+        status.set(None);
+        match read_form().and_then(|settings| save_settings(settings).map_err(|e| e.to_string())) {
+            Ok(_) => {
+                error.set(None);
+                navigator.push(Route::MainMenu {});
+            }
+            Err(err) => error.set(Some(err)),
+        }
+    };
+
+    let goto_main_menu = move |_evt| {
+        navigator.push(Route::MainMenu {});
+    };
+
+    rsx! {
+        div {
+            class: "main-menu",
+            div {
+                h1 { "Settings" }
+                div {
+                    input {
+                        r#type: "checkbox",
+                        checked: embedded(),
+                        onchange: move |e| embedded.set(e.checked()),
+                    }
+                    span { " Run the backend inside the app (applies after a restart)" }
+                }
+                br {}
+                div {
+                    span { "Backend URL :" }
+                    input {
+                        class: "input-field",
+                        disabled: embedded(),
+                        value: backend_url,
+                        oninput: move |e| backend_url.set(e.value()),
+                        placeholder: "http://127.0.0.1:7000"
+                    }
+                }
+                br {}
+                div {
+                    span { "API token :" }
+                    input {
+                        class: "input-field",
+                        r#type: "password",
+                        disabled: embedded(),
+                        value: api_token,
+                        oninput: move |e| api_token.set(e.value()),
+                        placeholder: "Contents of <storage>/auth/token"
+                    }
+                }
+                br {}
+                div {
+                    span { "Timeout (seconds) :" }
+                    input {
+                        class: "input-field",
+                        value: timeout_secs,
+                        oninput: move |e| timeout_secs.set(e.value()),
+                    }
+                }
+                br {}
+                div {
+                    span { "Retries :" }
+                    input {
+                        class: "input-field",
+                        value: retries,
+                        oninput: move |e| retries.set(e.value()),
+                    }
+                }
+                if let Some(error) = error.read().clone() {
+                    p { class: "error-message", "{error}" }
+                }
+                if let Some(status) = status.read().clone() {
+                    p { "{status}" }
+                }
+                div {
+                    class: "button-container-parent",
+                    div {
+                        class: "button-container",
+                        button {
+                            class: "cancel-button",
+                            onclick: goto_main_menu,
+                            "Cancel"
+                        }
+                        button {
+                            class: "export-button",
+                            onclick: test_connection,
+                            "Test connection"
+                        }
+                        button {
+                            class: "create-button",
+                            onclick: handle_save,
+                            "Save"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{OnceLock, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+use shared::write_private;
+
+const SETTINGS_FILE: &str = "settings.json";
+const APP_DIR: &str = "task-journal";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub backend_url: String,
+    pub timeout_secs: u64,
+    /// How many times a request is retried when the backend cannot be reached.
+    pub retries: u32,
+    /// Start the backend inside the app instead of connecting to `backend_url`.
+    pub embedded: bool,
+    /// Bearer token sent with every request to `backend_url`.
+    pub api_token: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            backend_url: "http://127.0.0.1:7000".to_string(),
+            timeout_secs: 10,
+            retries: 2,
+            embedded: true,
+            api_token: String::new(),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<RwLock<Settings>> = OnceLock::new();
+/// URL and session token of the embedded backend.
+static EMBEDDED_BACKEND: OnceLock<(String, String)> = OnceLock::new();
+
+fn settings_lock() -> &'static RwLock<Settings> {
+    SETTINGS.get_or_init(|| RwLock::new(load_settings()))
+}
+
+pub fn app_config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+pub fn app_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    app_config_dir().map(|dir| dir.join(SETTINGS_FILE))
+}
+
+/// Reads the settings file, falling back to the defaults when it is missing or invalid.
+pub fn load_settings() -> Settings {
+    settings_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Settings>(&content).ok())
+        .unwrap_or_default()
+}
+
+/// The settings currently in use by the API client.
+pub fn current_settings() -> Settings {
+    settings_lock()
+        .read()
+        .map(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// Points requests at the embedded backend for the rest of the session.
+pub fn set_embedded_backend(url: String, token: String) {
+    let _ = EMBEDDED_BACKEND.set((url, token));
+}
+
+/// The settings requests are sent with: pointing at the embedded backend
+/// when it is running, at the configured one otherwise.
+pub fn active_settings() -> Settings {
+    with_embedded_backend(current_settings())
+}
+
+/// Replaces the URL and token of `settings` with the embedded backend's when
+/// they select it and it is running.
+pub fn with_embedded_backend(settings: Settings) -> Settings {
+    match EMBEDDED_BACKEND.get() {
+        Some((url, token)) if settings.embedded => Settings {
+            backend_url: url.clone(),
+            api_token: token.clone(),
+            ..settings
+        },
+        _ => settings,
+    }
+}
+
+/// Writes `settings` to the settings file and applies them to subsequent requests.
+pub fn save_settings(settings: Settings) -> io::Result<()> {
+    let path = settings_path().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "No configuration directory available",
+        )
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    write_private(&path, content.as_bytes())?;
+
+    if let Ok(mut current) = settings_lock().write() {
+        *current = settings;
+    }
+    Ok(())
+}
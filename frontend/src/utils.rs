@@ -1,46 +1,23 @@
-use reqwest::{Error, Response};
-
-use crate::types::CreateJournalRequest;
-
-pub async fn create_journal(body: String, title: String, tags: Vec<String>) {
-    let client = reqwest::Client::new();
-    let payload = CreateJournalRequest { body, title, tags };
-
-    match client
-        .post("http://127.0.0.1:7000/entry")
-        .json(&payload)
-        .send()
-        .await
-    {
-        Ok(resp) => {
-            println!("Response: {:?}", resp);
-        }
-        Err(err) => eprintln!("Error: {:?}", err),
-    }
-}
-
-pub async fn delete_journal(id: String) {
-    let client = reqwest::Client::new();
-
-    match client
-        .delete(&format!("http://127.0.0.1:7000/entry/{}", id))
-        .send()
-        .await
-    {
-        Ok(resp) => {
-            println!("Response: {:?}", resp);
-        }
-        Err(err) => eprintln!("Error: {:?}", err),
-    }
-}
-
-pub async fn export() -> Result<Response, Error> {
-    let client = reqwest::Client::new();
-
-    match client.post("http://127.0.0.1:7000/export").send().await {
-        Ok(resp) => Ok(resp),
-        Err(err) => Err(err),
-    }
+use crate::types::{BodySegment, GraphEdge};
+
+pub use shared::{markdown_to_html, split_wiki_links};
+
+/// Turns resolved `[[wiki links]]` into `#<id>` Markdown links, which the card
+/// routes to the target entry, and unresolved ones into plain text, so the body
+/// can go through the Markdown renderer.
+pub fn link_wiki_references(body: &str, links: &[GraphEdge]) -> String {
+    split_wiki_links(body)
+        .into_iter()
+        .map(|segment| match segment {
+            BodySegment::Text(text) => text,
+            BodySegment::Link { target, label } => {
+                match links.iter().find(|link| link.text == target) {
+                    Some(link) => format!("[{}](#{})", label.replace(['[', ']'], ""), link.target),
+                    None => label,
+                }
+            }
+        })
+        .collect()
 }
 
 pub fn remove_from_vec(tags: Vec<String>, remove_tag: String) -> Vec<String> {
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BackupRequest {
+    /// Encrypts the backup, independently of the storage secret. At least 8
+    /// characters.
+    pub passphrase: String,
+}
+
+/// What happens to entries, drafts and templates that are both in the vault
+/// and in the backup. Nothing that is only in the vault is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreMode {
+    /// Keep the vault's version.
+    #[default]
+    Merge,
+    /// Replace it with the backup's version.
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct RestoreOptions {
+    /// Merge when unset.
+    #[serde(default)]
+    pub mode: RestoreMode,
+}
+
+/// How many of one kind of record a restore wrote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RestoreCounts {
+    /// Not in the vault before.
+    pub created: usize,
+    /// In the vault before, replaced when overwriting.
+    pub replaced: usize,
+    /// In the vault before, kept when merging.
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RestoreReport {
+    pub mode: RestoreMode,
+    /// When the backup was made, as an RFC 3339 timestamp.
+    pub created_at: String,
+    pub entries: RestoreCounts,
+    pub drafts: RestoreCounts,
+    pub templates: RestoreCounts,
+    /// Attachment files written, shared ones counted once.
+    pub attachments: usize,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Unsubmitted content of the Create/Edit form, saved periodically so it
+/// survives the app closing.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Draft {
+    pub id: String,
+    /// The entry being edited, or `None` for a new entry.
+    pub entry_id: Option<String>,
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub tags: Vec<String>,
+    /// RFC 3339 timestamp of the last save.
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DraftRequest {
+    pub entry_id: Option<String>,
+    pub title: Option<String>,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
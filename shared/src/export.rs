@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// The file format `/export` renders entries in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Markdown with a YAML front matter block per entry.
+    #[default]
+    Markdown,
+    /// One JSON `Journal` record per line.
+    Jsonl,
+    /// A spreadsheet with one row per entry.
+    Csv,
+    /// A single styled HTML page.
+    Html,
+    /// A printable report with a cover page and table of contents.
+    Pdf,
+    /// A zip archive with one Markdown file per entry and its attachments.
+    Zip,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 6] = [
+        ExportFormat::Markdown,
+        ExportFormat::Jsonl,
+        ExportFormat::Csv,
+        ExportFormat::Html,
+        ExportFormat::Pdf,
+        ExportFormat::Zip,
+    ];
+
+    /// File extension of exports in this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Html => "html",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Zip => "zip",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Jsonl => "JSON lines",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::Zip => "Zip of Markdown files",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct ExportOptions {
+    /// Markdown when unset.
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Zip only: put every entry in a folder named after its first tag.
+    #[serde(default)]
+    pub group_by_tag: bool,
+}
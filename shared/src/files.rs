@@ -0,0 +1,23 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Writes `contents` to `path` so that only the current user can read it.
+/// Both sides keep secrets on disk: the backend its config and tokens, the
+/// app its settings with the API token.
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files.
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents)
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+/// Which entries `/entries` and `/export` return, and in what order. Every
+/// criterion that is set has to match.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct EntryFilter {
+    /// Only entries carrying this tag.
+    pub tag: Option<String>,
+    /// Comma separated tags, all of which an entry has to carry.
+    pub tags: Option<String>,
+    /// Comma separated entry ids.
+    pub ids: Option<String>,
+    /// Only entries created on or after this day (`YYYY-MM-DD`, local time)
+    /// or instant (RFC 3339). Entries saved before timestamps were recorded
+    /// never match a date range.
+    pub from: Option<String>,
+    /// Only entries created on or before this day or instant.
+    pub to: Option<String>,
+    /// Only tasks, entries tagged `todo` or `done`, in this state.
+    pub status: Option<TaskStatus>,
+    /// Storage order when unset.
+    pub order: Option<EntryOrder>,
+}
+
+/// The state of a task, as its `todo` or `done` tag gives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Todo,
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum EntryOrder {
+    /// Alphabetically by title.
+    Title,
+    /// Oldest first.
+    Oldest,
+    /// Newest first.
+    Newest,
+    /// Most recently updated first.
+    Updated,
+}
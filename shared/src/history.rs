@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// What a commit did to an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum EntryChange {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A commit of the storage repository that changed an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HistoryCommit {
+    /// The full commit hash.
+    pub id: String,
+    /// First line of the commit message.
+    pub summary: String,
+    /// An RFC 3339 timestamp.
+    pub time: String,
+    pub change: EntryChange,
+    /// The entry's title as of the commit, unset when it was deleted.
+    pub title: Option<String>,
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+/// The tool an import file comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Our Markdown export, or any Markdown file split on its headings.
+    #[default]
+    Markdown,
+    /// A jrnl plain text journal.
+    Jrnl,
+    /// A Day One JSON export, or the zip holding it.
+    DayOne,
+    /// A todo.txt task list.
+    TodoTxt,
+    /// The output of `task export`.
+    Taskwarrior,
+    /// Markdown notes, one per file, or a zip of a whole vault.
+    Obsidian,
+    /// The VTODOs of an iCalendar file, such as our calendar feed.
+    #[serde(rename = "icalendar")]
+    ICalendar,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 7] = [
+        ImportFormat::Markdown,
+        ImportFormat::Jrnl,
+        ImportFormat::DayOne,
+        ImportFormat::TodoTxt,
+        ImportFormat::Taskwarrior,
+        ImportFormat::Obsidian,
+        ImportFormat::ICalendar,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportFormat::Markdown => "Markdown",
+            ImportFormat::Jrnl => "jrnl",
+            ImportFormat::DayOne => "Day One",
+            ImportFormat::TodoTxt => "todo.txt",
+            ImportFormat::Taskwarrior => "Taskwarrior",
+            ImportFormat::Obsidian => "Obsidian",
+            ImportFormat::ICalendar => "iCalendar",
+        }
+    }
+
+    /// Extensions of the files this format is read from.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            ImportFormat::Markdown => &["md", "markdown"],
+            ImportFormat::Jrnl => &["txt", "jrnl"],
+            ImportFormat::DayOne => &["json", "zip"],
+            ImportFormat::TodoTxt => &["txt"],
+            ImportFormat::Taskwarrior => &["json"],
+            ImportFormat::Obsidian => &["md", "zip"],
+            ImportFormat::ICalendar => &["ics"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct ImportOptions {
+    /// Markdown when unset.
+    #[serde(default)]
+    pub format: ImportFormat,
+    /// Only report what would be imported, without storing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// An entry created by an import, or that would be on a dry run.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportedEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// An entry of the import that was left out, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SkippedEntry {
+    pub title: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: Vec<ImportedEntry>,
+    pub skipped: Vec<SkippedEntry>,
+}
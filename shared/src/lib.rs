@@ -0,0 +1,148 @@
+//! Request, response and error types exchanged between the backend and the
+//! desktop app. Both sides depend on this crate, so a schema change shows up
+//! as a compile error instead of a failed request.
+
+use serde::{Deserialize, Serialize};
+
+mod attachments;
+mod backup;
+mod drafts;
+mod export;
+#[cfg(feature = "fs")]
+mod files;
+mod filters;
+mod history;
+mod import;
+mod links;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod templates;
+mod text;
+mod tokens;
+
+pub use attachments::*;
+pub use backup::*;
+pub use drafts::*;
+pub use export::*;
+#[cfg(feature = "fs")]
+pub use files::*;
+pub use filters::*;
+pub use history::*;
+pub use import::*;
+pub use links::*;
+#[cfg(feature = "markdown")]
+pub use markdown::*;
+pub use templates::*;
+pub use text::*;
+pub use tokens::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Journal {
+    pub id: String,
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// RFC 3339 timestamp, missing on entries saved before it was recorded.
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// RFC 3339 timestamp of the last edit.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl Journal {
+    pub fn new(id: String, title: Option<String>, body: Option<String>, tags: Vec<String>) -> Self {
+        Journal {
+            id,
+            title,
+            body,
+            tags,
+            attachments: vec![],
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateUpdateRequest {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// What went wrong, independent of the human readable message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    /// The resource is in use, for instance by another instance of the app.
+    Locked,
+    BadRequest,
+    PayloadTooLarge,
+    /// No token, or one that is unknown or revoked, was sent.
+    Unauthorized,
+    /// The token is valid but its scope does not allow the request.
+    Forbidden,
+    /// Stored data decrypted fine but could not be parsed.
+    CorruptData,
+    /// Stored data could not be encrypted or decrypted, usually because the
+    /// secret changed.
+    Crypto,
+    Io,
+    #[default]
+    #[serde(other)]
+    Internal,
+}
+
+/// Body of every non-success response.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorResponse {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Matches the `X-Request-Id` header, for finding the request in the server log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Outcome of reloading the backend's config file.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReloadReport {
+    /// Settings that changed and are now in effect.
+    pub applied: Vec<String>,
+    /// Settings that changed but only take effect after a restart.
+    pub restart_required: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SystemInfo {
+    pub disk_usage: Vec<DiskInfo>,
+    pub current_user: String,
+    pub top_cpu_processes: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DiskInfo {
+    pub mount_point: String,
+    pub total_space: u64,
+    pub available_space: u64,
+    pub used_percentage: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_usage: f32,
+}
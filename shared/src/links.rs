@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+use crate::delimited_spans;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphNode {
+    pub id: String,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    /// The text between the brackets, as written in the source entry.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AmbiguousLink {
+    pub source: String,
+    pub text: String,
+    pub candidates: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UnresolvedLink {
+    pub source: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub ambiguous: Vec<AmbiguousLink>,
+    pub unresolved: Vec<UnresolvedLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodySegment {
+    Text(String),
+    /// A `[[target]]` or `[[target|label]]` link, with the target as its
+    /// label when it has none.
+    Link {
+        target: String,
+        label: String,
+    },
+}
+
+/// The target of the link text `text`, leaving out the label of a
+/// `[[Title|label]]` link.
+pub fn wiki_link_target(text: &str) -> &str {
+    text.split_once('|')
+        .map_or(text, |(target, _)| target)
+        .trim()
+}
+
+/// Splits a journal body into plain text and `[[wiki link]]` or
+/// `[[wiki link|label]]` segments, finding links the way the backend indexes
+/// them.
+pub fn split_wiki_links(body: &str) -> Vec<BodySegment> {
+    let mut segments = vec![];
+    let mut last = 0;
+    for (start, end) in delimited_spans(body, "[[", "]]") {
+        let text = body[start + 2..end - 2].trim();
+        let target = wiki_link_target(text);
+        if target.is_empty() || text.contains('\n') {
+            continue;
+        }
+        let label = match text.split_once('|') {
+            Some((_, label)) if !label.trim().is_empty() => label.trim(),
+            _ => target,
+        };
+        if start > last {
+            segments.push(BodySegment::Text(body[last..start].to_string()));
+        }
+        segments.push(BodySegment::Link {
+            target: target.to_string(),
+            label: label.to_string(),
+        });
+        last = end;
+    }
+    if last < body.len() {
+        segments.push(BodySegment::Text(body[last..].to_string()));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> BodySegment {
+        BodySegment::Text(text.to_string())
+    }
+
+    fn link(target: &str, label: &str) -> BodySegment {
+        BodySegment::Link {
+            target: target.to_string(),
+            label: label.to_string(),
+        }
+    }
+
+    #[test]
+    fn splits_wiki_links() {
+        assert_eq!(
+            split_wiki_links("See [[ Paris ]] and [[Lyon | there]]."),
+            [
+                text("See "),
+                link("Paris", "Paris"),
+                text(" and "),
+                link("Lyon", "there"),
+                text("."),
+            ]
+        );
+        assert_eq!(
+            split_wiki_links("[[Paris|]][[a [[b]] c]]"),
+            [
+                link("Paris", "Paris"),
+                text("[[a "),
+                link("b", "b"),
+                text(" c]]")
+            ]
+        );
+        assert_eq!(
+            split_wiki_links("[[|label]] \\[[escaped]] [[two\nlines]] [[open"),
+            [text("[[|label]] \\[[escaped]] [[two\nlines]] [[open")]
+        );
+        assert_eq!(split_wiki_links(""), []);
+    }
+}
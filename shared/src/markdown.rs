@@ -0,0 +1,30 @@
+use pulldown_cmark::{Options, Parser, html};
+
+/// Renders Markdown to HTML, with any raw HTML in the source sanitized away.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES;
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+
+    ammonia::Builder::default()
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_markdown_without_raw_html() {
+        assert_eq!(
+            markdown_to_html("# Day\n\n- [x] *done*<script>alert(1)</script>"),
+            "<h1>Day</h1>\n<ul>\n<li><input disabled=\"\" type=\"checkbox\" checked=\"\">\n<em>done</em></li>\n</ul>\n"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+/// Byte ranges of every `open ... close` token in `text`, delimiters included.
+/// Of nested openings the innermost counts, and an opening preceded by a
+/// backslash is text.
+pub fn delimited_spans(text: &str, open: &str, close: &str) -> Vec<(usize, usize)> {
+    let mut spans = vec![];
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find(open) {
+        let start = offset + start;
+        if text[..start].ends_with('\\') {
+            offset = start + open.len();
+            continue;
+        }
+        let inner = start + open.len();
+        let Some(end) = text[inner..].find(close) else {
+            break;
+        };
+        let end = inner + end;
+        let start = match text[inner..end].rfind(open) {
+            Some(nested) => inner + nested,
+            None => start,
+        };
+        spans.push((start, end + close.len()));
+        offset = end + close.len();
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_delimited_spans() {
+        let spans = |text| delimited_spans(text, "[[", "]]");
+        assert_eq!(spans("[[a]] and [[b]]"), [(0, 5), (10, 15)]);
+        assert_eq!(spans("[[a]] [[unterminated"), [(0, 5)]);
+        assert_eq!(spans("no closing ]] before [[ it"), []);
+        assert_eq!(spans("[[outer [[inner]] rest]]"), [(8, 17)]);
+        assert_eq!(spans(r"\[[escaped]] [[b]]"), [(13, 18)]);
+        assert_eq!(spans("[[]]"), [(0, 4)]);
+        assert_eq!(spans("é[[ü]]"), [(2, 8)]);
+    }
+}